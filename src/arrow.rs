@@ -0,0 +1,523 @@
+//! Reading records into Apache Arrow record batches, [`Reader`].
+//!
+//! Fields are converted to the type of their column as records are read, and
+//! appended to Arrow builders until a batch is full, so that the input is
+//! never held as strings. Whether an empty field is null depends on whether
+//! it was quoted, see [`Nulls`].
+
+pub mod options;
+
+use std::{
+    borrow::Borrow,
+    collections::VecDeque,
+    iter::{self, Peekable},
+    str::FromStr,
+    sync::Arc,
+};
+
+use arrow_array::{
+    builder::{BooleanBuilder, GenericStringBuilder, PrimitiveBuilder},
+    types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    },
+    ArrayRef, ArrowPrimitiveType, OffsetSizeTrait, RecordBatch, RecordBatchOptions,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+pub use options::{Nulls, Options};
+
+use crate::{
+    recorder,
+    records::{self, Fields},
+    token::Token,
+    tokenizer,
+    value::{Type, Value},
+    ParseError,
+};
+
+/// Reads records into batches of a schema, either given or inferred from
+/// the first records.
+pub struct Reader<I, R>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    iter: Peekable<I>,
+    recorder: R,
+    /// Number of records read so far, for the recorder's limits.
+    read: usize,
+    options: Options,
+    schema: SchemaRef,
+    columns: Vec<Box<dyn Column>>,
+    /// Rows read ahead to infer the schema, to be appended first.
+    ahead: VecDeque<Result<Row, ParseError>>,
+    /// Number of rows read so far, including those that failed.
+    rows: usize,
+    /// Number of rows in the builders.
+    len: usize,
+}
+
+/// Converting to an Arrow record batch reader.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = Result<Token, tokenizer::Error>> + Sized,
+{
+    /// # Errors
+    ///
+    /// See [`Self::csv_arrow_custom`].
+    #[inline]
+    fn csv_arrow(
+        self,
+        options: Options,
+    ) -> Result<Reader<Self, &'static recorder::Options>, Error> {
+        self.csv_arrow_custom(options, &recorder::Options::DEFAULT)
+    }
+
+    /// Reads the header, if any, and the records to infer the schema from
+    /// if none is given.
+    ///
+    /// # Errors
+    ///
+    /// If the header fails to parse, or the schema has a column of a type
+    /// that cannot be read.
+    #[inline]
+    fn csv_arrow_custom<R>(self, options: Options, recorder: R) -> Result<Reader<Self, R>, Error>
+    where
+        R: Borrow<recorder::Options>,
+    {
+        Reader::new(self, options, recorder)
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = Result<Token, tokenizer::Error>> {}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("row {}: expected {expected} fields, instead got {actual}", row + 1)]
+    Width {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("row {}, column {}: cannot read {value:?} as {data_type}", row + 1, column + 1)]
+    Convert {
+        row: usize,
+        column: usize,
+        data_type: DataType,
+        value: String,
+    },
+    #[error("row {}, column {}: null in the non-nullable column {name:?}", row + 1, column + 1)]
+    Null {
+        row: usize,
+        column: usize,
+        name: String,
+    },
+    #[error("column {}: cannot read values of type {data_type}", column + 1)]
+    Unsupported { column: usize, data_type: DataType },
+    #[error("{0}")]
+    Arrow(#[from] ArrowError),
+}
+
+/// The fields of a record, and which of them are bare, see
+/// [`Fields::push_bare`].
+#[derive(Clone, Debug, Default)]
+struct Row {
+    fields: Vec<String>,
+    bare: Vec<bool>,
+}
+
+/// A column being built, which converts a field in two steps so that a row
+/// is only appended once every field of it converts.
+trait Column {
+    /// Converts the field, or takes it as null, returning whether it is of
+    /// the type of the column.
+    fn stage(&mut self, field: &str, null: bool) -> bool;
+
+    /// Appends the field last staged.
+    fn commit(&mut self, field: &str);
+
+    fn finish(&mut self) -> ArrayRef;
+}
+
+struct Text<O>
+where
+    O: OffsetSizeTrait,
+{
+    builder: GenericStringBuilder<O>,
+    null: bool,
+}
+
+struct Boolean {
+    builder: BooleanBuilder,
+    staged: Option<bool>,
+}
+
+struct Primitive<T>
+where
+    T: ArrowPrimitiveType,
+{
+    builder: PrimitiveBuilder<T>,
+    staged: Option<T::Native>,
+}
+
+impl<I, R> Reader<I, R>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+    R: Borrow<recorder::Options>,
+{
+    fn new(iter: I, options: Options, recorder: R) -> Result<Self, Error> {
+        let mut reader = Self {
+            iter: iter.peekable(),
+            recorder,
+            read: 0,
+            options,
+            schema: Arc::new(Schema::empty()),
+            columns: Vec::new(),
+            ahead: VecDeque::new(),
+            rows: 0,
+            len: 0,
+        };
+        let header = if reader.options.header() {
+            reader.read_row().transpose()?.map(|row| row.fields)
+        } else {
+            None
+        };
+        reader.schema = match reader.options.schema() {
+            Some(schema) => Arc::clone(schema),
+            None => reader.infer(header),
+        };
+        reader.columns = reader
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(column, field)| {
+                new_column(field.data_type(), reader.options.batch_size()).ok_or_else(|| {
+                    Error::Unsupported {
+                        column,
+                        data_type: field.data_type().clone(),
+                    }
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(reader)
+    }
+
+    /// The schema of the batches.
+    #[inline]
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    /// Reads records ahead to infer a nullable column of each, typed as the
+    /// common type of its non-empty fields, see [`Value::detect`], and named
+    /// by the header or as `column_1`, `column_2` and so on.
+    fn infer(&mut self, header: Option<Vec<String>>) -> SchemaRef {
+        for _ in 0..self.options.infer_records() {
+            match self.read_row() {
+                Some(row) => self.ahead.push_back(row),
+                None => break,
+            }
+        }
+        let width = header.as_ref().map_or_else(
+            || {
+                self.ahead
+                    .iter()
+                    .find_map(|row| row.as_ref().ok())
+                    .map_or(0, |row| row.fields.len())
+            },
+            Vec::len,
+        );
+        let mut kinds = vec![Type::Empty; width];
+        for row in self.ahead.iter().flatten() {
+            for (kind, field) in kinds.iter_mut().zip(&row.fields) {
+                *kind = kind.merge(Value::detect(field).kind());
+            }
+        }
+        let names = header
+            .into_iter()
+            .flatten()
+            .chain((1..).map(|column| format!("column_{column}")));
+        let fields = iter::zip(names, kinds)
+            .map(|(name, kind)| {
+                let data_type = match kind {
+                    Type::Boolean => DataType::Boolean,
+                    Type::Integer => DataType::Int64,
+                    Type::Float => DataType::Float64,
+                    Type::Empty | Type::Text => DataType::Utf8,
+                };
+                Field::new(name, data_type, true)
+            })
+            .collect::<Vec<_>>();
+        Arc::new(Schema::new(fields))
+    }
+
+    fn read_row(&mut self) -> Option<Result<Row, ParseError>> {
+        let mut row = Row::default();
+        let result = records::next_into(
+            &mut self.iter,
+            self.recorder.borrow(),
+            &mut self.read,
+            &mut row,
+        )?;
+        Some(result.map(|_| row))
+    }
+
+    /// Appends a row to the builders, or none of it if a field fails to
+    /// convert.
+    fn append(&mut self, row: &Row) -> Result<(), Error> {
+        let at = self.rows;
+        self.rows += 1;
+        if row.fields.len() != self.columns.len() {
+            return Err(Error::Width {
+                row: at,
+                expected: self.columns.len(),
+                actual: row.fields.len(),
+            });
+        }
+        let fields = self.schema.fields().iter();
+        let values = row.fields.iter().zip(&row.bare);
+        for (column, (builder, (field, (value, bare)))) in
+            self.columns.iter_mut().zip(fields.zip(values)).enumerate()
+        {
+            let text = matches!(field.data_type(), DataType::Utf8 | DataType::LargeUtf8);
+            let empty = if text {
+                value.is_empty()
+            } else {
+                value.trim().is_empty()
+            };
+            let null = empty
+                && match self.options.nulls() {
+                    Nulls::Unquoted => *bare || !text,
+                    Nulls::Empty => true,
+                    Nulls::Never => false,
+                };
+            if null && !field.is_nullable() {
+                return Err(Error::Null {
+                    row: at,
+                    column,
+                    name: field.name().clone(),
+                });
+            }
+            if !builder.stage(value, null) {
+                return Err(Error::Convert {
+                    row: at,
+                    column,
+                    data_type: field.data_type().clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        for (builder, field) in self.columns.iter_mut().zip(&row.fields) {
+            builder.commit(field);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<RecordBatch, Error> {
+        let columns = self
+            .columns
+            .iter_mut()
+            .map(|column| column.finish())
+            .collect();
+        let options = RecordBatchOptions::new().with_row_count(Some(self.len));
+        self.len = 0;
+        Ok(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            columns,
+            &options,
+        )?)
+    }
+}
+
+impl<I, R> Iterator for Reader<I, R>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+    R: Borrow<recorder::Options>,
+{
+    type Item = Result<RecordBatch, Error>;
+
+    /// Reads the next batch, or reports the next record that cannot be
+    /// read, in which case the rows read before it are kept for the next
+    /// batch.
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.len < self.options.batch_size().max(1) {
+            let row = match self.ahead.pop_front() {
+                Some(row) => row,
+                None => match self.read_row() {
+                    Some(row) => row,
+                    None => break,
+                },
+            };
+            let result = match row {
+                Ok(row) => self.append(&row),
+                Err(cause) => {
+                    self.rows += 1;
+                    Err(cause.into())
+                }
+            };
+            if let Err(cause) = result {
+                return Some(Err(cause));
+            }
+        }
+        (self.len > 0).then(|| self.finish())
+    }
+}
+
+impl Fields for Row {
+    #[inline]
+    fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    #[inline]
+    fn push(&mut self, field: String) -> usize {
+        let len = field.len();
+        self.fields.push(field);
+        self.bare.push(false);
+        len
+    }
+
+    #[inline]
+    fn push_bare(&mut self) -> usize {
+        self.fields.push(String::new());
+        self.bare.push(true);
+        0
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        self.fields.pop();
+        self.bare.pop();
+    }
+}
+
+/// A column of the type, if it can be read.
+fn new_column(data_type: &DataType, capacity: usize) -> Option<Box<dyn Column>> {
+    Some(match data_type {
+        DataType::Utf8 => Box::new(Text::<i32>::with_capacity(capacity)),
+        DataType::LargeUtf8 => Box::new(Text::<i64>::with_capacity(capacity)),
+        DataType::Boolean => Box::new(Boolean {
+            builder: BooleanBuilder::with_capacity(capacity),
+            staged: None,
+        }),
+        DataType::Int8 => Box::new(Primitive::<Int8Type>::with_capacity(capacity)),
+        DataType::Int16 => Box::new(Primitive::<Int16Type>::with_capacity(capacity)),
+        DataType::Int32 => Box::new(Primitive::<Int32Type>::with_capacity(capacity)),
+        DataType::Int64 => Box::new(Primitive::<Int64Type>::with_capacity(capacity)),
+        DataType::UInt8 => Box::new(Primitive::<UInt8Type>::with_capacity(capacity)),
+        DataType::UInt16 => Box::new(Primitive::<UInt16Type>::with_capacity(capacity)),
+        DataType::UInt32 => Box::new(Primitive::<UInt32Type>::with_capacity(capacity)),
+        DataType::UInt64 => Box::new(Primitive::<UInt64Type>::with_capacity(capacity)),
+        DataType::Float32 => Box::new(Primitive::<Float32Type>::with_capacity(capacity)),
+        DataType::Float64 => Box::new(Primitive::<Float64Type>::with_capacity(capacity)),
+        _ => return None,
+    })
+}
+
+impl<O> Text<O>
+where
+    O: OffsetSizeTrait,
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            builder: GenericStringBuilder::with_capacity(capacity, capacity * 8),
+            null: false,
+        }
+    }
+}
+
+impl<O> Column for Text<O>
+where
+    O: OffsetSizeTrait,
+{
+    #[inline]
+    fn stage(&mut self, _field: &str, null: bool) -> bool {
+        self.null = null;
+        true
+    }
+
+    #[inline]
+    fn commit(&mut self, field: &str) {
+        self.builder.append_option((!self.null).then_some(field));
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        Arc::new(self.builder.finish())
+    }
+}
+
+impl Column for Boolean {
+    /// Reads `true` and `false` in any case, as [`Value::detect`] does.
+    #[inline]
+    fn stage(&mut self, field: &str, null: bool) -> bool {
+        self.staged = None;
+        if null {
+            return true;
+        }
+        match Value::detect(field) {
+            Value::Boolean(boolean) => {
+                self.staged = Some(boolean);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn commit(&mut self, _field: &str) {
+        self.builder.append_option(self.staged);
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        Arc::new(self.builder.finish())
+    }
+}
+
+impl<T> Primitive<T>
+where
+    T: ArrowPrimitiveType,
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            builder: PrimitiveBuilder::with_capacity(capacity),
+            staged: None,
+        }
+    }
+}
+
+impl<T> Column for Primitive<T>
+where
+    T: ArrowPrimitiveType,
+    T::Native: FromStr,
+{
+    /// Reads numbers ignoring surrounding whitespace.
+    #[inline]
+    fn stage(&mut self, field: &str, null: bool) -> bool {
+        self.staged = None;
+        if null {
+            return true;
+        }
+        match field.trim().parse() {
+            Ok(number) => {
+                self.staged = Some(number);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[inline]
+    fn commit(&mut self, _field: &str) {
+        self.builder.append_option(self.staged);
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        Arc::new(self.builder.finish())
+    }
+}
@@ -0,0 +1,225 @@
+//! Arrow reading, [`Reader`] options.
+//!
+//! [`Reader`]: super::Reader
+
+use arrow_schema::SchemaRef;
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_batch_size(self, batch_size: usize) -> Self;
+
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_infer_records(self, infer_records: usize) -> Self;
+
+    #[must_use]
+    fn with_nulls(self, nulls: Nulls) -> Self;
+
+    #[must_use]
+    fn with_schema(self, schema: Option<SchemaRef>) -> Self;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    batch_size: usize,
+    header: bool,
+    infer_records: usize,
+    nulls: Nulls,
+    schema: Option<SchemaRef>,
+}
+
+/// Which empty fields are null.
+///
+/// In columns other than text ones, fields of only whitespace are empty too,
+/// as when inferring the schema.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Nulls {
+    /// Empty fields that are not quoted, such as between two delimiters,
+    /// while quoted empty fields are empty strings in text columns and null
+    /// in others.
+    #[default]
+    Unquoted,
+    /// Every empty field.
+    Empty,
+    /// None, so that empty fields are empty strings in text columns and
+    /// errors in others.
+    Never,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        batch_size: 1024,
+        header: true,
+        infer_records: 1000,
+        nulls: Nulls::Unquoted,
+        schema: None,
+    };
+
+    /// Maximum number of rows of a batch.
+    #[inline]
+    #[must_use]
+    pub const fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    #[inline]
+    pub fn batch_size_mut(&mut self) -> &mut usize {
+        &mut self.batch_size
+    }
+
+    #[inline]
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size;
+    }
+
+    /// Whether the first record is a header naming the columns, which is
+    /// skipped if a schema is given.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    /// Number of records read ahead to infer a schema when none is given.
+    #[inline]
+    #[must_use]
+    pub const fn infer_records(&self) -> usize {
+        self.infer_records
+    }
+
+    #[inline]
+    pub fn infer_records_mut(&mut self) -> &mut usize {
+        &mut self.infer_records
+    }
+
+    #[inline]
+    pub fn set_infer_records(&mut self, infer_records: usize) {
+        self.infer_records = infer_records;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn nulls(&self) -> Nulls {
+        self.nulls
+    }
+
+    #[inline]
+    pub fn nulls_mut(&mut self) -> &mut Nulls {
+        &mut self.nulls
+    }
+
+    #[inline]
+    pub fn set_nulls(&mut self, nulls: Nulls) {
+        self.nulls = nulls;
+    }
+
+    /// The schema of the batches, inferred if `None`.
+    #[inline]
+    #[must_use]
+    pub const fn schema(&self) -> Option<&SchemaRef> {
+        self.schema.as_ref()
+    }
+
+    #[inline]
+    pub fn schema_mut(&mut self) -> &mut Option<SchemaRef> {
+        &mut self.schema
+    }
+
+    #[inline]
+    pub fn set_schema(&mut self, schema: Option<SchemaRef>) {
+        self.schema = schema;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_batch_size(self, batch_size: usize) -> Self {
+        self.set_batch_size(batch_size);
+        self
+    }
+
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_infer_records(self, infer_records: usize) -> Self {
+        self.set_infer_records(infer_records);
+        self
+    }
+
+    #[inline]
+    fn with_nulls(self, nulls: Nulls) -> Self {
+        self.set_nulls(nulls);
+        self
+    }
+
+    #[inline]
+    fn with_schema(self, schema: Option<SchemaRef>) -> Self {
+        self.set_schema(schema);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.set_batch_size(batch_size);
+        self
+    }
+
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_infer_records(mut self, infer_records: usize) -> Self {
+        self.set_infer_records(infer_records);
+        self
+    }
+
+    #[inline]
+    fn with_nulls(mut self, nulls: Nulls) -> Self {
+        self.set_nulls(nulls);
+        self
+    }
+
+    #[inline]
+    fn with_schema(mut self, schema: Option<SchemaRef>) -> Self {
+        self.set_schema(schema);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -0,0 +1,253 @@
+//! A record of bytes in one buffer, [`ByteRecord`].
+//!
+//! Like a [`StringRecord`], but fields are bytes that need not be UTF-8,
+//! see [`crate::bytes`]. Fields are only checked to be UTF-8 when asked, so
+//! that valid fields can be used even when others are not.
+
+use std::{iter::FusedIterator, ops, str};
+
+use crate::string_record::StringRecord;
+
+/// The fields of a record as bytes, stored contiguously.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ByteRecord {
+    idx: usize,
+    buf: Vec<u8>,
+    /// The end of every field in `buf`.
+    ends: Vec<usize>,
+}
+
+/// Iterator over the fields of a [`ByteRecord`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    record: &'a ByteRecord,
+    front: usize,
+    back: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("field {field} is not UTF-8: {cause}")]
+pub struct Utf8Error {
+    field: usize,
+    cause: str::Utf8Error,
+}
+
+impl ByteRecord {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            idx: 0,
+            buf: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// A record with room for `fields` fields of `bytes` bytes in total.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(bytes: usize, fields: usize) -> Self {
+        Self {
+            idx: 0,
+            buf: Vec::with_capacity(bytes),
+            ends: Vec::with_capacity(fields),
+        }
+    }
+
+    /// The byte offset of the start of the record.
+    #[inline]
+    #[must_use]
+    pub const fn idx(&self) -> usize {
+        self.idx
+    }
+
+    #[inline]
+    pub fn set_idx(&mut self, idx: usize) {
+        self.idx = idx;
+    }
+
+    /// Number of fields.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The field at `field`, counting from zero.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, field: usize) -> Option<&[u8]> {
+        let end = *self.ends.get(field)?;
+        let start = field.checked_sub(1).map_or(0, |prev| self.ends[prev]);
+        Some(&self.buf[start..end])
+    }
+
+    /// The field at `field` as UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// If the field is not UTF-8.
+    #[inline]
+    pub fn get_str(&self, field: usize) -> Option<Result<&str, Utf8Error>> {
+        let bytes = self.get(field)?;
+        Some(str::from_utf8(bytes).map_err(|cause| Utf8Error::new(field, cause)))
+    }
+
+    /// All fields, concatenated.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            record: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    /// Appends a field.
+    #[inline]
+    pub fn push_field(&mut self, field: &[u8]) {
+        self.buf.extend_from_slice(field);
+        self.ends.push(self.buf.len());
+    }
+
+    /// Removes all fields, keeping the allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.idx = 0;
+        self.buf.clear();
+        self.ends.clear();
+    }
+
+    /// Checks that every field is UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// At the first field that is not UTF-8.
+    pub fn validate(&self) -> Result<(), Utf8Error> {
+        for field in 0..self.len() {
+            self.get_str(field).transpose()?;
+        }
+        Ok(())
+    }
+
+    /// Copies the fields into a [`StringRecord`], the byte offset of the
+    /// record becoming its index.
+    ///
+    /// # Errors
+    ///
+    /// At the first field that is not UTF-8.
+    pub fn to_string_record(&self) -> Result<StringRecord, Utf8Error> {
+        let mut record = StringRecord::with_capacity(self.buf.len(), self.len());
+        record.set_idx(self.idx);
+        for field in 0..self.len() {
+            record.push_field(self.get_str(field).expect("within bounds")?);
+        }
+        Ok(record)
+    }
+
+    /// The buffer and field ends, to be filled by a reader.
+    pub(crate) fn parts_mut(&mut self) -> (&mut usize, &mut Vec<u8>, &mut Vec<usize>) {
+        (&mut self.idx, &mut self.buf, &mut self.ends)
+    }
+}
+
+impl Utf8Error {
+    #[inline]
+    #[must_use]
+    pub const fn new(field: usize, cause: str::Utf8Error) -> Self {
+        Self { field, cause }
+    }
+
+    /// The field that is not UTF-8, counting from zero.
+    #[inline]
+    #[must_use]
+    pub const fn field(&self) -> usize {
+        self.field
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn cause(&self) -> &str::Utf8Error {
+        &self.cause
+    }
+}
+
+impl From<&StringRecord> for ByteRecord {
+    fn from(value: &StringRecord) -> Self {
+        let mut record = Self::with_capacity(value.as_str().len(), value.len());
+        record.set_idx(value.idx());
+        for field in value {
+            record.push_field(field.as_bytes());
+        }
+        record
+    }
+}
+
+impl ops::Index<usize> for ByteRecord {
+    type Output = [u8];
+
+    /// # Panics
+    ///
+    /// If there is no such field.
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("field index out of bounds")
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteRecord {
+    type Item = &'a [u8];
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.record.get(self.front - 1)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.record.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
@@ -0,0 +1,250 @@
+//! Reading records of bytes that need not be UTF-8, [`Reader`].
+//!
+//! Every byte is read as one character, see [`to_char`], so that the
+//! [`Tokenizer`] handles bytes as it does characters, with the same quoting,
+//! limits and errors, and indices being byte offsets. Fields come back as
+//! the bytes they were, in a [`ByteRecord`].
+//!
+//! [`Tokenizer`]: crate::tokenizer::Tokenizer
+
+pub mod options;
+
+use std::{
+    borrow::Borrow,
+    io::{self, Read},
+    iter,
+};
+
+use crate::{
+    byte_record::ByteRecord,
+    reader::{self, Into as _},
+    recorder,
+    string_record::StringRecord,
+    tokenizer::{self, Warning},
+    ParseError,
+};
+pub use options::Options;
+
+const CHUNK: usize = 8 * 1024;
+
+/// The first of the private use characters standing for the bytes from
+/// `0x80` on.
+const HIGH: u32 = 0xF780;
+
+/// Bytes read as characters, see [`to_char`].
+type Decoded<I> = iter::Map<I, fn(u8) -> char>;
+
+/// Iterator over the bytes of a reader.
+///
+/// Iteration ends at the first I/O error, which is kept aside and can be
+/// retrieved with [`Bytes::take_error`].
+#[derive(Debug)]
+pub struct Bytes<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    error: Option<io::Error>,
+}
+
+/// Reads records of bytes into a [`ByteRecord`].
+#[derive(Clone, Debug)]
+pub struct Reader<I, R>
+where
+    I: Iterator<Item = u8>,
+{
+    inner: reader::Reader<Decoded<I>, tokenizer::Options, R>,
+    /// The record as read by `inner`, in characters.
+    chars: StringRecord,
+}
+
+/// Converting to a csv byte record reader.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = u8> + Sized,
+{
+    #[inline]
+    fn csv_byte_reader(self) -> Reader<Self, &'static recorder::Options> {
+        self.csv_byte_reader_custom(&Options::DEFAULT, &recorder::Options::DEFAULT)
+    }
+
+    #[inline]
+    fn csv_byte_reader_custom<R>(self, options: &Options, recorder: R) -> Reader<Self, R> {
+        let chars = self.map(to_char as fn(u8) -> char);
+        Reader {
+            inner: chars
+                .csv_reader_custom(options.to_tokenizer(), recorder)
+                .with_measure(|field| field.chars().count()),
+            chars: StringRecord::new(),
+        }
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = u8> {}
+
+/// The character a byte is read as: ASCII as is, and the bytes from `0x80`
+/// on as private use characters from U+F780 on, which no other byte is read
+/// as.
+#[inline]
+#[must_use]
+pub const fn to_char(byte: u8) -> char {
+    if byte.is_ascii() {
+        byte as char
+    } else {
+        match char::from_u32(HIGH + (byte - 0x80) as u32) {
+            Some(ch) => ch,
+            None => unreachable!(),
+        }
+    }
+}
+
+/// The byte a character was read from, see [`to_char`].
+///
+/// # Panics
+///
+/// If no byte is read as the character.
+#[inline]
+#[must_use]
+pub fn to_byte(ch: char) -> u8 {
+    if ch.is_ascii() {
+        ch as u8
+    } else {
+        u32::from(ch)
+            .checked_sub(HIGH)
+            .and_then(|offset| u8::try_from(offset + 0x80).ok())
+            .expect("read from a byte")
+    }
+}
+
+impl<R> Bytes<R> {
+    #[inline]
+    #[must_use]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            error: None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    #[inline]
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R> Iterator for Bytes<R>
+where
+    R: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.buf.len() {
+            if self.error.is_some() {
+                return None;
+            }
+            self.buf.resize(CHUNK, 0);
+            self.pos = 0;
+            let read = loop {
+                match self.inner.read(&mut self.buf) {
+                    Ok(read) => break read,
+                    Err(cause) if cause.kind() == io::ErrorKind::Interrupted => {}
+                    Err(cause) => {
+                        self.buf.clear();
+                        self.error = Some(cause);
+                        return None;
+                    }
+                }
+            };
+            self.buf.truncate(read);
+        }
+        let byte = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+}
+
+impl<I, R> Reader<I, R>
+where
+    I: Iterator<Item = u8>,
+    R: Borrow<recorder::Options>,
+{
+    /// Reads the next record into `record`, replacing its fields, and
+    /// returns whether there was one.
+    ///
+    /// # Errors
+    ///
+    /// If the record fails to parse, in which case `record` is cleared.
+    /// Indices of errors are byte offsets.
+    pub fn read_record(&mut self, record: &mut ByteRecord) -> Result<bool, ParseError> {
+        record.clear();
+        if !self.inner.read_record(&mut self.chars)? {
+            return Ok(false);
+        }
+        let (idx, buf, ends) = record.parts_mut();
+        *idx = self.chars.idx();
+        for field in &self.chars {
+            buf.extend(field.chars().map(to_byte));
+            ends.push(buf.len());
+        }
+        Ok(true)
+    }
+
+    /// Takes the warnings of the records read so far, which are kept until
+    /// taken.
+    #[inline]
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.take_warnings()
+    }
+
+    /// The delimiter declared by a `sep=` directive at the start of the
+    /// input, used instead of the configured one once read.
+    #[inline]
+    #[must_use]
+    pub fn declared_delimiter(&self) -> Option<u8> {
+        self.inner.declared_delimiter().map(to_byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::{options::Builder as _, ErrorKind};
+
+    fn read(input: &[u8], max_record_bytes: usize) -> Result<Vec<Vec<u8>>, ParseError> {
+        let recorder = recorder::Options::DEFAULT.with_max_record_bytes(Some(max_record_bytes));
+        let mut reader = input
+            .iter()
+            .copied()
+            .csv_byte_reader_custom(&Options::DEFAULT, &recorder);
+        let mut record = ByteRecord::new();
+        reader.read_record(&mut record)?;
+        Ok(record.iter().map(<[u8]>::to_vec).collect())
+    }
+
+    #[test]
+    fn counts_high_bytes_once_against_record_length() {
+        let fields = read(b"a,\xff\xfe", 3).unwrap();
+        assert_eq!(fields, [b"a".to_vec(), b"\xff\xfe".to_vec()]);
+
+        let cause = read(b"a,\xff\xfe", 2).unwrap_err();
+        let ParseError::Recorder(cause) = cause else {
+            panic!("unexpected error: {cause}");
+        };
+        assert_eq!(*cause.kind(), ErrorKind::RecordTooLong { max: 2 });
+    }
+}
@@ -0,0 +1,187 @@
+use crate::tokenizer::{
+    self,
+    options::{Builder as _, Error, LineBreak},
+};
+
+use super::to_char;
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+pub trait Builder: private_builder::Sealed {
+    /// # Errors
+    ///
+    /// See [`Options::set_delimiter`]
+    fn with_delimiter(self, delimiter: u8) -> Result<Self, Error>;
+
+    #[must_use]
+    fn with_line_break(self, line_break: LineBreak) -> Self;
+
+    /// # Errors
+    ///
+    /// See [`Options::set_quote`]
+    fn with_quote(self, quote: u8) -> Result<Self, Error>;
+
+    #[must_use]
+    fn with_tokenizer(self, tokenizer: tokenizer::Options) -> Self;
+}
+
+/// The dialect of bytes: the delimiter, quote and line break, along with
+/// the other settings of a [`tokenizer::Options`], whose own delimiter,
+/// quote and line break are ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    delimiter: u8,
+    line_break: LineBreak,
+    quote: u8,
+    tokenizer: tokenizer::Options,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        delimiter: b',',
+        line_break: LineBreak::Crlf,
+        quote: b'"',
+        tokenizer: tokenizer::Options::DEFAULT,
+    };
+
+    #[inline]
+    #[must_use]
+    pub const fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// # Errors
+    ///
+    /// If `delimiter` is a control code character other than a tab, or the
+    /// quote.
+    #[inline]
+    pub fn set_delimiter(&mut self, delimiter: u8) -> Result<(), Error> {
+        self.to_tokenizer().set_delimiter(to_char(delimiter))?;
+        self.delimiter = delimiter;
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn line_break(&self) -> LineBreak {
+        self.line_break
+    }
+
+    #[inline]
+    pub fn line_break_mut(&mut self) -> &mut LineBreak {
+        &mut self.line_break
+    }
+
+    #[inline]
+    pub fn set_line_break(&mut self, line_break: LineBreak) {
+        self.line_break = line_break;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn quote(&self) -> u8 {
+        self.quote
+    }
+
+    /// # Errors
+    ///
+    /// If `quote` is a control code character, or the delimiter.
+    #[inline]
+    pub fn set_quote(&mut self, quote: u8) -> Result<(), Error> {
+        self.to_tokenizer().set_quote(to_char(quote))?;
+        self.quote = quote;
+        Ok(())
+    }
+
+    /// The settings other than the delimiter, quote and line break.
+    #[inline]
+    #[must_use]
+    pub const fn tokenizer(&self) -> &tokenizer::Options {
+        &self.tokenizer
+    }
+
+    #[inline]
+    pub fn tokenizer_mut(&mut self) -> &mut tokenizer::Options {
+        &mut self.tokenizer
+    }
+
+    #[inline]
+    pub fn set_tokenizer(&mut self, tokenizer: tokenizer::Options) {
+        self.tokenizer = tokenizer;
+    }
+
+    /// The options tokenizing bytes read as characters, see
+    /// [`super::to_char`].
+    #[must_use]
+    pub fn to_tokenizer(&self) -> tokenizer::Options {
+        let mut options = self.tokenizer.clone().with_line_break(self.line_break);
+        options
+            .set_delimiter_and_quote(to_char(self.delimiter), to_char(self.quote))
+            .expect("checked when set");
+        options
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_delimiter(self, delimiter: u8) -> Result<Self, Error> {
+        self.set_delimiter(delimiter)?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn with_line_break(self, line_break: LineBreak) -> Self {
+        self.set_line_break(line_break);
+        self
+    }
+
+    #[inline]
+    fn with_quote(self, quote: u8) -> Result<Self, Error> {
+        self.set_quote(quote)?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn with_tokenizer(self, tokenizer: tokenizer::Options) -> Self {
+        self.set_tokenizer(tokenizer);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_delimiter(mut self, delimiter: u8) -> Result<Self, Error> {
+        self.set_delimiter(delimiter)?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn with_line_break(mut self, line_break: LineBreak) -> Self {
+        self.set_line_break(line_break);
+        self
+    }
+
+    #[inline]
+    fn with_quote(mut self, quote: u8) -> Result<Self, Error> {
+        self.set_quote(quote)?;
+        Ok(self)
+    }
+
+    #[inline]
+    fn with_tokenizer(mut self, tokenizer: tokenizer::Options) -> Self {
+        self.set_tokenizer(tokenizer);
+        self
+    }
+}
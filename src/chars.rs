@@ -0,0 +1,327 @@
+//! Decoding readers into characters, [`Chars`], in an [`Encoding`].
+//!
+//! The [`Tokenizer`] consumes an `Iterator<Item = char>`, which a
+//! [`std::io::Read`] source cannot provide without buffering the whole input
+//! as a [`String`] first.
+//!
+//! [`Tokenizer`]: crate::tokenizer::Tokenizer
+
+use std::io::{self, Read};
+
+const CHUNK: usize = 8 * 1024;
+
+/// The characters of Windows-1252 bytes `0x80` to `0x9F`, the others being
+/// those of Latin-1. Undefined bytes are read as the control characters of
+/// the same code, as browsers do.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// A character encoding of an input.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::Display, strum::EnumIs)]
+pub enum Encoding {
+    #[default]
+    #[strum(to_string = "UTF-8")]
+    Utf8,
+    #[strum(to_string = "UTF-16LE")]
+    Utf16Le,
+    #[strum(to_string = "UTF-16BE")]
+    Utf16Be,
+    #[strum(to_string = "ISO-8859-1")]
+    Latin1,
+    #[strum(to_string = "windows-1252")]
+    Windows1252,
+}
+
+/// Iterator over the characters of a reader.
+///
+/// Unless told otherwise, input is UTF-8, or UTF-16 if it starts with a
+/// UTF-16 byte order mark. A byte order mark is skipped rather than read as
+/// the first character.
+///
+/// Iteration ends at the first I/O or decoding error, which is kept aside
+/// and can be retrieved with [`Chars::take_error`].
+#[derive(Debug)]
+pub struct Chars<R> {
+    inner: R,
+    bytes: Vec<u8>,
+    text: String,
+    pos: usize,
+    error: Option<io::Error>,
+    encoding: Encoding,
+    /// Whether a byte order mark may still be found, and of which encoding
+    /// if not of any.
+    sniff: Option<Option<Encoding>>,
+    bom: bool,
+}
+
+impl Encoding {
+    /// The byte order mark, if the encoding has one.
+    #[inline]
+    #[must_use]
+    pub const fn bom(self) -> Option<&'static [u8]> {
+        match self {
+            Self::Utf8 => Some(b"\xEF\xBB\xBF"),
+            Self::Utf16Le => Some(b"\xFF\xFE"),
+            Self::Utf16Be => Some(b"\xFE\xFF"),
+            Self::Latin1 | Self::Windows1252 => None,
+        }
+    }
+
+    /// Number of bytes encoding a character.
+    #[inline]
+    #[must_use]
+    pub const fn encoded_len(self, ch: char) -> usize {
+        match self {
+            Self::Utf8 => ch.len_utf8(),
+            Self::Utf16Le | Self::Utf16Be => ch.len_utf16() * 2,
+            Self::Latin1 | Self::Windows1252 => 1,
+        }
+    }
+
+    /// Decodes the longest prefix of whole characters, returning the number
+    /// of bytes decoded.
+    fn decode(self, bytes: &[u8], text: &mut String) -> io::Result<usize> {
+        match self {
+            Self::Utf8 => {
+                let valid = match std::str::from_utf8(bytes) {
+                    Ok(valid) => valid.len(),
+                    Err(cause) if cause.error_len().is_none() => cause.valid_up_to(),
+                    Err(cause) => return Err(io::Error::new(io::ErrorKind::InvalidData, cause)),
+                };
+                text.push_str(std::str::from_utf8(&bytes[..valid]).expect("validated above"));
+                Ok(valid)
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let unit = |at: usize| {
+                    let pair = [bytes[at], bytes[at + 1]];
+                    if self.is_utf_16_le() {
+                        u32::from(u16::from_le_bytes(pair))
+                    } else {
+                        u32::from(u16::from_be_bytes(pair))
+                    }
+                };
+                let unpaired = || {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unpaired surrogate in {self}"),
+                    )
+                };
+                let mut at = 0;
+                while at + 2 <= bytes.len() {
+                    let high = unit(at);
+                    let code = match high {
+                        0xD800..=0xDBFF => {
+                            if at + 4 > bytes.len() {
+                                break;
+                            }
+                            let low = unit(at + 2);
+                            if !(0xDC00..=0xDFFF).contains(&low) {
+                                return Err(unpaired());
+                            }
+                            at += 4;
+                            0x1_0000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        }
+                        0xDC00..=0xDFFF => return Err(unpaired()),
+                        _ => {
+                            at += 2;
+                            high
+                        }
+                    };
+                    text.push(char::from_u32(code).expect("decoded from UTF-16"));
+                }
+                Ok(at)
+            }
+            Self::Latin1 => {
+                text.extend(bytes.iter().map(|byte| char::from(*byte)));
+                Ok(bytes.len())
+            }
+            Self::Windows1252 => {
+                text.extend(bytes.iter().map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252[usize::from(byte - 0x80)],
+                    _ => char::from(*byte),
+                }));
+                Ok(bytes.len())
+            }
+        }
+    }
+}
+
+impl<R> Chars<R>
+where
+    R: Read,
+{
+    /// Decodes UTF-8, or UTF-16 if the input starts with a UTF-16 byte
+    /// order mark.
+    #[inline]
+    #[must_use]
+    pub const fn new(inner: R) -> Self {
+        Self::decoding(inner, Encoding::Utf8, Some(None))
+    }
+
+    /// Decodes the given encoding, skipping its byte order mark, if any.
+    #[inline]
+    #[must_use]
+    pub const fn with_encoding(inner: R, encoding: Encoding) -> Self {
+        Self::decoding(inner, encoding, Some(Some(encoding)))
+    }
+
+    /// Decodes the given encoding from within an input, such as after
+    /// seeking, where there is no byte order mark to skip.
+    #[inline]
+    #[must_use]
+    pub const fn resume(inner: R, encoding: Encoding) -> Self {
+        Self::decoding(inner, encoding, None)
+    }
+
+    const fn decoding(inner: R, encoding: Encoding, sniff: Option<Option<Encoding>>) -> Self {
+        Self {
+            inner,
+            bytes: Vec::new(),
+            text: String::new(),
+            pos: 0,
+            error: None,
+            encoding,
+            sniff,
+            bom: false,
+        }
+    }
+
+    /// The encoding being decoded, which is only known for sure once a
+    /// character has been read.
+    #[inline]
+    #[must_use]
+    pub const fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The byte order mark that was skipped, if any.
+    #[inline]
+    #[must_use]
+    pub const fn bom(&self) -> Option<&'static [u8]> {
+        if self.bom {
+            self.encoding.bom()
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    #[inline]
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decodes the next chunk into `text`, returning `false` at the end.
+    fn fill(&mut self) -> io::Result<bool> {
+        loop {
+            let start = self.bytes.len();
+            self.bytes.resize(start + CHUNK, 0);
+            let read = loop {
+                match self.inner.read(&mut self.bytes[start..]) {
+                    Ok(read) => break read,
+                    Err(cause) if cause.kind() == io::ErrorKind::Interrupted => {}
+                    Err(cause) => {
+                        self.bytes.truncate(start);
+                        return Err(cause);
+                    }
+                }
+            };
+            self.bytes.truncate(start + read);
+            if !self.sniff_bom(read == 0) {
+                continue;
+            }
+            if read == 0 {
+                return if self.bytes.is_empty() {
+                    Ok(false)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "stream ended in the middle of a {} character",
+                            self.encoding
+                        ),
+                    ))
+                };
+            }
+
+            self.text.clear();
+            self.pos = 0;
+            let decoded = self.encoding.decode(&self.bytes, &mut self.text)?;
+            self.bytes.drain(..decoded);
+            if !self.text.is_empty() {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Looks for a byte order mark at the start of the input, skipping it
+    /// and switching to its encoding if found. Returns `false` while more
+    /// bytes are needed to tell.
+    fn sniff_bom(&mut self, end: bool) -> bool {
+        let Some(expected) = self.sniff else {
+            return true;
+        };
+        let candidates = match expected {
+            Some(encoding) => vec![encoding],
+            None => vec![Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be],
+        };
+        let mut undecided = false;
+        for encoding in candidates {
+            let Some(bom) = encoding.bom() else {
+                continue;
+            };
+            if self.bytes.starts_with(bom) {
+                self.bytes.drain(..bom.len());
+                self.encoding = encoding;
+                self.bom = true;
+                self.sniff = None;
+                return true;
+            }
+            undecided |= bom.starts_with(&self.bytes);
+        }
+        if undecided && !end {
+            return false;
+        }
+        self.sniff = None;
+        true
+    }
+}
+
+impl<R> Iterator for Chars<R>
+where
+    R: Read,
+{
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.text.len() {
+            if self.error.is_some() {
+                return None;
+            }
+            match self.fill() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(cause) => {
+                    self.error = Some(cause);
+                    return None;
+                }
+            }
+        }
+        let ch = self.text[self.pos..].chars().next()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+}
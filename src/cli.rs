@@ -0,0 +1,520 @@
+//! The `csv` command-line interface.
+
+pub mod diff;
+pub mod filter;
+pub mod from_json;
+pub mod index;
+pub mod join;
+pub mod patch;
+pub mod query;
+pub mod select;
+pub mod slice;
+pub mod sort;
+#[cfg(feature = "sqlite")]
+pub mod sql;
+pub mod stats;
+pub mod table;
+pub mod to_json;
+pub mod validate;
+
+use std::{
+    env,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, IsTerminal, Read, Write},
+    mem,
+    path::Path,
+    process::ExitCode,
+};
+
+use csv::{
+    chars::{Chars, Encoding},
+    diagnostic::{Diagnostic, Renderer, Source},
+    location::FieldLocator,
+    recorder::{self, options::Builder as _},
+    tokenizer::{
+        self,
+        options::{Builder as _, LineBreak, Quoting, Unterminated},
+    },
+    writer::Writer,
+    ParseError,
+};
+
+#[derive(Debug, clap::Parser)]
+#[command(
+    name = "csv",
+    version,
+    about = "Inspect, validate and convert CSV files"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Check that files parse as uniform CSV.
+    Validate(validate::Args),
+    /// Compare two files by key columns or row by row.
+    Diff(diff::Args),
+    /// Apply a patch made by `diff` to the old file.
+    Patch(patch::Args),
+    /// Keep records matching a predicate on their columns.
+    Filter(filter::Args),
+    /// Pick columns by number, range or header name.
+    Select(select::Args),
+    /// Join two files on key columns.
+    Join(join::Args),
+    /// Index the records of a file for `slice`.
+    Index(index::Args),
+    /// Read a range of records of an indexed file without scanning it.
+    Slice(slice::Args),
+    /// Sort records by key columns, spilling to disk when needed.
+    Sort(sort::Args),
+    /// Profile each column: counts, types, ranges and frequent values.
+    Stats(stats::Args),
+    /// Show the first records as an aligned table.
+    Table(table::Args),
+    /// Run a SQL-like query: select, filter, group, aggregate and sort.
+    Query(query::Args),
+    /// Convert CSV to JSON or JSON Lines.
+    ToJson(to_json::Args),
+    /// Convert a JSON array or JSON Lines of flat objects to CSV.
+    FromJson(from_json::Args),
+    /// Import files into and export query results from SQLite databases.
+    #[cfg(feature = "sqlite")]
+    Sql(sql::Args),
+}
+
+/// Dialect flags shared by every subcommand.
+#[derive(Clone, Debug, clap::Args)]
+pub struct Dialect {
+    /// Character separating fields.
+    #[arg(short, long, default_value_t = ',')]
+    delimiter: char,
+
+    /// Character enclosing escaped fields.
+    #[arg(short, long, default_value_t = '"')]
+    quote: char,
+
+    /// Accepted line breaks.
+    #[arg(short, long, value_enum, default_value_t = LineBreakArg::Any)]
+    line_break: LineBreakArg,
+
+    /// Allow control code characters in unquoted fields.
+    #[arg(long)]
+    allow_controls: bool,
+
+    /// Allow a delimiter at the end of a line, read as a trailing empty field.
+    #[arg(long)]
+    trailing_delimiter: bool,
+
+    /// Take quotes within unquoted fields literally, and append text after a
+    /// closing quote to the field.
+    #[arg(long)]
+    relaxed_quotes: bool,
+
+    /// Take quotes literally everywhere, so that fields end only at
+    /// delimiters and line breaks; fields needing quotes cannot be written.
+    #[arg(long, conflicts_with = "relaxed_quotes")]
+    no_quoting: bool,
+
+    /// Handling of a quoted field left open at the end of the input: report
+    /// an error, accept the rest of the input, or take the rest of the line.
+    #[arg(long, value_enum, default_value_t = UnterminatedArg::Strict)]
+    unterminated: UnterminatedArg,
+
+    /// Maximum number of characters of a field.
+    #[arg(long, value_name = "CHARS")]
+    max_field_len: Option<usize>,
+
+    /// Maximum number of line breaks within a quoted field.
+    #[arg(long, value_name = "COUNT")]
+    max_quoted_line_breaks: Option<usize>,
+
+    /// Maximum number of fields of a record.
+    #[arg(long, value_name = "COUNT")]
+    max_fields: Option<usize>,
+
+    /// Maximum number of bytes of the fields of a record.
+    #[arg(long, value_name = "BYTES")]
+    max_record_bytes: Option<usize>,
+
+    /// Maximum number of records, including the header.
+    #[arg(long, value_name = "COUNT")]
+    max_records: Option<usize>,
+
+    /// Encoding of the input; `auto` reads UTF-8, or UTF-16 after a UTF-16
+    /// byte order mark.
+    #[arg(long, value_enum, default_value_t = EncodingArg::Auto)]
+    encoding: EncodingArg,
+
+    /// Start CSV output with a UTF-8 byte order mark, as Excel expects.
+    #[arg(long)]
+    bom: bool,
+
+    /// Read a `sep=` line at the start of the input as a record rather than
+    /// as declaring the delimiter.
+    #[arg(long)]
+    no_sep_directive: bool,
+
+    /// Start CSV output with a `sep=` line declaring the delimiter, as Excel
+    /// reads.
+    #[arg(long)]
+    sep_directive: bool,
+
+    /// Coloring of diagnostics; `auto` colors them on a terminal unless
+    /// `NO_COLOR` is set.
+    #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum LineBreakArg {
+    Crlf,
+    Cr,
+    Lf,
+    Any,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum EncodingArg {
+    Auto,
+    Utf8,
+    Utf16le,
+    Utf16be,
+    Latin1,
+    Windows1252,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum UnterminatedArg {
+    Strict,
+    Lenient,
+    Recover,
+}
+
+impl Cli {
+    #[must_use]
+    pub fn run(&self) -> ExitCode {
+        match &self.command {
+            Command::Validate(args) => validate::run(args),
+            Command::Diff(args) => diff::run(args),
+            Command::Patch(args) => patch::run(args),
+            Command::Filter(args) => filter::run(args),
+            Command::Select(args) => select::run(args),
+            Command::Join(args) => join::run(args),
+            Command::Index(args) => index::run(args),
+            Command::Slice(args) => slice::run(args),
+            Command::Sort(args) => sort::run(args),
+            Command::Stats(args) => stats::run(args),
+            Command::Table(args) => table::run(args),
+            Command::Query(args) => query::run(args),
+            Command::ToJson(args) => to_json::run(args),
+            Command::FromJson(args) => from_json::run(args),
+            #[cfg(feature = "sqlite")]
+            Command::Sql(args) => sql::run(args),
+        }
+    }
+}
+
+impl Dialect {
+    /// # Errors
+    ///
+    /// See [`tokenizer::Options::set_delimiter`] and
+    /// [`tokenizer::Options::set_quote`].
+    pub fn tokenizer_options(&self) -> Result<tokenizer::Options, tokenizer::options::Error> {
+        let options = tokenizer::Options::default()
+            .with_allow_controls(self.allow_controls)
+            .with_line_break(self.line_break.into())
+            .with_quoting(if self.no_quoting {
+                Quoting::Disabled
+            } else if self.relaxed_quotes {
+                Quoting::Relaxed { warn: false }
+            } else {
+                Quoting::Strict
+            })
+            .with_sep_directive(!self.no_sep_directive)
+            .with_unterminated(self.unterminated.into())
+            .with_max_field_len(self.max_field_len)
+            .with_max_quoted_line_breaks(self.max_quoted_line_breaks);
+        // Swapping the delimiter and quote must not trip over the defaults.
+        if self.delimiter == options.quote() {
+            options
+                .with_quote(self.quote)?
+                .with_delimiter(self.delimiter)
+        } else {
+            options
+                .with_delimiter(self.delimiter)?
+                .with_quote(self.quote)
+        }
+    }
+
+    #[must_use]
+    pub fn recorder_options(&self) -> recorder::Options {
+        recorder::Options::default()
+            .with_trailing_delimiter(self.trailing_delimiter)
+            .with_max_fields(self.max_fields)
+            .with_max_record_bytes(self.max_record_bytes)
+            .with_max_records(self.max_records)
+    }
+
+    /// The input encoding, if not to be detected.
+    #[must_use]
+    pub const fn encoding(&self) -> Option<Encoding> {
+        match self.encoding {
+            EncodingArg::Auto => None,
+            EncodingArg::Utf8 => Some(Encoding::Utf8),
+            EncodingArg::Utf16le => Some(Encoding::Utf16Le),
+            EncodingArg::Utf16be => Some(Encoding::Utf16Be),
+            EncodingArg::Latin1 => Some(Encoding::Latin1),
+            EncodingArg::Windows1252 => Some(Encoding::Windows1252),
+        }
+    }
+
+    /// Decodes an input in the chosen encoding.
+    #[must_use]
+    pub fn chars<R>(&self, input: R) -> Chars<R>
+    where
+        R: Read,
+    {
+        match self.encoding() {
+            Some(encoding) => Chars::with_encoding(input, encoding),
+            None => Chars::new(input),
+        }
+    }
+
+    /// A CSV writer, starting with a byte order mark and a `sep=` directive
+    /// if asked to, and writing trailing delimiters if allowed.
+    #[must_use]
+    pub fn writer<W>(&self, inner: W, options: tokenizer::Options) -> Writer<W>
+    where
+        W: Write,
+    {
+        Writer::new(inner, options)
+            .with_bom(self.bom)
+            .with_sep_directive(self.sep_directive)
+            .with_trailing_delimiter(self.trailing_delimiter)
+    }
+
+    /// A CSV writer in the dialect of an input, as told by its first line:
+    /// with the delimiter it declares and the line break ending it, if any.
+    #[must_use]
+    pub fn writer_like<W>(
+        &self,
+        inner: W,
+        options: tokenizer::Options,
+        first_line: &FirstLine,
+    ) -> Writer<W>
+    where
+        W: Write,
+    {
+        let options = first_line
+            .delimiter
+            .and_then(|delimiter| options.clone().with_delimiter(delimiter).ok())
+            .unwrap_or(options);
+        let writer = self.writer(inner, options);
+        match first_line.line_break {
+            Some(line_break) => writer.with_line_break(line_break),
+            None => writer,
+        }
+    }
+
+    /// A diagnostic renderer for a stream, coloring if asked to or if the
+    /// stream is a terminal.
+    #[must_use]
+    pub fn renderer(&self, stream: &impl IsTerminal) -> Renderer {
+        Renderer::new(match self.color {
+            ColorArg::Always => true,
+            ColorArg::Never => false,
+            ColorArg::Auto => stream.is_terminal() && env::var_os("NO_COLOR").is_none(),
+        })
+    }
+}
+
+/// The first line of an input, read ahead to write output in the dialect of
+/// the input, see [`Dialect::writer_like`].
+#[derive(Debug, Default)]
+pub struct FirstLine {
+    /// The characters read, to be read again.
+    chars: Vec<char>,
+    line_break: Option<LineBreak>,
+    /// The delimiter declared by the line if it is a `sep=` directive.
+    delimiter: Option<char>,
+}
+
+impl FirstLine {
+    /// Reads characters up to and including the first line break outside
+    /// quotes.
+    pub fn read<I>(chars: &mut I, options: &tokenizer::Options) -> Self
+    where
+        I: Iterator<Item = char>,
+    {
+        let sep = ['s', 'e', 'p', '='];
+        let quoting = !options.quoting().is_disabled();
+        let mut line = Vec::new();
+        let mut line_break = None;
+        let mut quoted = false;
+        while let Some(ch) = chars.next() {
+            line.push(ch);
+            if ch == options.quote() && quoting && line[..line.len() - 1] != sep {
+                quoted = !quoted;
+            } else if ch == '\n' && !quoted {
+                line_break = Some(LineBreak::Lf);
+                break;
+            } else if ch == '\r' && !quoted {
+                line_break = Some(match chars.next() {
+                    Some('\n') => {
+                        line.push('\n');
+                        LineBreak::Crlf
+                    }
+                    next => {
+                        line.extend(next);
+                        LineBreak::Cr
+                    }
+                });
+                break;
+            }
+        }
+        let len = line
+            .iter()
+            .rposition(|ch| !matches!(ch, '\r' | '\n'))
+            .map_or(0, |idx| idx + 1);
+        let delimiter = match line[..len] {
+            [s, e, p, eq, delimiter] if options.sep_directive() && [s, e, p, eq] == sep => {
+                Some(delimiter)
+            }
+            _ => None,
+        };
+        Self {
+            chars: line,
+            line_break,
+            delimiter,
+        }
+    }
+
+    /// Takes the characters read, to be read again before the rest.
+    pub fn take_chars(&mut self) -> Vec<char> {
+        mem::take(&mut self.chars)
+    }
+}
+
+impl From<LineBreakArg> for LineBreak {
+    fn from(value: LineBreakArg) -> Self {
+        match value {
+            LineBreakArg::Crlf => Self::Crlf,
+            LineBreakArg::Cr => Self::Cr,
+            LineBreakArg::Lf => Self::Lf,
+            LineBreakArg::Any => Self::Any,
+        }
+    }
+}
+
+impl From<UnterminatedArg> for Unterminated {
+    fn from(value: UnterminatedArg) -> Self {
+        match value {
+            UnterminatedArg::Strict => Self::Strict,
+            UnterminatedArg::Lenient => Self::Lenient,
+            UnterminatedArg::Recover => Self::Recover,
+        }
+    }
+}
+
+/// Reads a whole input, where `-` stands for the standard input.
+///
+/// # Errors
+///
+/// If the input cannot be read or decoded.
+pub fn read_input(path: &Path, dialect: &Dialect) -> io::Result<String> {
+    let mut chars = dialect.chars(open_input(path)?);
+    let text = (&mut chars).collect();
+    match chars.take_error() {
+        Some(cause) => Err(cause),
+        None => Ok(text),
+    }
+}
+
+/// Opens an input for streaming, where `-` stands for the standard input.
+///
+/// # Errors
+///
+/// If the file cannot be opened.
+pub fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// Reports an error about an input and returns the failure exit code.
+pub fn fail(path: &Path, cause: impl fmt::Display) -> ExitCode {
+    eprintln!("csv: {}: {cause}", input_name(path));
+    ExitCode::FAILURE
+}
+
+/// Reports an error about an input and returns the failure exit code, as a
+/// diagnostic pointing into the input if the error is a parse error.
+///
+/// The input is read again to show the line in error, unless it is the
+/// standard input.
+pub fn report(path: &Path, dialect: &Dialect, cause: &(dyn Error + 'static)) -> ExitCode {
+    let Some(parse) = parse_error(cause) else {
+        return fail(path, cause);
+    };
+    let name = input_name(path);
+    let mut diagnostic = Diagnostic::from(&parse);
+    let text = (path != Path::new("-"))
+        .then(|| read_input(path, dialect).ok())
+        .flatten();
+    let source = match &text {
+        Some(text) => {
+            if let (Some(span), Ok(options)) = (diagnostic.span(), dialect.tokenizer_options()) {
+                if let Some(field) = FieldLocator::new(text, &options).locate(span.start) {
+                    diagnostic = diagnostic.with_field(field);
+                }
+            }
+            Source::new(&name, text)
+        }
+        None => Source::unread(&name),
+    };
+    let stderr = io::stderr();
+    eprint!("{}", dialect.renderer(&stderr).render(&diagnostic, &source));
+    ExitCode::FAILURE
+}
+
+/// The parse error an error is caused by, if any.
+fn parse_error(cause: &(dyn Error + 'static)) -> Option<ParseError> {
+    let mut next = Some(cause);
+    while let Some(cause) = next {
+        if let Some(cause) = cause.downcast_ref::<ParseError>() {
+            return Some(cause.clone());
+        }
+        if let Some(cause) = cause.downcast_ref::<tokenizer::Error>() {
+            return Some(cause.clone().into());
+        }
+        if let Some(cause) = cause.downcast_ref::<recorder::Error>() {
+            return Some(cause.clone().into());
+        }
+        next = cause.source();
+    }
+    None
+}
+
+/// Display name of an input path.
+#[must_use]
+pub fn input_name(path: &Path) -> String {
+    if path == Path::new("-") {
+        "<stdin>".to_owned()
+    } else {
+        path.display().to_string()
+    }
+}
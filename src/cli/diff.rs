@@ -0,0 +1,112 @@
+//! `csv diff`: compare two files.
+
+use std::{
+    fmt,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{column::Column, diff, records::Into as _, tokenizer::Into as _};
+
+use super::{fail, input_name, open_input, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Old file; `-` reads the standard input.
+    old: PathBuf,
+
+    /// New file; `-` reads the standard input.
+    new: PathBuf,
+
+    /// Key column matching records, by number starting at 1 or by header
+    /// name. Repeat for compound keys; records match by position without
+    /// any.
+    #[arg(short, long = "key")]
+    keys: Vec<Column>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = Format::Report)]
+    format: Format,
+
+    /// Treat the first records as data rather than headers.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Format {
+    /// A human-readable list of changes.
+    Report,
+    /// A CSV patch, one record per changed cell.
+    Csv,
+    /// A JSON patch.
+    Json,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+
+    let mut old_chars = match open_input(&args.old) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.old, cause),
+    };
+    let mut new_chars = match open_input(&args.new) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.new, cause),
+    };
+    let old = (&mut old_chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let new = (&mut new_chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let diff = diff::diff(old, new, &args.keys, !args.no_header);
+    if let Some(cause) = old_chars.take_error() {
+        return fail(&args.old, cause);
+    }
+    if let Some(cause) = new_chars.take_error() {
+        return fail(&args.new, cause);
+    }
+    let diff = match diff {
+        Ok(diff) => diff,
+        Err(cause) => return fail_both(args, cause),
+    };
+
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = match args.format {
+        Format::Report => writeln!(output, "{diff}").map_err(|cause| cause.to_string()),
+        Format::Csv => {
+            let mut writer = args.dialect.writer(&mut output, tokenizer_options);
+            diff.write_patch(&mut writer)
+                .map_err(|cause| cause.to_string())
+        }
+        Format::Json => serde_json::to_writer_pretty(&mut output, &diff.to_json())
+            .map_err(|cause| cause.to_string())
+            .and_then(|()| writeln!(output).map_err(|cause| cause.to_string())),
+    };
+    if let Err(cause) = result.and_then(|()| output.flush().map_err(|cause| cause.to_string())) {
+        return fail_both(args, cause);
+    }
+    ExitCode::SUCCESS
+}
+
+fn fail_both(args: &Args, cause: impl fmt::Display) -> ExitCode {
+    eprintln!(
+        "csv: {}, {}: {cause}",
+        input_name(&args.old),
+        input_name(&args.new)
+    );
+    ExitCode::FAILURE
+}
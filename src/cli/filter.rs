@@ -0,0 +1,81 @@
+//! `csv filter`: keep records matching a predicate.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    filter::{Into as _, Predicate},
+    records::Into as _,
+    tokenizer::Into as _,
+};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Predicate such as `status == active and (age >= 18 or #3 is empty)`.
+    ///
+    /// Tests are `==`, `!=`, numeric `<`, `<=`, `>`, `>=`, regex `=~` and
+    /// `!~`, `is [not] empty` and `[not] in (a, b)`, combined with `and`,
+    /// `or`, `not` and parentheses. Columns are header names, `#` and a
+    /// number starting at 1, or names quoted in backticks.
+    predicate: Predicate,
+
+    /// File to read; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let mut writer = args.dialect.writer(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options.clone(),
+    );
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options)
+        .csv_filter(args.predicate.clone(), !args.no_header);
+    for record in records {
+        let result = match record {
+            Ok(record) => writer
+                .write_record(record.fields())
+                .map_err(|cause| cause.to_string()),
+            Err(cause) => return report(&args.file, &args.dialect, &cause),
+        };
+        if let Err(cause) = result {
+            return fail(&args.file, cause);
+        }
+    }
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
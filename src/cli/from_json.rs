@@ -0,0 +1,57 @@
+//! `csv from-json`: convert a JSON array or JSON Lines to records.
+
+use std::{
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::json;
+
+use super::{fail, open_input, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to convert; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Read one element per line instead of a JSON array.
+    #[arg(long)]
+    lines: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let input = match open_input(&args.file) {
+        Ok(input) => BufReader::new(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let mut writer = args
+        .dialect
+        .writer(BufWriter::new(io::stdout().lock()), tokenizer_options);
+    let result = if args.lines {
+        json::from_json_lines(input, &mut writer)
+    } else {
+        json::from_json(input, &mut writer)
+    };
+
+    if let Err(cause) = result {
+        return fail(&args.file, cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,36 @@
+//! `csv index`: build the sidecar index of a file.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use csv::index::{sidecar_path, Index};
+
+use super::{fail, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to index, next to which the index is saved with `.idx` appended.
+    file: PathBuf,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let index = match Index::build_file(&args.file, args.dialect.encoding(), &tokenizer_options) {
+        Ok(index) => index,
+        Err(cause) => return report(&args.file, &args.dialect, &cause),
+    };
+    if let Err(cause) = index.save(&args.file) {
+        return fail(&sidecar_path(&args.file), cause);
+    }
+    ExitCode::SUCCESS
+}
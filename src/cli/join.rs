@@ -0,0 +1,173 @@
+//! `csv join`: join two files on key columns.
+
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    column::Column,
+    join::{self, options::Builder as _, Kind},
+    records::Into as _,
+    tokenizer::Into as _,
+};
+
+use super::{fail, input_name, open_input, Dialect, FirstLine};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Left file; `-` reads the standard input.
+    left: PathBuf,
+
+    /// Right file; `-` reads the standard input.
+    right: PathBuf,
+
+    /// Key column of both files, by number starting at 1 or by header name.
+    /// Repeat to join on several columns.
+    #[arg(short, long = "key", required_unless_present = "left_keys")]
+    keys: Vec<Column>,
+
+    /// Key column of the left file, paired in order with `--right-key`.
+    #[arg(long = "left-key", requires = "right_keys", conflicts_with = "keys")]
+    left_keys: Vec<Column>,
+
+    /// Key column of the right file, paired in order with `--left-key`.
+    #[arg(long = "right-key", requires = "left_keys", conflicts_with = "keys")]
+    right_keys: Vec<Column>,
+
+    /// Which unmatched records to keep.
+    #[arg(short, long, value_enum, default_value_t = KindArg::Inner)]
+    join: KindArg,
+
+    /// Suffix of left header names also found in the right header.
+    #[arg(long, default_value = "_left")]
+    left_suffix: String,
+
+    /// Suffix of right header names also found in the left header.
+    #[arg(long, default_value = "_right")]
+    right_suffix: String,
+
+    /// Mebibytes of right records to hash in memory before sorting both
+    /// files on disk instead.
+    #[arg(short, long, default_value_t = 64)]
+    memory: usize,
+
+    /// Directory for temporary files.
+    #[arg(long)]
+    temp_dir: Option<PathBuf>,
+
+    /// Treat the first records as data rather than headers.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum KindArg {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let join_options = join::Options::default()
+        .with_kind(args.join.into())
+        .with_header(!args.no_header)
+        .with_left_suffix(Cow::Owned(args.left_suffix.clone()))
+        .with_right_suffix(Cow::Owned(args.right_suffix.clone()))
+        .with_budget(args.memory.saturating_mul(1024 * 1024))
+        .with_temp_dir(args.temp_dir.clone());
+    let (left_keys, right_keys) = if args.keys.is_empty() {
+        (&args.left_keys, &args.right_keys)
+    } else {
+        (&args.keys, &args.keys)
+    };
+
+    let mut left_chars = match open_input(&args.left) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.left, cause),
+    };
+    let mut right_chars = match open_input(&args.right) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.right, cause),
+    };
+    let mut first_line = FirstLine::read(&mut left_chars, &tokenizer_options);
+    let mut writer = args.dialect.writer_like(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options.clone(),
+        &first_line,
+    );
+    let left = first_line
+        .take_chars()
+        .into_iter()
+        .chain(&mut left_chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let right = (&mut right_chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let result = join::join(left, right, left_keys, right_keys, &join_options)
+        .map_err(|cause| cause.to_string())
+        .and_then(|joined| {
+            if let Some(header) = joined.header() {
+                writer
+                    .write_record(header)
+                    .map_err(|cause| cause.to_string())?;
+            }
+            joined.into_iter().try_for_each(|record| match record {
+                Ok(record) => writer
+                    .write_record(&record)
+                    .map_err(|cause| cause.to_string()),
+                Err(cause) => Err(cause.to_string()),
+            })
+        });
+
+    if let Some(cause) = left_chars.take_error() {
+        return fail(&args.left, cause);
+    }
+    if let Some(cause) = right_chars.take_error() {
+        return fail(&args.right, cause);
+    }
+    if let Err(cause) = result {
+        return fail_both(args, cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail_both(args, cause);
+    }
+    ExitCode::SUCCESS
+}
+
+fn fail_both(args: &Args, cause: impl fmt::Display) -> ExitCode {
+    eprintln!(
+        "csv: {}, {}: {cause}",
+        input_name(&args.left),
+        input_name(&args.right)
+    );
+    ExitCode::FAILURE
+}
+
+impl From<KindArg> for Kind {
+    fn from(value: KindArg) -> Self {
+        match value {
+            KindArg::Inner => Self::Inner,
+            KindArg::Left => Self::Left,
+            KindArg::Right => Self::Right,
+            KindArg::Full => Self::Full,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! `csv patch`: apply a patch made by `csv diff`.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{diff::Diff, records::Into as _, tokenizer::Into as _};
+
+use super::{fail, open_input, report, Dialect, FirstLine};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Old file; `-` reads the standard input.
+    old: PathBuf,
+
+    /// Patch file, as written by `csv diff --format csv` or `--format json`.
+    patch: PathBuf,
+
+    /// Read the patch as JSON rather than CSV.
+    #[arg(long)]
+    json: bool,
+
+    /// Treat the first record of the old file as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+
+    let patch = match open_input(&args.patch) {
+        Ok(input) if args.json => Diff::from_json(input),
+        Ok(input) => {
+            let mut chars = args.dialect.chars(input);
+            let patch = Diff::read_patch(
+                (&mut chars)
+                    .csv_tokens_custom(&tokenizer_options)
+                    .csv_records_custom(&recorder_options),
+            );
+            if let Some(cause) = chars.take_error() {
+                return fail(&args.patch, cause);
+            }
+            patch
+        }
+        Err(cause) => return fail(&args.patch, cause),
+    };
+    let patch = match patch {
+        Ok(patch) => patch,
+        Err(cause) => return report(&args.patch, &args.dialect, &cause),
+    };
+
+    let mut chars = match open_input(&args.old) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.old, cause),
+    };
+    let mut first_line = FirstLine::read(&mut chars, &tokenizer_options);
+    let records = first_line
+        .take_chars()
+        .into_iter()
+        .chain(&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options)
+        .map(|record| record.map(|record| record.into_fields()))
+        .collect::<Result<Vec<_>, _>>();
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.old, cause);
+    }
+    let mut records = match records {
+        Ok(records) => records,
+        Err(cause) => return report(&args.old, &args.dialect, &cause),
+    };
+    let header = (!args.no_header && !records.is_empty()).then(|| records.remove(0));
+    let records = match patch.apply(records) {
+        Ok(records) => records,
+        Err(cause) => return fail(&args.old, cause),
+    };
+
+    let mut writer = args.dialect.writer_like(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options,
+        &first_line,
+    );
+    for record in header.iter().chain(&records) {
+        if let Err(cause) = writer.write_record(record) {
+            return fail(&args.old, cause);
+        }
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.old, cause);
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,85 @@
+//! `csv query`: run a SQL-like query over a file.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    query::{self, Query},
+    records::Into as _,
+    tokenizer::Into as _,
+};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Query such as `SELECT city, avg(age) FROM people.csv WHERE age > 18
+    /// GROUP BY city ORDER BY 2 DESC LIMIT 10`.
+    ///
+    /// Expressions combine columns, numbers and 'strings' with arithmetic,
+    /// `||`, comparisons, `AND`, `OR`, `NOT`, `IS [NOT] NULL`, `[NOT] IN`,
+    /// `[NOT] LIKE` and `[NOT] BETWEEN`, and the functions `lower`, `upper`,
+    /// `length`, `trim`, `abs`, `round` and `coalesce`. The aggregates are
+    /// `count`, `sum`, `avg`, `min` and `max`. Columns are header names,
+    /// names quoted in double quotes or backticks, or `#` and a number
+    /// starting at 1. Empty fields are NULL.
+    query: Query,
+
+    /// File to query, instead of the one named by `FROM`; `-` reads the
+    /// standard input, which is read if neither is given.
+    file: Option<PathBuf>,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let file = match (&args.file, args.query.from()) {
+        (Some(_), Some(_)) => {
+            eprintln!("csv: the query names a file with FROM, and so does the command line");
+            return ExitCode::from(2);
+        }
+        (Some(file), None) => file.clone(),
+        (None, from) => PathBuf::from(from.unwrap_or("-")),
+    };
+
+    let mut chars = match open_input(&file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&file, cause),
+    };
+    let mut writer = args.dialect.writer(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options.clone(),
+    );
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let result = query::execute(&args.query, records, !args.no_header, &mut writer);
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&file, cause);
+    }
+    if let Err(cause) = result {
+        return report(&file, &args.dialect, &cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&file, cause);
+    }
+    ExitCode::SUCCESS
+}
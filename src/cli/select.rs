@@ -0,0 +1,76 @@
+//! `csv select`: pick, reorder and duplicate columns.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::projection::{Into as _, Selection};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Comma separated columns: numbers starting at 1, inclusive ranges such
+    /// as `2-5`, `4-` or `-2`, and header names. Quote names with `"`,
+    /// doubling quotes within, to select names such as `"1"` or `"a,b"`.
+    selection: Selection,
+
+    /// File to read; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let mut writer = args.dialect.writer(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options.clone(),
+    );
+    let records = (&mut chars).csv_projected(
+        &tokenizer_options,
+        &recorder_options,
+        args.selection.clone(),
+        !args.no_header,
+    );
+    for record in records {
+        let result = match record {
+            Ok(record) => writer
+                .write_record(record.fields())
+                .map_err(|cause| cause.to_string()),
+            Err(cause) => return report(&args.file, &args.dialect, &cause),
+        };
+        if let Err(cause) = result {
+            return fail(&args.file, cause);
+        }
+    }
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
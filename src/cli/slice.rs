@@ -0,0 +1,144 @@
+//! `csv slice`: read a range of records through the sidecar index.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    chars::Chars,
+    index::{self, Index},
+    records::Into as _,
+    tokenizer::{options::Builder as _, Into as _},
+};
+
+use super::{fail, report, Dialect, FirstLine};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Indexed file, see `csv index`.
+    file: PathBuf,
+
+    /// First record to write, counting data records from 1.
+    #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    start: u64,
+
+    /// Number of records to write; all remaining ones without it.
+    #[arg(short = 'n', long)]
+    count: Option<usize>,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    /// Check the whole file against the index, rather than samples of it.
+    #[arg(long)]
+    verify: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+
+    let index = match Index::load(&args.file) {
+        Ok(index) => index,
+        Err(cause @ (index::Error::Stale | index::Error::Io(_))) => {
+            return fail(
+                &args.file,
+                format_args!("{cause}; rebuild the index with `csv index`"),
+            )
+        }
+        Err(cause) => return fail(&args.file, cause),
+    };
+    if args.verify {
+        let fresh = File::open(&args.file).and_then(|file| index.verify(BufReader::new(file)));
+        match fresh {
+            Ok(true) => {}
+            Ok(false) => {
+                return fail(
+                    &args.file,
+                    format_args!(
+                        "{}; rebuild the index with `csv index`",
+                        index::Error::Stale
+                    ),
+                )
+            }
+            Err(cause) => return fail(&args.file, cause),
+        }
+    }
+    // Records are read past any `sep=` directive, so its delimiter comes
+    // from the index.
+    let mut reading = tokenizer_options.clone();
+    if let Some(delimiter) = index.delimiter() {
+        if let Err(cause) = reading.set_delimiter(delimiter) {
+            return fail(&args.file, cause);
+        }
+    }
+    let mut file = match File::open(&args.file) {
+        Ok(file) => file,
+        Err(cause) => return fail(&args.file, cause),
+    };
+
+    // The output ends records with the line break of the first one.
+    let first_line = match index.seek(&mut file, 0) {
+        Ok(Some(_)) => FirstLine::read(
+            &mut Chars::resume(BufReader::new(&mut file), index.encoding()),
+            &reading.clone().with_sep_directive(false),
+        ),
+        Ok(None) => FirstLine::default(),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let mut writer = args.dialect.writer_like(
+        BufWriter::new(io::stdout().lock()),
+        reading.clone(),
+        &first_line,
+    );
+    let header = usize::from(!args.no_header);
+    let start = usize::try_from(args.start - 1)
+        .unwrap_or(usize::MAX)
+        .saturating_add(header);
+    let ranges = [(0, header), (start, args.count.unwrap_or(usize::MAX))];
+    for (record, count) in ranges {
+        let offset = match index.seek(&mut file, record) {
+            Ok(Some(offset)) => offset,
+            Ok(None) => continue,
+            Err(cause) => return fail(&args.file, cause),
+        };
+        let mut chars = Chars::resume(BufReader::new(&mut file), index.encoding());
+        let records = (&mut chars)
+            .csv_tokens_at(offset.char, &reading)
+            .csv_records_custom(&recorder_options)
+            .take(count);
+        for record in records {
+            let result = match record {
+                Ok(record) => writer
+                    .write_record(record.fields())
+                    .map_err(|cause| cause.to_string()),
+                Err(cause) => return report(&args.file, &args.dialect, &cause),
+            };
+            if let Err(cause) = result {
+                return fail(&args.file, cause);
+            }
+        }
+        if let Some(cause) = chars.take_error() {
+            return fail(&args.file, cause);
+        }
+    }
+
+    if let Err(cause) = writer.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
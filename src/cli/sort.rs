@@ -0,0 +1,105 @@
+//! `csv sort`: sort records by key columns.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    records::Into as _,
+    sort::{self, options::Builder as _, Key},
+    tokenizer::Into as _,
+};
+
+use super::{fail, open_input, report, Dialect, FirstLine};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to sort; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Key column, by number starting at 1 or by header name, optionally
+    /// followed by `:lexical`, `:numeric` or `:natural` then `:asc` or
+    /// `:desc`. Repeat for tie breakers; whole records compare without any.
+    #[arg(short, long = "key")]
+    keys: Vec<Key>,
+
+    /// Mebibytes of records to sort in memory before spilling to disk.
+    #[arg(short, long, default_value_t = 64)]
+    memory: usize,
+
+    /// Directory for temporary files.
+    #[arg(long)]
+    temp_dir: Option<PathBuf>,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let sort_options = sort::Options::default()
+        .with_budget(args.memory.saturating_mul(1024 * 1024))
+        .with_header(!args.no_header)
+        .with_temp_dir(args.temp_dir.clone());
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let mut first_line = FirstLine::read(&mut chars, &tokenizer_options);
+    let records = first_line
+        .take_chars()
+        .into_iter()
+        .chain(&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let sorted = sort::sort(records, &args.keys, &sort_options);
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    let sorted = match sorted {
+        Ok(sorted) => sorted,
+        Err(cause) => return report(&args.file, &args.dialect, &cause),
+    };
+
+    let mut writer = args.dialect.writer_like(
+        BufWriter::new(io::stdout().lock()),
+        tokenizer_options,
+        &first_line,
+    );
+    if let Some(header) = sorted.header() {
+        if let Err(cause) = writer.write_record(header) {
+            return fail(&args.file, cause);
+        }
+    }
+    for record in sorted {
+        let result = match record {
+            Ok(record) => writer
+                .write_record(&record)
+                .map_err(|cause| cause.to_string()),
+            Err(cause) => return report(&args.file, &args.dialect, &cause),
+        };
+        if let Err(cause) = result {
+            return fail(&args.file, cause);
+        }
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,168 @@
+//! `csv sql`: import records into and export query results from SQLite.
+
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    records::Into as _,
+    sqlite::{self, options::Builder as _, IfExists},
+    tokenizer::Into as _,
+};
+use rusqlite::{Connection, OpenFlags};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Create a table from a file and insert its records.
+    Import(ImportArgs),
+    /// Write the result of a query as CSV.
+    Export(ExportArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct ImportArgs {
+    /// Database file, created if missing.
+    database: PathBuf,
+
+    /// File to import; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Table to create; defaults to the file name without its extension.
+    #[arg(short, long)]
+    table: Option<String>,
+
+    /// What to do if the table already exists.
+    #[arg(long, value_enum, default_value_t = IfExistsArg::Fail)]
+    if_exists: IfExistsArg,
+
+    /// Number of records read to infer the column types.
+    #[arg(long, value_name = "COUNT", default_value_t = 1000)]
+    infer_records: usize,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportArgs {
+    /// Database file, opened read-only.
+    database: PathBuf,
+
+    /// Query whose result to write.
+    query: String,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum IfExistsArg {
+    /// Fail without importing anything.
+    Fail,
+    /// Insert into the existing table.
+    Append,
+    /// Drop the existing table first.
+    Replace,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    match &args.command {
+        Command::Import(args) => import(args),
+        Command::Export(args) => export(args),
+    }
+}
+
+fn import(args: &ImportArgs) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let Some(table) = args.table.clone().or_else(|| {
+        args.file
+            .file_stem()
+            .filter(|_| args.file.as_os_str() != "-")
+            .map(|stem| stem.to_string_lossy().into_owned())
+    }) else {
+        eprintln!("csv: a table name is required when importing the standard input");
+        return ExitCode::from(2);
+    };
+    let sqlite_options = sqlite::Options::default()
+        .with_header(!args.no_header)
+        .with_infer_records(args.infer_records)
+        .with_if_exists(match args.if_exists {
+            IfExistsArg::Fail => IfExists::Fail,
+            IfExistsArg::Append => IfExists::Append,
+            IfExistsArg::Replace => IfExists::Replace,
+        });
+
+    let mut connection = match Connection::open(&args.database) {
+        Ok(connection) => connection,
+        Err(cause) => return fail(&args.database, cause),
+    };
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let result = sqlite::import(records, &mut connection, &table, &sqlite_options);
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(cause @ sqlite::Error::Sqlite(_)) => fail(&args.database, cause),
+        Err(cause) => report(&args.file, &args.dialect, &cause),
+    }
+}
+
+fn export(args: &ExportArgs) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let connection = match Connection::open_with_flags(
+        &args.database,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    ) {
+        Ok(connection) => connection,
+        Err(cause) => return fail(&args.database, cause),
+    };
+    let mut writer = args
+        .dialect
+        .writer(BufWriter::new(io::stdout().lock()), tokenizer_options);
+
+    if let Err(cause) = sqlite::export(&connection, &args.query, &mut writer) {
+        return fail(&args.database, cause);
+    }
+    if let Err(cause) = writer.flush() {
+        return fail(&args.database, cause);
+    }
+    ExitCode::SUCCESS
+}
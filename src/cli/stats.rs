@@ -0,0 +1,190 @@
+//! `csv stats`: profile the columns of a file.
+
+use std::{
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    column::Column,
+    records::Into as _,
+    stats::{self, options::Builder as _, Distinct, HyperLogLog, Summary},
+    tokenizer::Into as _,
+};
+use serde_json::{json, Value};
+
+use super::{fail, open_input, report, Dialect};
+
+/// The header of the statistics, one record per column following it.
+const HEADER: [&str; 11] = [
+    "column", "type", "count", "empty", "distinct", "min", "max", "mean", "stddev", "longest",
+    "top",
+];
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to profile; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Number of most frequent values to list per column.
+    #[arg(short, long, default_value_t = 5)]
+    top: usize,
+
+    /// Count distinct and frequent values approximately, in bounded memory;
+    /// approximate counts are marked with `~`, and those of frequent values
+    /// are upper bounds.
+    #[arg(short, long)]
+    approximate: bool,
+
+    /// Precision of approximate distinct counts, taking `2^P` bytes per
+    /// column for a standard error of about `1.04 / sqrt(2^P)`.
+    #[arg(
+        long,
+        default_value_t = 14,
+        requires = "approximate",
+        value_parser = clap::value_parser!(u8).range(
+            i64::from(HyperLogLog::MIN_PRECISION)..=i64::from(HyperLogLog::MAX_PRECISION)
+        ),
+    )]
+    precision: u8,
+
+    /// Write the statistics as JSON rather than CSV.
+    #[arg(long)]
+    json: bool,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let distinct = if args.approximate {
+        Distinct::Approximate {
+            precision: args.precision,
+        }
+    } else {
+        Distinct::Exact
+    };
+    let stats_options = stats::Options::default()
+        .with_distinct(distinct)
+        .with_header(!args.no_header)
+        .with_top(args.top);
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let summaries = stats::profile(records, &stats_options);
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    let summaries = match summaries {
+        Ok(summaries) => summaries,
+        Err(cause) => return report(&args.file, &args.dialect, &cause),
+    };
+
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = if args.json {
+        let summaries = summaries
+            .iter()
+            .enumerate()
+            .map(|(idx, summary)| to_json(idx, summary))
+            .collect::<Value>();
+        serde_json::to_writer_pretty(&mut output, &summaries)
+            .map_err(|cause| cause.to_string())
+            .and_then(|()| writeln!(output).map_err(|cause| cause.to_string()))
+    } else {
+        let mut writer = args.dialect.writer(&mut output, tokenizer_options);
+        writer
+            .write_record(HEADER)
+            .and_then(|()| {
+                summaries
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(idx, summary)| writer.write_record(to_fields(idx, summary)))
+            })
+            .map_err(|cause| cause.to_string())
+    };
+    if let Err(cause) = result.and_then(|()| output.flush().map_err(|cause| cause.to_string())) {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
+
+fn name(idx: usize, summary: &Summary) -> String {
+    summary
+        .name
+        .clone()
+        .unwrap_or_else(|| Column::Index(idx).to_string())
+}
+
+fn to_fields(idx: usize, summary: &Summary) -> [String; 11] {
+    let number = |number: Option<f64>| number.map(|number| number.to_string()).unwrap_or_default();
+    let top = summary
+        .top
+        .iter()
+        .map(|(value, count)| {
+            if summary.exact {
+                format!("{value} ({count})")
+            } else {
+                format!("{value} (~{count})")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    [
+        name(idx, summary),
+        summary.kind.to_string(),
+        summary.count.to_string(),
+        summary.empty.to_string(),
+        if summary.exact {
+            summary.distinct.to_string()
+        } else {
+            format!("~{}", summary.distinct)
+        },
+        summary.min.clone().unwrap_or_default(),
+        summary.max.clone().unwrap_or_default(),
+        number(summary.mean),
+        number(summary.stddev),
+        summary.longest.clone().unwrap_or_default(),
+        top,
+    ]
+}
+
+fn to_json(idx: usize, summary: &Summary) -> Value {
+    json!({
+        "column": name(idx, summary),
+        "type": summary.kind.to_string(),
+        "count": summary.count,
+        "empty": summary.empty,
+        "distinct": summary.distinct,
+        "exact": summary.exact,
+        "min": summary.min,
+        "max": summary.max,
+        "mean": summary.mean,
+        "stddev": summary.stddev,
+        "longest": summary.longest,
+        "top": summary
+            .top
+            .iter()
+            .map(|(value, count)| json!({ "value": value, "count": count }))
+            .collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,90 @@
+//! `csv table`: show records in aligned columns.
+
+use std::{
+    env,
+    io::{self, BufWriter, Write as _},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    records::Into as _,
+    table::{self, options::Builder as _},
+    tokenizer::Into as _,
+};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to show; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Number of records to show past the header; 0 shows every record.
+    #[arg(short = 'n', long, default_value_t = 100)]
+    rows: usize,
+
+    /// Width of a whole line; defaults to `$COLUMNS` when set.
+    #[arg(short = 'w', long)]
+    max_width: Option<usize>,
+
+    /// Width past which cells are truncated; 0 never truncates.
+    #[arg(long, default_value_t = 40)]
+    max_cell_width: usize,
+
+    /// Draw box borders around cells.
+    #[arg(short, long)]
+    borders: bool,
+
+    /// Treat the first record as data rather than a header.
+    #[arg(long)]
+    no_header: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let max_width = args.max_width.or_else(|| {
+        env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+    });
+    let table_options = table::Options::default()
+        .with_borders(args.borders)
+        .with_header(!args.no_header)
+        .with_max_cell_width(Some(args.max_cell_width).filter(|width| *width != 0))
+        .with_max_width(max_width)
+        .with_rows(Some(args.rows).filter(|rows| *rows != 0));
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = table::render(records, &mut output, &table_options);
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    if let Err(cause) = result {
+        return report(&args.file, &args.dialect, &cause);
+    }
+    if let Err(cause) = output.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
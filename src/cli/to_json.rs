@@ -0,0 +1,82 @@
+//! `csv to-json`: convert records to JSON or JSON Lines.
+
+use std::{
+    io::{self, BufWriter, Write as _},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use csv::{
+    json::{self, Layout},
+    records::Into as _,
+    tokenizer::Into as _,
+};
+
+use super::{fail, open_input, report, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// File to convert; `-` reads the standard input.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+
+    /// Shape of each element.
+    #[arg(long, value_enum, default_value_t = LayoutArg::Objects)]
+    layout: LayoutArg,
+
+    /// Write one element per line instead of a JSON array.
+    #[arg(long)]
+    lines: bool,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum LayoutArg {
+    /// Objects keyed by the header.
+    Objects,
+    /// Arrays of fields, the header included.
+    Arrays,
+}
+
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            eprintln!("csv: {cause}");
+            return ExitCode::from(2);
+        }
+    };
+    let recorder_options = args.dialect.recorder_options();
+    let layout = match args.layout {
+        LayoutArg::Objects => Layout::Objects,
+        LayoutArg::Arrays => Layout::Arrays,
+    };
+
+    let mut chars = match open_input(&args.file) {
+        Ok(input) => args.dialect.chars(input),
+        Err(cause) => return fail(&args.file, cause),
+    };
+    let records = (&mut chars)
+        .csv_tokens_custom(&tokenizer_options)
+        .csv_records_custom(&recorder_options);
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = if args.lines {
+        json::to_json_lines(records, &mut output, layout)
+    } else {
+        json::to_json(records, &mut output, layout)
+    };
+
+    if let Some(cause) = chars.take_error() {
+        return fail(&args.file, cause);
+    }
+    if let Err(cause) = result {
+        return report(&args.file, &args.dialect, &cause);
+    }
+    if let Err(cause) = output.flush() {
+        return fail(&args.file, cause);
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,153 @@
+//! `csv validate`: report every parse error along with its location, and
+//! every relaxation of the quoting rules when quotes are relaxed, as
+//! diagnostics showing the line in question, or as JSON Lines of
+//! diagnostics for programs.
+
+use std::{io, path::PathBuf, process::ExitCode};
+
+use csv::{
+    diagnostic::{Diagnostic, Severity, Source},
+    location::FieldLocator,
+    recorder,
+    records::Into as _,
+    tokenizer::{self, options::Quoting, Into as _, Warning},
+    ParseError,
+};
+
+use super::{input_name, read_input, Dialect};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Files to validate; `-` reads the standard input.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Report as text, or as one JSON object per diagnostic.
+    #[arg(long, value_enum, default_value_t = FormatArg::Text)]
+    format: FormatArg,
+
+    #[command(flatten)]
+    dialect: Dialect,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum FormatArg {
+    Text,
+    Json,
+}
+
+/// Validates each file, exiting with a failure if any of them is invalid.
+#[must_use]
+pub fn run(args: &Args) -> ExitCode {
+    let mut tokenizer_options = match args.dialect.tokenizer_options() {
+        Ok(options) => options,
+        Err(cause) => {
+            match args.format {
+                FormatArg::Text => eprintln!("csv: {cause}"),
+                FormatArg::Json => println!("{}", Diagnostic::from(&cause).to_json(None)),
+            }
+            return ExitCode::from(2);
+        }
+    };
+    if let Quoting::Relaxed { warn } = tokenizer_options.quoting_mut() {
+        *warn = true;
+    }
+    let recorder_options = args.dialect.recorder_options();
+    let renderer = args.dialect.renderer(&io::stdout());
+
+    let mut valid = true;
+    for path in &args.files {
+        let name = input_name(path);
+        let text = match read_input(path, &args.dialect) {
+            Ok(text) => text,
+            Err(cause) => {
+                match args.format {
+                    FormatArg::Text => eprintln!("{name}: {cause}"),
+                    FormatArg::Json => {
+                        let diagnostic = Diagnostic::new(Severity::Error, cause.to_string());
+                        println!("{}", diagnostic.to_json(Some(&Source::unread(&name))));
+                    }
+                }
+                valid = false;
+                continue;
+            }
+        };
+        let source = Source::new(&name, &text);
+        let fields = FieldLocator::new(&text, &tokenizer_options);
+        let (errors, warnings) = check(&text, &tokenizer_options, &recorder_options);
+        let mut errors = errors.into_iter().peekable();
+        let mut warnings = warnings.into_iter().peekable();
+        // Both are in order; merge them so that the report reads top down.
+        loop {
+            let warning =
+                warnings.next_if(|warning| errors.peek().is_none_or(|(idx, _)| warning.at() < idx));
+            let (idx, diagnostic) = if let Some(warning) = warning {
+                (*warning.at(), Diagnostic::from(&warning))
+            } else if let Some((idx, cause)) = errors.next() {
+                valid = false;
+                let mut diagnostic = Diagnostic::from(&cause);
+                if diagnostic.span().is_none() {
+                    diagnostic = diagnostic.with_span(idx..idx);
+                }
+                (idx, diagnostic)
+            } else {
+                break;
+            };
+            let diagnostic = match fields.locate(idx) {
+                Some(field) => diagnostic.with_field(field),
+                None => diagnostic,
+            };
+            match args.format {
+                FormatArg::Text => println!("{}", renderer.render(&diagnostic, &source)),
+                FormatArg::Json => println!("{}", diagnostic.to_json(Some(&source))),
+            }
+        }
+    }
+
+    if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Collects every error in the text, each paired with the character index it
+/// occurred at, along with every warning.
+fn check(
+    text: &str,
+    tokenizer_options: &tokenizer::Options,
+    recorder_options: &recorder::Options,
+) -> (Vec<(usize, ParseError)>, Vec<Warning>) {
+    let mut len = None;
+    let mut tokens = text.chars().csv_tokens_custom(tokenizer_options);
+    let errors = (&mut tokens)
+        .csv_records_custom(recorder_options)
+        .enumerate()
+        .filter_map(|(at, record)| match record {
+            Ok(record) => match len {
+                Some(expected) if expected != record.len() => Some((
+                    record.idx(),
+                    ParseError::JaggedRecords {
+                        expected,
+                        at,
+                        actual: record.len(),
+                    },
+                )),
+                Some(_) => None,
+                None => {
+                    len = Some(record.len());
+                    None
+                }
+            },
+            Err(cause) => {
+                let idx = match &cause {
+                    ParseError::Tokenizer(cause) => *cause.at(),
+                    ParseError::Recorder(cause) => *cause.at(),
+                    ParseError::JaggedRecords { .. } => unreachable!(),
+                };
+                Some((idx, cause))
+            }
+        })
+        .collect();
+    (errors, tokens.take_warnings())
+}
@@ -0,0 +1,60 @@
+//! Column references, [`Column`].
+
+use std::{convert::Infallible, fmt, str::FromStr};
+
+/// A column referenced either by position or by header name.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Column {
+    /// A zero-based column index.
+    Index(usize),
+    /// The first column of the header with the given name.
+    Name(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error("no column is named {0:?}")]
+    UnknownName(String),
+    #[error("column {0:?} is referenced by name, but there is no header")]
+    NameWithoutHeader(String),
+}
+
+impl Column {
+    /// Resolves the column to an index.
+    ///
+    /// # Errors
+    ///
+    /// If a name is referenced without a header, or no column has that name.
+    pub fn resolve(&self, header: Option<&[String]>) -> Result<usize, Error> {
+        match self {
+            Self::Index(idx) => Ok(*idx),
+            Self::Name(name) => header
+                .ok_or_else(|| Error::NameWithoutHeader(name.clone()))?
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| Error::UnknownName(name.clone())),
+        }
+    }
+}
+
+/// Parses one-based column numbers such as `3` as indices, and anything else
+/// as a name.
+impl FromStr for Column {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse::<usize>() {
+            Ok(number @ 1..) => Self::Index(number - 1),
+            _ => Self::Name(s.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(idx) => write!(f, "{}", idx + 1),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
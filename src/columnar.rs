@@ -0,0 +1,493 @@
+//! Column-major tables, [`Columns`], read by [`parse`].
+//!
+//! Records are read one at a time and their fields moved into one vector per
+//! column, so that no row-major copy of the input is ever held. Columns are
+//! read as text and typed afterwards, one column at a time, see
+//! [`Column::infer`], so that at most one column is held twice.
+
+pub mod options;
+
+use std::{mem, ops::Range};
+
+pub use options::Options;
+
+use crate::{
+    column,
+    records::Record,
+    value::{Type, Value},
+    ParseError,
+};
+
+/// Columns of equal length.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Columns {
+    columns: Vec<Column>,
+    /// Number of rows, kept apart for tables without columns.
+    len: usize,
+}
+
+/// A named column of values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    name: Option<String>,
+    data: Data,
+}
+
+/// The values of a column, where `None` stands for an empty field of a typed
+/// column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    Text(Vec<String>),
+    Boolean(Vec<Option<bool>>),
+    Integer(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error("expected {expected} fields, instead got {actual}")]
+    Width { expected: usize, actual: usize },
+    #[error("{value:?} in column {} is not of type {kind}", column + 1)]
+    Value {
+        column: usize,
+        kind: Type,
+        value: String,
+    },
+    #[error("cannot append a column of type {other} to column {} of type {kind}", column + 1)]
+    Append {
+        column: usize,
+        kind: Type,
+        other: Type,
+    },
+    #[error("column {} has {actual} values, expected {expected}", column + 1)]
+    Length {
+        column: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("column {} is out of bounds of {width} columns", column + 1)]
+    OutOfBounds { column: usize, width: usize },
+    #[error("{0}")]
+    Column(#[from] column::Error),
+}
+
+/// Reads records into columns, typing them if asked to.
+///
+/// # Errors
+///
+/// If a record fails to parse, or has a different number of fields than the
+/// first.
+pub fn parse<I>(records: I, options: &Options) -> Result<Columns, ParseError>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    let mut records = records.peekable();
+    let mut columns = match records.next_if(|_| options.header()) {
+        Some(record) => Columns::new(Some(record?.into_fields())),
+        None => Columns::new(None),
+    };
+    let skipped = usize::from(options.header());
+    for (at, record) in records.enumerate() {
+        let fields = record?.into_fields();
+        if let Err(Error::Width { expected, actual }) = columns.push_record(fields) {
+            return Err(ParseError::JaggedRecords {
+                expected,
+                at: at + skipped,
+                actual,
+            });
+        }
+    }
+    if options.infer() {
+        columns.infer();
+    }
+    Ok(columns)
+}
+
+impl Columns {
+    /// Text columns named by the header, if any, or as wide as the first
+    /// record pushed otherwise.
+    #[must_use]
+    pub fn new(header: Option<Vec<String>>) -> Self {
+        let columns = header
+            .into_iter()
+            .flatten()
+            .map(|name| Column::new(Some(name), Data::Text(Vec::new())))
+            .collect();
+        Self { columns, len: 0 }
+    }
+
+    /// Columns with the given values, which must be of equal length.
+    ///
+    /// # Errors
+    ///
+    /// If a column differs in length from the first.
+    pub fn from_columns(columns: Vec<Column>) -> Result<Self, Error> {
+        let len = columns.first().map_or(0, Column::len);
+        match columns.iter().position(|column| column.len() != len) {
+            None => Ok(Self { columns, len }),
+            Some(column) => Err(Error::Length {
+                column,
+                expected: len,
+                actual: columns[column].len(),
+            }),
+        }
+    }
+
+    /// Number of rows.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of columns.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_columns(self) -> Vec<Column> {
+        self.columns
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn column(&self, idx: usize) -> Option<&Column> {
+        self.columns.get(idx)
+    }
+
+    /// The first column with the given name.
+    #[inline]
+    #[must_use]
+    pub fn column_named(&self, name: &str) -> Option<&Column> {
+        self.columns
+            .iter()
+            .find(|column| column.name() == Some(name))
+    }
+
+    /// The names of the columns, if every column has one.
+    #[must_use]
+    pub fn header(&self) -> Option<Vec<String>> {
+        self.columns
+            .iter()
+            .map(|column| column.name.clone())
+            .collect()
+    }
+
+    /// Appends a record as a row.
+    ///
+    /// The first record pushed without a header decides the number of
+    /// columns. Fields of typed columns must be of their type, an integer
+    /// fitting a float column.
+    ///
+    /// # Errors
+    ///
+    /// If the record has a different number of fields than there are
+    /// columns, or a field is not of the type of its column; the columns are
+    /// then left unchanged.
+    pub fn push_record(&mut self, fields: Vec<String>) -> Result<(), Error> {
+        if self.columns.is_empty() && self.len == 0 {
+            self.columns = (0..fields.len())
+                .map(|_| Column::new(None, Data::Text(Vec::new())))
+                .collect();
+        }
+        if fields.len() != self.width() {
+            return Err(Error::Width {
+                expected: self.width(),
+                actual: fields.len(),
+            });
+        }
+        for (idx, (column, field)) in self.columns.iter().zip(&fields).enumerate() {
+            if !column.data.fits(&Value::detect(field)) {
+                return Err(Error::Value {
+                    column: idx,
+                    kind: column.kind(),
+                    value: field.clone(),
+                });
+            }
+        }
+        for (column, field) in self.columns.iter_mut().zip(fields) {
+            column.data.push(field);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends the rows of other columns, keeping the names of these ones.
+    ///
+    /// Columns must be of the same type, except that integers and floats
+    /// make floats.
+    ///
+    /// # Errors
+    ///
+    /// If the number of columns differs, or a column cannot be appended;
+    /// the columns are then left unchanged.
+    pub fn append(&mut self, other: Self) -> Result<(), Error> {
+        if self.columns.is_empty() && self.len == 0 {
+            *self = other;
+            return Ok(());
+        }
+        if other.width() != self.width() {
+            return Err(Error::Width {
+                expected: self.width(),
+                actual: other.width(),
+            });
+        }
+        for (idx, (column, other)) in self.columns.iter().zip(&other.columns).enumerate() {
+            let (kind, other) = (column.kind(), other.kind());
+            if kind != other && !(kind.is_numeric() && other.is_numeric()) {
+                return Err(Error::Append {
+                    column: idx,
+                    kind,
+                    other,
+                });
+            }
+        }
+        for (column, other) in self.columns.iter_mut().zip(other.columns) {
+            column.data.append(other.data);
+        }
+        self.len += other.len;
+        Ok(())
+    }
+
+    /// The rows in the range, copied.
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    #[must_use]
+    pub fn slice(&self, rows: Range<usize>) -> Self {
+        assert!(
+            rows.start <= rows.end && rows.end <= self.len,
+            "rows {rows:?} out of bounds of {} rows",
+            self.len
+        );
+        Self {
+            columns: self
+                .columns
+                .iter()
+                .map(|column| Column::new(column.name.clone(), column.data.slice(rows.clone())))
+                .collect(),
+            len: rows.len(),
+        }
+    }
+
+    /// The given columns, copied, in the given order.
+    ///
+    /// # Errors
+    ///
+    /// If a column is referenced by a name no column has, or is out of
+    /// bounds.
+    pub fn project(&self, columns: &[column::Column]) -> Result<Self, Error> {
+        let header = self.header();
+        let columns = columns
+            .iter()
+            .map(|column| {
+                let idx = column.resolve(header.as_deref())?;
+                self.columns.get(idx).cloned().ok_or(Error::OutOfBounds {
+                    column: idx,
+                    width: self.width(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            columns,
+            len: self.len,
+        })
+    }
+
+    /// Types every text column, see [`Column::infer`].
+    pub fn infer(&mut self) {
+        for column in &mut self.columns {
+            column.infer();
+        }
+    }
+}
+
+impl Column {
+    #[inline]
+    #[must_use]
+    pub const fn new(name: Option<String>, data: Data) -> Self {
+        Self { name, data }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn data(&self) -> &Data {
+        &self.data
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_data(self) -> Data {
+        self.data
+    }
+
+    /// The type of the values, text columns being text even if their fields
+    /// are all empty or numbers.
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> Type {
+        match self.data {
+            Data::Text(_) => Type::Text,
+            Data::Boolean(_) => Type::Boolean,
+            Data::Integer(_) => Type::Integer,
+            Data::Float(_) => Type::Float,
+        }
+    }
+
+    /// Number of values.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match &self.data {
+            Data::Text(values) => values.len(),
+            Data::Boolean(values) => values.len(),
+            Data::Integer(values) => values.len(),
+            Data::Float(values) => values.len(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value at the row, fields of text columns always being text.
+    #[must_use]
+    pub fn get(&self, row: usize) -> Option<Value<'_>> {
+        Some(match &self.data {
+            Data::Text(values) => Value::Text(values.get(row)?),
+            Data::Boolean(values) => values.get(row)?.map_or(Value::Empty, Value::Boolean),
+            Data::Integer(values) => values.get(row)?.map_or(Value::Empty, Value::Integer),
+            Data::Float(values) => values.get(row)?.map_or(Value::Empty, Value::Float),
+        })
+    }
+
+    /// Types a text column as the common type of its values, see
+    /// [`Value::detect`] and [`Type::merge`], leaving it as text if that is
+    /// text or if every field is empty. Empty fields become `None`.
+    pub fn infer(&mut self) {
+        let Data::Text(values) = &mut self.data else {
+            return;
+        };
+        let kind = values.iter().fold(Type::Empty, |kind, value| {
+            kind.merge(Value::detect(value).kind())
+        });
+        let values = mem::take(values).into_iter();
+        self.data = match kind {
+            Type::Boolean => Data::Boolean(
+                values
+                    .map(|value| match Value::detect(&value) {
+                        Value::Boolean(boolean) => Some(boolean),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            Type::Integer => Data::Integer(
+                values
+                    .map(|value| match Value::detect(&value) {
+                        Value::Integer(integer) => Some(integer),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            Type::Float => {
+                Data::Float(values.map(|value| Value::detect(&value).as_f64()).collect())
+            }
+            Type::Empty | Type::Text => Data::Text(values.collect()),
+        };
+    }
+}
+
+impl Data {
+    /// Whether a value can be pushed onto the column.
+    fn fits(&self, value: &Value<'_>) -> bool {
+        matches!(
+            (self, value),
+            (Self::Text(_), _)
+                | (_, Value::Empty)
+                | (Self::Boolean(_), Value::Boolean(_))
+                | (Self::Integer(_), Value::Integer(_))
+                | (Self::Float(_), Value::Integer(_) | Value::Float(_))
+        )
+    }
+
+    /// Pushes a field that [`Self::fits`].
+    fn push(&mut self, field: String) {
+        match self {
+            Self::Text(values) => values.push(field),
+            Self::Boolean(values) => values.push(match Value::detect(&field) {
+                Value::Boolean(boolean) => Some(boolean),
+                _ => None,
+            }),
+            Self::Integer(values) => values.push(match Value::detect(&field) {
+                Value::Integer(integer) => Some(integer),
+                _ => None,
+            }),
+            Self::Float(values) => values.push(Value::detect(&field).as_f64()),
+        }
+    }
+
+    /// Appends values of the same type, or of integers and floats.
+    #[allow(clippy::cast_precision_loss)]
+    fn append(&mut self, other: Self) {
+        match (self, other) {
+            (Self::Text(values), Self::Text(other)) => values.extend(other),
+            (Self::Boolean(values), Self::Boolean(other)) => values.extend(other),
+            (Self::Integer(values), Self::Integer(other)) => values.extend(other),
+            (Self::Float(values), Self::Float(other)) => values.extend(other),
+            (Self::Float(values), Self::Integer(other)) => {
+                values.extend(
+                    other
+                        .into_iter()
+                        .map(|value| value.map(|value| value as f64)),
+                );
+            }
+            (this @ Self::Integer(_), Self::Float(other)) => {
+                let Self::Integer(values) = mem::replace(this, Self::Float(Vec::new())) else {
+                    unreachable!()
+                };
+                *this = Self::Float(
+                    values
+                        .into_iter()
+                        .map(|value| value.map(|value| value as f64))
+                        .chain(other)
+                        .collect(),
+                );
+            }
+            _ => unreachable!("appending columns of different types"),
+        }
+    }
+
+    fn slice(&self, rows: Range<usize>) -> Self {
+        match self {
+            Self::Text(values) => Self::Text(values[rows].to_vec()),
+            Self::Boolean(values) => Self::Boolean(values[rows].to_vec()),
+            Self::Integer(values) => Self::Integer(values[rows].to_vec()),
+            Self::Float(values) => Self::Float(values[rows].to_vec()),
+        }
+    }
+}
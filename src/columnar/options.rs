@@ -0,0 +1,104 @@
+//! Columnar parsing, [`parse`] options.
+//!
+//! [`parse`]: super::parse
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_infer(self, infer: bool) -> Self;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+    header: bool,
+    infer: bool,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        header: true,
+        infer: false,
+    };
+
+    /// Whether the first record is a header naming the columns.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    /// Whether columns are typed once read, see
+    /// [`Column::infer`](super::Column::infer).
+    #[inline]
+    #[must_use]
+    pub const fn infer(&self) -> bool {
+        self.infer
+    }
+
+    #[inline]
+    pub fn infer_mut(&mut self) -> &mut bool {
+        &mut self.infer
+    }
+
+    #[inline]
+    pub fn set_infer(&mut self, infer: bool) {
+        self.infer = infer;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_infer(self, infer: bool) -> Self {
+        self.set_infer(infer);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_infer(mut self, infer: bool) -> Self {
+        self.set_infer(infer);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
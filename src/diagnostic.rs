@@ -0,0 +1,498 @@
+//! Rendering errors and warnings for people, [`Diagnostic`].
+//!
+//! A diagnostic shows the line of the input a problem is on, underlining
+//! where it is, along with how it may be resolved and the record and field
+//! it is in:
+//!
+//! ```text
+//! error[CSV005]: quote in unquoted string
+//!  --> data.csv:3:4 (record 3, field 2)
+//!   |
+//! 3 | a,b"c,d
+//!   |    ^
+//!   = help: a field must be quoted to contain quotes, which are escaped by doubling them
+//! ```
+//!
+//! Diagnostics can also be written as JSON for programs, see
+//! [`Diagnostic::to_json`].
+//!
+//! # Codes
+//!
+//! Every kind of error and warning has a stable code, which is never reused
+//! for another kind even if the kind is removed:
+//!
+//! | Codes         | Kinds                                        |
+//! |---------------|----------------------------------------------|
+//! | `CSV001`–`049` | [`tokenizer::ErrorKind`]                    |
+//! | `CSV051`–`099` | [`tokenizer::WarningKind`]                  |
+//! | `CSV101`–`199` | [`recorder::ErrorKind`]                     |
+//! | `CSV201`–`299` | [`tokenizer::options::ErrorKind`]           |
+//! | `CSV301`–`399` | [`ParseError`] of whole records             |
+//!
+//! [`tokenizer::ErrorKind`]: crate::tokenizer::ErrorKind
+//! [`tokenizer::WarningKind`]: crate::tokenizer::WarningKind
+//! [`recorder::ErrorKind`]: crate::recorder::ErrorKind
+//! [`tokenizer::options::ErrorKind`]: crate::tokenizer::options::ErrorKind
+
+use std::{fmt, iter, ops::Range};
+
+use serde_json::{json, Map, Value};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    location::{FieldLocation, Locator},
+    tokenizer::{options, Warning},
+    ParseError,
+};
+
+/// Number of columns of the line shown around the span.
+const WIDTH: usize = 100;
+
+/// Number of columns of the line shown before the span when it is cut.
+const LEAD: usize = 40;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display, strum::EnumIs)]
+pub enum Severity {
+    #[strum(to_string = "error")]
+    Error,
+    #[strum(to_string = "warning")]
+    Warning,
+}
+
+/// A problem with an input, to be rendered by a [`Renderer`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: Option<&'static str>,
+    message: String,
+    hint: Option<String>,
+    /// The characters at fault; an empty span points between characters.
+    span: Option<Range<usize>>,
+    field: Option<FieldLocation>,
+    /// Further facts about the problem, such as expected and actual values.
+    details: Map<String, Value>,
+}
+
+/// The input a diagnostic is about.
+#[derive(Clone, Debug)]
+pub struct Source<'a> {
+    name: &'a str,
+    text: Option<(&'a str, Locator)>,
+}
+
+/// Renders diagnostics as text, colored with ANSI escape codes if asked to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Renderer {
+    color: bool,
+}
+
+/// ANSI escape codes of a renderer, empty when not coloring.
+struct Style {
+    severity: &'static str,
+    emphasis: &'static str,
+    gutter: &'static str,
+    reset: &'static str,
+}
+
+impl Diagnostic {
+    #[inline]
+    #[must_use]
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            hint: None,
+            span: None,
+            field: None,
+            details: Map::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_field(mut self, field: FieldLocation) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_detail(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.details.insert(name.into(), value.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// The stable code of the kind of problem, see [the module
+    /// documentation](self#codes).
+    #[inline]
+    #[must_use]
+    pub const fn code(&self) -> Option<&'static str> {
+        self.code
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    /// The character indices at fault.
+    #[inline]
+    #[must_use]
+    pub const fn span(&self) -> Option<&Range<usize>> {
+        self.span.as_ref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn field(&self) -> Option<FieldLocation> {
+        self.field
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn details(&self) -> &Map<String, Value> {
+        &self.details
+    }
+
+    /// The diagnostic as a JSON object, with the position in the source, if
+    /// it is about one, given as a character index and, when known, as
+    /// one-based line, column, record and field numbers:
+    ///
+    /// ```json
+    /// {
+    ///   "severity": "error",
+    ///   "code": "CSV005",
+    ///   "message": "quote in unquoted string",
+    ///   "hint": "a field must be quoted to contain quotes, which are escaped by doubling them",
+    ///   "source": "data.csv",
+    ///   "position": { "index": 13, "line": 3, "column": 4, "record": 3, "field": 2 },
+    ///   "details": {}
+    /// }
+    /// ```
+    #[must_use]
+    pub fn to_json(&self, source: Option<&Source<'_>>) -> Value {
+        let position = self.span.as_ref().map(|span| {
+            let mut position = Map::new();
+            position.insert("index".to_owned(), span.start.into());
+            if let Some((_, locator)) = source.and_then(|source| source.text.as_ref()) {
+                let location = locator.locate(span.start);
+                position.insert("line".to_owned(), location.line.into());
+                position.insert("column".to_owned(), location.column.into());
+            }
+            if let Some(field) = self.field {
+                position.insert("record".to_owned(), field.record.into());
+                position.insert("field".to_owned(), field.field.into());
+            }
+            position
+        });
+        json!({
+            "severity": self.severity.to_string(),
+            "code": self.code,
+            "message": self.message,
+            "hint": self.hint,
+            "source": source.map(|source| source.name),
+            "position": position,
+            "details": self.details,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.severity)?;
+        if let Some(code) = self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    /// A diagnostic at the error, except for [`ParseError::JaggedRecords`]
+    /// which does not know where the record is.
+    fn from(value: &ParseError) -> Self {
+        let (message, hint, max) = match value {
+            ParseError::Tokenizer(cause) => (
+                cause.kind().message(),
+                cause.kind().hint(),
+                cause.kind().max(),
+            ),
+            ParseError::Recorder(cause) => (
+                cause.kind().message(),
+                cause.kind().hint(),
+                cause.kind().max(),
+            ),
+            ParseError::JaggedRecords { .. } => (value.to_string(), None, None),
+        };
+        let mut diagnostic = Self::new(value.severity(), message).with_code(value.code());
+        diagnostic.span = value.at().map(|at| at..at + 1);
+        diagnostic.hint = hint.map(str::to_owned);
+        if let Some(max) = max {
+            diagnostic = diagnostic.with_detail("expected", max);
+        }
+        if let ParseError::JaggedRecords {
+            expected,
+            at,
+            actual,
+        } = value
+        {
+            diagnostic = diagnostic
+                .with_detail("expected", *expected)
+                .with_detail("actual", *actual)
+                .with_detail("record", at + 1);
+        }
+        diagnostic
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(value: &Warning) -> Self {
+        Self::new(value.kind().severity(), value.kind().to_string())
+            .with_code(value.kind().code())
+            .with_span(*value.at()..*value.at() + 1)
+    }
+}
+
+impl From<&options::Error> for Diagnostic {
+    fn from(value: &options::Error) -> Self {
+        Self::new(value.kind().severity(), value.kind().to_string())
+            .with_code(value.kind().code())
+            .with_detail("character", value.kind().ch().to_string())
+    }
+}
+
+impl<'a> Source<'a> {
+    /// An input whose text is at hand, so that lines can be shown.
+    #[must_use]
+    pub fn new(name: &'a str, text: &'a str) -> Self {
+        Self {
+            name,
+            text: Some((text, Locator::new(text))),
+        }
+    }
+
+    /// An input that can no longer be read, such as the standard input, so
+    /// that positions are shown as character indices.
+    #[inline]
+    #[must_use]
+    pub const fn unread(name: &'a str) -> Self {
+        Self { name, text: None }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> &str {
+        self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_ref().map(|(text, _)| *text)
+    }
+}
+
+impl Renderer {
+    /// Renders without color.
+    pub const PLAIN: Self = Self { color: false };
+
+    /// Renders with color.
+    pub const COLORED: Self = Self { color: true };
+
+    #[inline]
+    #[must_use]
+    pub const fn new(color: bool) -> Self {
+        Self { color }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn color(&self) -> bool {
+        self.color
+    }
+
+    #[inline]
+    pub fn set_color(&mut self, color: bool) {
+        self.color = color;
+    }
+
+    /// Renders the diagnostic, ending with a line break.
+    #[must_use]
+    pub fn render(&self, diagnostic: &Diagnostic, source: &Source<'_>) -> String {
+        let mut out = String::new();
+        self.write(&mut out, diagnostic, source)
+            .expect("writing to a string");
+        out
+    }
+
+    /// Writes the diagnostic, ending with a line break.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn write(
+        &self,
+        out: &mut impl fmt::Write,
+        diagnostic: &Diagnostic,
+        source: &Source<'_>,
+    ) -> fmt::Result {
+        let Style {
+            severity,
+            emphasis,
+            gutter,
+            reset,
+        } = self.style(diagnostic.severity);
+        write!(out, "{severity}{}", diagnostic.severity)?;
+        if let Some(code) = diagnostic.code {
+            write!(out, "[{code}]")?;
+        }
+        writeln!(out, "{reset}{emphasis}: {}{reset}", diagnostic.message)?;
+
+        let snippet = match (&source.text, &diagnostic.span) {
+            (Some((text, locator)), Some(span)) => {
+                let location = locator.locate(span.start);
+                locator
+                    .line(text, location.line)
+                    .map(|line| (location, line))
+            }
+            _ => None,
+        };
+        let number = snippet
+            .as_ref()
+            .map(|(location, _)| location.line.to_string())
+            .unwrap_or_default();
+        let pad = " ".repeat(number.len().max(1));
+
+        write!(out, "{pad}{gutter}-->{reset} {}", source.name)?;
+        match (&snippet, &diagnostic.span) {
+            (Some((location, _)), _) => write!(out, ":{location}")?,
+            (None, Some(span)) => write!(out, ", character {}", span.start)?,
+            (None, None) => {}
+        }
+        match diagnostic.field {
+            Some(field) => writeln!(out, " ({field})")?,
+            None => writeln!(out)?,
+        }
+
+        if let (Some((location, line)), Some(span)) = (&snippet, &diagnostic.span) {
+            let start = location.column - 1;
+            let len = span.len().max(1);
+            let (shown, offset, width) = excerpt(line, start, len);
+            writeln!(out, "{pad} {gutter}|{reset}")?;
+            writeln!(out, "{gutter}{number} |{reset} {shown}")?;
+            writeln!(
+                out,
+                "{pad} {gutter}|{reset} {}{severity}{}{reset}",
+                " ".repeat(offset),
+                "^".repeat(width)
+            )?;
+        }
+
+        if let Some(hint) = &diagnostic.hint {
+            let mut lines = hint.lines();
+            let first = lines.next().unwrap_or_default();
+            writeln!(out, "{pad} {gutter}={reset} {emphasis}help{reset}: {first}")?;
+            for line in lines {
+                writeln!(out, "{pad}         {line}")?;
+            }
+        }
+        Ok(())
+    }
+
+    const fn style(self, severity: Severity) -> Style {
+        if !self.color {
+            return Style {
+                severity: "",
+                emphasis: "",
+                gutter: "",
+                reset: "",
+            };
+        }
+        Style {
+            severity: match severity {
+                Severity::Error => "\x1b[1;31m",
+                Severity::Warning => "\x1b[1;33m",
+            },
+            emphasis: "\x1b[1m",
+            gutter: "\x1b[1;34m",
+            reset: "\x1b[0m",
+        }
+    }
+}
+
+/// The part of a line to show for `len` characters from `start`, with the
+/// columns before the span and the columns of the span.
+///
+/// Control characters are shown as their pictures, so that they neither
+/// disturb the terminal nor the alignment, and long lines are cut around
+/// the span.
+fn excerpt(line: &str, start: usize, len: usize) -> (String, usize, usize) {
+    let shown = line.chars().map(|ch| match ch {
+        '\0'..='\x1f' => char::from_u32(0x2400 + u32::from(ch)).expect("control picture"),
+        '\x7f' => '\u{2421}',
+        _ => ch,
+    });
+    // The column at which every character starts, and the line ends.
+    let columns = iter::once(0)
+        .chain(line.chars().scan(0, |column, ch| {
+            *column += ch.width().unwrap_or(1);
+            Some(*column)
+        }))
+        .collect::<Vec<_>>();
+    let start = start.min(columns.len() - 1);
+    // Cut whole characters until the span is within the lead.
+    let skip = columns.partition_point(|column| column + LEAD < columns[start]);
+
+    let mut excerpt = String::new();
+    let mut offset = columns[start] - columns[skip];
+    if skip > 0 {
+        excerpt.push('…');
+        offset += 1;
+    }
+    for (ch, end) in shown.zip(&columns[1..]).skip(skip) {
+        if end - columns[skip] > WIDTH {
+            excerpt.push('…');
+            break;
+        }
+        excerpt.push(ch);
+    }
+    // The span may go past the end of the line, as at a line break.
+    let end = (start + len).min(columns.len() - 1);
+    let width = (columns[end] - columns[start]).clamp(1, WIDTH + 1 - offset.min(WIDTH));
+    (excerpt, offset, width)
+}
@@ -0,0 +1,404 @@
+//! Differences between two tables, [`diff`] and [`Diff`].
+//!
+//! Records are matched either by key columns or by position. A [`Diff`]
+//! displays as a human-readable report, can be written as a patch, see
+//! [`patch`], and can be applied to the old table to get the new one.
+
+pub mod patch;
+
+use std::{collections::HashMap, fmt, io};
+
+use crate::{
+    column::{self, Column},
+    records::Record,
+    ParseError,
+};
+
+/// The changes turning an old table into a new one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Diff {
+    header: Option<Vec<String>>,
+    changes: Vec<Change>,
+}
+
+/// A change to a record, whose rows are zero-based and exclude the header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// A new record at row `new` of the new table.
+    Added { new: usize, fields: Vec<String> },
+    /// An old record at row `old` of the old table.
+    Removed { old: usize, fields: Vec<String> },
+    /// A record at row `old` of the old table and row `new` of the new one,
+    /// whose cells differ.
+    Modified {
+        old: usize,
+        new: usize,
+        cells: Vec<Cell>,
+    },
+    /// An unchanged record at row `old` of the old table and row `new` of
+    /// the new one, out of order with the other unchanged records.
+    Moved { old: usize, new: usize },
+}
+
+/// A changed cell of a modified record.
+///
+/// Missing fields of short records compare as if empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cell {
+    pub column: usize,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Column(#[from] column::Error),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the headers differ")]
+    HeaderMismatch,
+    #[error("the key {key:?} is repeated at row {}", row + 1)]
+    DuplicateKey { key: Vec<String>, row: usize },
+    #[error("invalid patch at {at}: {reason}")]
+    Patch { at: usize, reason: &'static str },
+    #[error("row {} does not match the patch", row + 1)]
+    Conflict { row: usize },
+}
+
+/// Compares two tables, matching records by the given key columns, or by
+/// position when there are none.
+///
+/// With `header`, the first record of each table is a header, which must be
+/// the same for both and to which names in the keys refer.
+///
+/// Keyed changes come in the order of the new table, followed by removals in
+/// the order of the old table. Unchanged records are reported as moved when
+/// needed to restore the order of the new table, as few as possible.
+///
+/// # Errors
+///
+/// If a record fails to parse, the headers differ, a key column cannot be
+/// resolved, or a key is repeated within a table.
+pub fn diff<O, N>(old: O, new: N, keys: &[Column], header: bool) -> Result<Diff, Error>
+where
+    O: Iterator<Item = Result<Record, ParseError>>,
+    N: Iterator<Item = Result<Record, ParseError>>,
+{
+    let mut old = old
+        .map(|record| record.map(Record::into_fields))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut new = new
+        .map(|record| record.map(Record::into_fields))
+        .collect::<Result<Vec<_>, _>>()?;
+    let header = if header {
+        let old_header = (!old.is_empty()).then(|| old.remove(0));
+        let new_header = (!new.is_empty()).then(|| new.remove(0));
+        match (old_header, new_header) {
+            (Some(old_header), Some(new_header)) if old_header != new_header => {
+                return Err(Error::HeaderMismatch)
+            }
+            (old_header, new_header) => new_header.or(old_header),
+        }
+    } else {
+        None
+    };
+
+    let changes = if keys.is_empty() {
+        by_position(old, new)
+    } else {
+        let keys = keys
+            .iter()
+            .map(|key| key.resolve(header.as_deref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        by_key(old, new, &keys)?
+    };
+    Ok(Diff { header, changes })
+}
+
+fn by_position(old: Vec<Vec<String>>, new: Vec<Vec<String>>) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut old = old.into_iter().enumerate();
+    let mut new = new.into_iter().enumerate();
+    loop {
+        match (old.next(), new.next()) {
+            (None, None) => return changes,
+            (Some((row, fields)), None) => changes.push(Change::Removed { old: row, fields }),
+            (None, Some((row, fields))) => changes.push(Change::Added { new: row, fields }),
+            (Some((row, old_fields)), Some((_, new_fields))) => {
+                let cells = cells(&old_fields, &new_fields);
+                if !cells.is_empty() {
+                    changes.push(Change::Modified {
+                        old: row,
+                        new: row,
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn by_key(
+    old: Vec<Vec<String>>,
+    new: Vec<Vec<String>>,
+    keys: &[usize],
+) -> Result<Vec<Change>, Error> {
+    let key = |fields: &[String]| {
+        keys.iter()
+            .map(|idx| fields.get(*idx).cloned().unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    let mut index = index(&old, key)?;
+    // Only checked for repeated keys.
+    self::index(&new, key)?;
+
+    let mut changes = Vec::new();
+    let mut unchanged = Vec::new();
+    for (row, fields) in new.into_iter().enumerate() {
+        match index.remove(&key(&fields)) {
+            Some(old_row) => {
+                let cells = cells(&old[old_row], &fields);
+                if cells.is_empty() {
+                    unchanged.push((row, old_row));
+                } else {
+                    changes.push(Change::Modified {
+                        old: old_row,
+                        new: row,
+                        cells,
+                    });
+                }
+            }
+            None => changes.push(Change::Added { new: row, fields }),
+        }
+    }
+    let in_order = increasing(&unchanged.iter().map(|(_, old)| *old).collect::<Vec<_>>());
+    changes.extend(
+        unchanged
+            .into_iter()
+            .zip(in_order)
+            .filter(|(_, in_order)| !in_order)
+            .map(|((new, old), _)| Change::Moved { old, new }),
+    );
+    changes.sort_by_key(|change| match change {
+        Change::Added { new, .. } | Change::Modified { new, .. } | Change::Moved { new, .. } => {
+            *new
+        }
+        Change::Removed { old, .. } => *old,
+    });
+    let mut removed = index.into_values().collect::<Vec<_>>();
+    removed.sort_unstable();
+    let mut old = old.into_iter().map(Some).collect::<Vec<_>>();
+    changes.extend(removed.into_iter().map(|row| Change::Removed {
+        old: row,
+        fields: old[row].take().unwrap_or_default(),
+    }));
+    Ok(changes)
+}
+
+fn index<F>(table: &[Vec<String>], key: F) -> Result<HashMap<Vec<String>, usize>, Error>
+where
+    F: Fn(&[String]) -> Vec<String>,
+{
+    let mut index = HashMap::with_capacity(table.len());
+    for (row, fields) in table.iter().enumerate() {
+        let key = key(fields);
+        if index.contains_key(&key) {
+            return Err(Error::DuplicateKey { key, row });
+        }
+        index.insert(key, row);
+    }
+    Ok(index)
+}
+
+/// Marks a longest strictly increasing subsequence of the values.
+fn increasing(values: &[usize]) -> Vec<bool> {
+    // Indices of the smallest last value of an increasing subsequence of
+    // each length, and the index before each value in its subsequence.
+    let mut tails = Vec::<usize>::new();
+    let mut previous = vec![None; values.len()];
+    for (idx, value) in values.iter().enumerate() {
+        let len = tails.partition_point(|tail| values[*tail] < *value);
+        previous[idx] = len.checked_sub(1).map(|len| tails[len]);
+        if len == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[len] = idx;
+        }
+    }
+    let mut marked = vec![false; values.len()];
+    let mut next = tails.last().copied();
+    while let Some(idx) = next {
+        marked[idx] = true;
+        next = previous[idx];
+    }
+    marked
+}
+
+fn cells(old: &[String], new: &[String]) -> Vec<Cell> {
+    fn field(fields: &[String], idx: usize) -> &str {
+        fields.get(idx).map_or("", String::as_str)
+    }
+
+    (0..old.len().max(new.len()))
+        .filter(|idx| field(old, *idx) != field(new, *idx))
+        .map(|idx| Cell {
+            column: idx,
+            old: field(old, idx).to_owned(),
+            new: field(new, idx).to_owned(),
+        })
+        .collect()
+}
+
+impl Diff {
+    #[inline]
+    #[must_use]
+    pub const fn new(header: Option<Vec<String>>, changes: Vec<Change>) -> Self {
+        Self { header, changes }
+    }
+
+    /// The header shared by both tables, used to name columns in the report.
+    #[inline]
+    #[must_use]
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_changes(self) -> Vec<Change> {
+        self.changes
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Applies the changes to the records of the old table, without its
+    /// header.
+    ///
+    /// Removed and modified records are checked against the old table.
+    /// Added, modified and moved records are placed at their rows of the new
+    /// table, and the other records fill the rows left in between, keeping
+    /// their order.
+    ///
+    /// # Errors
+    ///
+    /// If a removed, modified or moved record is missing from the old table
+    /// or differs from what the patch expects, or the rows of the new table
+    /// cannot all be filled.
+    pub fn apply(&self, old: Vec<Vec<String>>) -> Result<Vec<Vec<String>>, Error> {
+        let mut rows = old.into_iter().map(Some).collect::<Vec<_>>();
+        let mut placed = Vec::new();
+        for change in &self.changes {
+            match change {
+                Change::Added { new, fields } => placed.push((*new, fields.clone())),
+                Change::Removed { old, fields } => match rows.get_mut(*old) {
+                    Some(row) if row.as_ref() == Some(fields) => *row = None,
+                    _ => return Err(Error::Conflict { row: *old }),
+                },
+                Change::Modified { old, new, cells } => {
+                    let Some(mut row) = rows.get_mut(*old).and_then(Option::take) else {
+                        return Err(Error::Conflict { row: *old });
+                    };
+                    for cell in cells {
+                        if row.get(cell.column).map_or("", String::as_str) != cell.old {
+                            return Err(Error::Conflict { row: *old });
+                        }
+                        if row.len() <= cell.column {
+                            row.resize(cell.column + 1, String::new());
+                        }
+                        row[cell.column].clone_from(&cell.new);
+                    }
+                    placed.push((*new, row));
+                }
+                Change::Moved { old, new } => match rows.get_mut(*old).and_then(Option::take) {
+                    Some(row) => placed.push((*new, row)),
+                    None => return Err(Error::Conflict { row: *old }),
+                },
+            }
+        }
+
+        let kept = rows.into_iter().flatten().collect::<Vec<_>>();
+        let len = kept.len() + placed.len();
+        let mut kept = kept.into_iter();
+        placed.sort_by_key(|(row, _)| *row);
+        let mut placed = placed.into_iter().peekable();
+        let mut table = Vec::with_capacity(len);
+        while table.len() < len {
+            let row = table.len();
+            match placed.next_if(|(new, _)| *new == row) {
+                Some((_, fields)) => table.push(fields),
+                None => table.push(kept.next().ok_or(Error::Conflict { row })?),
+            }
+        }
+        match placed.next() {
+            Some((row, _)) => Err(Error::Conflict { row }),
+            None => Ok(table),
+        }
+    }
+
+    fn column_name(&self, column: usize) -> String {
+        self.header
+            .as_ref()
+            .and_then(|header| header.get(column))
+            .cloned()
+            .unwrap_or_else(|| Column::Index(column).to_string())
+    }
+}
+
+/// A report listing each change, followed by a summary.
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut added, mut removed, mut modified, mut moved) = (0, 0, 0, 0);
+        for change in &self.changes {
+            match change {
+                Change::Added { new, fields } => {
+                    added += 1;
+                    writeln!(f, "+ row {}: {fields:?}", new + 1)?;
+                }
+                Change::Removed { old, fields } => {
+                    removed += 1;
+                    writeln!(f, "- row {}: {fields:?}", old + 1)?;
+                }
+                Change::Moved { old, new } => {
+                    moved += 1;
+                    writeln!(f, "> row {} (now {})", old + 1, new + 1)?;
+                }
+                Change::Modified { old, new, cells } => {
+                    modified += 1;
+                    if old == new {
+                        writeln!(f, "~ row {}:", old + 1)?;
+                    } else {
+                        writeln!(f, "~ row {} (now {}):", old + 1, new + 1)?;
+                    }
+                    for cell in cells {
+                        writeln!(
+                            f,
+                            "    {}: {:?} -> {:?}",
+                            self.column_name(cell.column),
+                            cell.old,
+                            cell.new
+                        )?;
+                    }
+                }
+            }
+        }
+        write!(
+            f,
+            "{added} added, {removed} removed, {modified} modified, {moved} moved"
+        )
+    }
+}
@@ -0,0 +1,326 @@
+//! Machine-readable patches of a [`Diff`], as CSV or JSON.
+//!
+//! A CSV patch has one record per changed cell, under the header
+//! `op,old,new,column,old_value,new_value`. `op` is `add`, `remove`,
+//! `modify` or `move`, and rows and columns are numbered from 1. Added and
+//! removed records list each of their cells, with an empty row on the side
+//! they are missing from, while moved records, and added and removed records
+//! without fields, have a single record without a column.
+//!
+//! A JSON patch is an array of objects such as
+//! `{"op": "add", "new": 3, "fields": ["a", "b"]}`,
+//! `{"op": "remove", "old": 2, "fields": ["c", "d"]}` and
+//! `{"op": "modify", "old": 1, "new": 1, "cells": [{"column": 2, "old": "e", "new": "f"}]}`
+//! and `{"op": "move", "old": 4, "new": 5}`.
+
+use std::io;
+
+use serde_json::{json, Value};
+
+use super::{Cell, Change, Diff, Error};
+use crate::{
+    records::Record,
+    writer::{self, Writer},
+    ParseError,
+};
+
+/// The header of a CSV patch.
+pub const HEADER: [&str; 6] = ["op", "old", "new", "column", "old_value", "new_value"];
+
+impl Diff {
+    /// Writes the changes as a CSV patch.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn write_patch<W>(&self, writer: &mut Writer<W>) -> Result<(), writer::Error>
+    where
+        W: io::Write,
+    {
+        let row = |row: &usize| (row + 1).to_string();
+        writer.write_record(HEADER)?;
+        for change in &self.changes {
+            match change {
+                Change::Added { new, fields } if fields.is_empty() => {
+                    writer.write_record(["add", "", &row(new), "", "", ""])?;
+                }
+                Change::Removed { old, fields } if fields.is_empty() => {
+                    writer.write_record(["remove", &row(old), "", "", "", ""])?;
+                }
+                Change::Added { new, fields } => {
+                    for (column, field) in fields.iter().enumerate() {
+                        let column = (column + 1).to_string();
+                        writer.write_record(["add", "", &row(new), &column, "", field])?;
+                    }
+                }
+                Change::Removed { old, fields } => {
+                    for (column, field) in fields.iter().enumerate() {
+                        let column = (column + 1).to_string();
+                        writer.write_record(["remove", &row(old), "", &column, field, ""])?;
+                    }
+                }
+                Change::Moved { old, new } => {
+                    writer.write_record(["move", &row(old), &row(new), "", "", ""])?;
+                }
+                Change::Modified { old, new, cells } => {
+                    for cell in cells {
+                        writer.write_record([
+                            "modify",
+                            &row(old),
+                            &row(new),
+                            &(cell.column + 1).to_string(),
+                            &cell.old,
+                            &cell.new,
+                        ])?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a CSV patch, such as written by [`Self::write_patch`].
+    ///
+    /// Consecutive records of the same operation and rows make up one
+    /// change, whose cells must come in column order for added and removed
+    /// records.
+    ///
+    /// # Errors
+    ///
+    /// If a record fails to parse or is not a valid patch record.
+    pub fn read_patch<I>(records: I) -> Result<Self, Error>
+    where
+        I: Iterator<Item = Result<Record, ParseError>>,
+    {
+        let mut changes = Vec::<Change>::new();
+        for (at, record) in records.enumerate() {
+            let fields = record?.into_fields();
+            if at == 0 && fields == HEADER {
+                continue;
+            }
+            let [op, old, new, column, old_value, new_value] = <[String; 6]>::try_from(fields)
+                .map_err(|_| Error::Patch {
+                    at,
+                    reason: "expected 6 fields",
+                })?;
+            let number = |field: &str| match field.parse::<usize>() {
+                Ok(number @ 1..) => Ok(number - 1),
+                _ => Err(Error::Patch {
+                    at,
+                    reason: "expected a number starting at 1",
+                }),
+            };
+            if op == "move" {
+                changes.push(Change::Moved {
+                    old: number(&old)?,
+                    new: number(&new)?,
+                });
+                continue;
+            }
+            if column.is_empty() && op == "add" {
+                changes.push(Change::Added {
+                    new: number(&new)?,
+                    fields: Vec::new(),
+                });
+                continue;
+            }
+            if column.is_empty() && op == "remove" {
+                changes.push(Change::Removed {
+                    old: number(&old)?,
+                    fields: Vec::new(),
+                });
+                continue;
+            }
+            let column = number(&column)?;
+            let in_order = |fields: &Vec<String>| {
+                if fields.len() == column {
+                    Ok(())
+                } else {
+                    Err(Error::Patch {
+                        at,
+                        reason: "cells are out of column order",
+                    })
+                }
+            };
+            match (op.as_str(), changes.last_mut()) {
+                ("add", last) => {
+                    let new = number(&new)?;
+                    match last {
+                        Some(Change::Added { new: row, fields }) if *row == new && column > 0 => {
+                            in_order(fields)?;
+                            fields.push(new_value);
+                        }
+                        _ => {
+                            in_order(&Vec::new())?;
+                            changes.push(Change::Added {
+                                new,
+                                fields: vec![new_value],
+                            });
+                        }
+                    }
+                }
+                ("remove", last) => {
+                    let old = number(&old)?;
+                    match last {
+                        Some(Change::Removed { old: row, fields }) if *row == old && column > 0 => {
+                            in_order(fields)?;
+                            fields.push(old_value);
+                        }
+                        _ => {
+                            in_order(&Vec::new())?;
+                            changes.push(Change::Removed {
+                                old,
+                                fields: vec![old_value],
+                            });
+                        }
+                    }
+                }
+                ("modify", last) => {
+                    let (old, new) = (number(&old)?, number(&new)?);
+                    let cell = Cell {
+                        column,
+                        old: old_value,
+                        new: new_value,
+                    };
+                    match last {
+                        Some(Change::Modified {
+                            old: old_row,
+                            new: new_row,
+                            cells,
+                        }) if *old_row == old && *new_row == new => cells.push(cell),
+                        _ => changes.push(Change::Modified {
+                            old,
+                            new,
+                            cells: vec![cell],
+                        }),
+                    }
+                }
+                _ => {
+                    return Err(Error::Patch {
+                        at,
+                        reason: "expected add, remove, modify or move",
+                    })
+                }
+            }
+        }
+        Ok(Self::new(None, changes))
+    }
+
+    /// The changes as a JSON patch.
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        self.changes
+            .iter()
+            .map(|change| match change {
+                Change::Added { new, fields } => {
+                    json!({ "op": "add", "new": new + 1, "fields": fields })
+                }
+                Change::Removed { old, fields } => {
+                    json!({ "op": "remove", "old": old + 1, "fields": fields })
+                }
+                Change::Moved { old, new } => {
+                    json!({ "op": "move", "old": old + 1, "new": new + 1 })
+                }
+                Change::Modified { old, new, cells } => json!({
+                    "op": "modify",
+                    "old": old + 1,
+                    "new": new + 1,
+                    "cells": cells
+                        .iter()
+                        .map(|cell| json!({
+                            "column": cell.column + 1,
+                            "old": cell.old,
+                            "new": cell.new,
+                        }))
+                        .collect::<Vec<_>>(),
+                }),
+            })
+            .collect()
+    }
+
+    /// Reads a JSON patch, such as made by [`Self::to_json`].
+    ///
+    /// # Errors
+    ///
+    /// If the input is not JSON, or not a valid patch.
+    pub fn from_json<R>(reader: R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        let Value::Array(elements) = serde_json::from_reader(reader)? else {
+            return Err(Error::Patch {
+                at: 0,
+                reason: "expected an array",
+            });
+        };
+        let changes = elements
+            .iter()
+            .enumerate()
+            .map(|(at, element)| change(at, element))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(None, changes))
+    }
+}
+
+fn change(at: usize, element: &Value) -> Result<Change, Error> {
+    let invalid = |reason| Error::Patch { at, reason };
+    let number = |value: &Value, key: &str| match value.get(key).and_then(Value::as_u64) {
+        Some(number @ 1..) => {
+            usize::try_from(number - 1).map_err(|_| invalid("expected a number starting at 1"))
+        }
+        _ => Err(invalid("expected a number starting at 1")),
+    };
+    let string = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .ok_or_else(|| invalid("expected a string"))
+    };
+    let fields = || {
+        element
+            .get("fields")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("expected an array of fields"))?
+            .iter()
+            .map(|field| {
+                field
+                    .as_str()
+                    .map(str::to_owned)
+                    .ok_or_else(|| invalid("expected a string"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match element.get("op").and_then(Value::as_str) {
+        Some("add") => Ok(Change::Added {
+            new: number(element, "new")?,
+            fields: fields()?,
+        }),
+        Some("remove") => Ok(Change::Removed {
+            old: number(element, "old")?,
+            fields: fields()?,
+        }),
+        Some("modify") => Ok(Change::Modified {
+            old: number(element, "old")?,
+            new: number(element, "new")?,
+            cells: element
+                .get("cells")
+                .and_then(Value::as_array)
+                .ok_or_else(|| invalid("expected an array of cells"))?
+                .iter()
+                .map(|cell| {
+                    Ok(Cell {
+                        column: number(cell, "column")?,
+                        old: string(cell, "old")?,
+                        new: string(cell, "new")?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        }),
+        Some("move") => Ok(Change::Moved {
+            old: number(element, "old")?,
+            new: number(element, "new")?,
+        }),
+        _ => Err(invalid("expected add, remove, modify or move")),
+    }
+}
@@ -0,0 +1,221 @@
+//! Record filtering by column predicates, [`Predicate`] and [`Filtered`].
+//!
+//! Records are tested one at a time as they are read, so filtering never
+//! holds more than a single record in memory.
+
+pub mod parse;
+
+use std::collections::HashSet;
+
+use regex::Regex;
+
+pub use crate::column::Column;
+use crate::{column, records::Record, value::Value, ParseError};
+
+/// A test on a single field.
+///
+/// Missing fields of short records are tested as if empty.
+#[derive(Clone, Debug)]
+pub enum Test {
+    Equal(String),
+    NotEqual(String),
+    Matches(Regex),
+    /// Compares the field as a number, never matching non-numeric fields.
+    Compare(Comparison, f64),
+    Empty,
+    NonEmpty,
+    In(HashSet<String>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Tests combined with and, or and not.
+#[derive(Clone, Debug)]
+pub enum Predicate {
+    Test(Column, Test),
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+    Not(Box<Predicate>),
+}
+
+/// Records matching a [`Predicate`].
+///
+/// When a header is expected, the first record is always kept and used to
+/// resolve column names.
+#[derive(Clone, Debug)]
+pub struct Filtered<I> {
+    iter: I,
+    predicate: Predicate,
+    header: bool,
+    bound: bool,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Column(#[from] column::Error),
+}
+
+/// Converting to a filtered csv record iterator.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = Result<Record, ParseError>> + Sized,
+{
+    #[inline]
+    fn csv_filter(self, predicate: Predicate, header: bool) -> Filtered<Self> {
+        Filtered {
+            iter: self,
+            predicate,
+            header,
+            bound: false,
+        }
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = Result<Record, ParseError>> {}
+
+impl Test {
+    #[must_use]
+    pub fn matches(&self, field: &str) -> bool {
+        match self {
+            Self::Equal(value) => field == value,
+            Self::NotEqual(value) => field != value,
+            Self::Matches(regex) => regex.is_match(field),
+            Self::Compare(comparison, value) => Value::detect(field)
+                .as_f64()
+                .is_some_and(|field| comparison.holds(field, *value)),
+            Self::Empty => field.is_empty(),
+            Self::NonEmpty => !field.is_empty(),
+            Self::In(values) => values.contains(field),
+        }
+    }
+}
+
+impl Comparison {
+    #[inline]
+    #[must_use]
+    pub fn holds(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Self::Less => lhs < rhs,
+            Self::LessOrEqual => lhs <= rhs,
+            Self::Greater => lhs > rhs,
+            Self::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+impl Predicate {
+    #[inline]
+    #[must_use]
+    pub const fn test(column: Column, test: Test) -> Self {
+        Self::Test(column, test)
+    }
+
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::And(mut predicates) => {
+                predicates.push(other);
+                Self::And(predicates)
+            }
+            predicate => Self::And(vec![predicate, other]),
+        }
+    }
+
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Or(mut predicates) => {
+                predicates.push(other);
+                Self::Or(predicates)
+            }
+            predicate => Self::Or(vec![predicate, other]),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn negate(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+
+    /// Resolves column names to indices.
+    ///
+    /// # Errors
+    ///
+    /// If a name is referenced without a header, or no column has that name.
+    pub fn bind(&mut self, header: Option<&[String]>) -> Result<(), Error> {
+        match self {
+            Self::Test(column, _) => {
+                *column = Column::Index(column.resolve(header)?);
+                Ok(())
+            }
+            Self::And(predicates) | Self::Or(predicates) => predicates
+                .iter_mut()
+                .try_for_each(|predicate| predicate.bind(header)),
+            Self::Not(predicate) => predicate.bind(header),
+        }
+    }
+
+    /// Tests the fields of a record.
+    ///
+    /// Tests on columns that are still named, see [`Self::bind`], never match.
+    #[must_use]
+    pub fn matches(&self, fields: &[String]) -> bool {
+        match self {
+            Self::Test(Column::Index(idx), test) => {
+                test.matches(fields.get(*idx).map_or("", String::as_str))
+            }
+            Self::Test(Column::Name(_), _) => false,
+            Self::And(predicates) => predicates.iter().all(|predicate| predicate.matches(fields)),
+            Self::Or(predicates) => predicates.iter().any(|predicate| predicate.matches(fields)),
+            Self::Not(predicate) => !predicate.matches(fields),
+        }
+    }
+}
+
+impl<I> Filtered<I> {
+    #[inline]
+    #[must_use]
+    pub const fn predicate(&self) -> &Predicate {
+        &self.predicate
+    }
+}
+
+impl<I> Iterator for Filtered<I>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.iter.next()? {
+                Ok(record) => record,
+                Err(cause) => return Some(Err(cause.into())),
+            };
+            if !self.bound {
+                self.bound = true;
+                let header = self.header.then_some(record.fields());
+                if let Err(cause) = self.predicate.bind(header) {
+                    return Some(Err(cause));
+                }
+                if self.header {
+                    return Some(Ok(record));
+                }
+            }
+            if self.predicate.matches(record.fields()) {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
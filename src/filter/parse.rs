@@ -0,0 +1,373 @@
+//! Parsing predicate expressions into a [`Predicate`].
+//!
+//! ```text
+//! expression := and ("or" and)*
+//! and        := unary ("and" unary)*
+//! unary      := "not" unary | "(" expression ")" | test
+//! test       := column ("==" | "=" | "!=") value
+//!             | column ("<" | "<=" | ">" | ">=") value
+//!             | column ("=~" | "!~" | "matches") value
+//!             | column "is" ["not"] "empty"
+//!             | column ["not"] "in" "(" value ("," value)* ")"
+//! column     := word | `name` | #number
+//! value      := word | "string" | 'string'
+//! ```
+//!
+//! Column numbers start at 1. Strings may escape their quote or a backslash
+//! with a backslash.
+
+use std::{collections::HashSet, str::FromStr};
+
+use regex::Regex;
+
+use super::{Column, Comparison, Predicate, Test};
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("invalid predicate at {at}: {kind}")]
+pub struct Error {
+    at: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, strum::Display)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    #[strum(to_string = "unterminated string")]
+    UnterminatedString,
+    #[strum(to_string = "unexpected character '{ch}'")]
+    UnexpectedChar { ch: char },
+    #[strum(to_string = "unexpected end of the predicate")]
+    UnexpectedEnd,
+    #[strum(to_string = "unexpected '{lexeme}'")]
+    Unexpected { lexeme: String },
+    #[strum(to_string = "'{value}' is not a number")]
+    NotANumber { value: String },
+    #[strum(to_string = "column numbers start at 1")]
+    Zero,
+    #[strum(to_string = "invalid regular expression: {message}")]
+    Regex { message: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Lexeme {
+    Word(String),
+    String(String),
+    Name(String),
+    Index(usize),
+    Operator(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+struct Parser {
+    lexemes: Vec<(usize, Lexeme)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Error {
+    #[inline]
+    #[must_use]
+    pub const fn new(at: usize, kind: ErrorKind) -> Self {
+        Self { at, kind }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn at(&self) -> &usize {
+        &self.at
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            lexemes: lex(s)?,
+            pos: 0,
+            end: s.chars().count(),
+        };
+        let predicate = parser.expression()?;
+        match parser.lexemes.get(parser.pos) {
+            Some((at, lexeme)) => Err(Error::new(
+                *at,
+                ErrorKind::Unexpected {
+                    lexeme: lexeme.to_string(),
+                },
+            )),
+            None => Ok(predicate),
+        }
+    }
+}
+
+const OPERATORS: [&str; 9] = ["==", "!=", "<=", ">=", "=~", "!~", "=", "<", ">"];
+
+fn lex(s: &str) -> Result<Vec<(usize, Lexeme)>, Error> {
+    let mut lexemes = Vec::new();
+    let mut chars = s.chars().enumerate().peekable();
+    while let Some(&(at, ch)) = chars.peek() {
+        let lexeme = match ch {
+            ch if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                match ch {
+                    '(' => Lexeme::Open,
+                    ')' => Lexeme::Close,
+                    _ => Lexeme::Comma,
+                }
+            }
+            '"' | '\'' | '`' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, ch)) => string.push(ch),
+                            None => return Err(Error::new(at, ErrorKind::UnterminatedString)),
+                        },
+                        Some((_, end)) if end == ch => break,
+                        Some((_, ch)) => string.push(ch),
+                        None => return Err(Error::new(at, ErrorKind::UnterminatedString)),
+                    }
+                }
+                if ch == '`' {
+                    Lexeme::Name(string)
+                } else {
+                    Lexeme::String(string)
+                }
+            }
+            '#' => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                    digits.push(ch);
+                }
+                match digits.parse::<usize>() {
+                    Ok(0) => return Err(Error::new(at, ErrorKind::Zero)),
+                    Ok(number) => Lexeme::Index(number - 1),
+                    Err(_) => return Err(Error::new(at, ErrorKind::NotANumber { value: digits })),
+                }
+            }
+            _ => {
+                let ahead = chars.clone().take(2).map(|(_, ch)| ch).collect::<String>();
+                if let Some(operator) = OPERATORS
+                    .iter()
+                    .find(|operator| ahead.starts_with(**operator))
+                {
+                    for _ in 0..operator.len() {
+                        chars.next();
+                    }
+                    Lexeme::Operator(operator)
+                } else if is_word(ch) {
+                    let mut word = String::new();
+                    while let Some((_, ch)) = chars.next_if(|(_, ch)| is_word(*ch)) {
+                        word.push(ch);
+                    }
+                    Lexeme::Word(word)
+                } else {
+                    return Err(Error::new(at, ErrorKind::UnexpectedChar { ch }));
+                }
+            }
+        };
+        lexemes.push((at, lexeme));
+    }
+    Ok(lexemes)
+}
+
+fn is_word(ch: char) -> bool {
+    !ch.is_whitespace() && !"()<>=!~,\"'`#".contains(ch)
+}
+
+impl std::fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Word(word) => f.write_str(word),
+            Self::String(string) => write!(f, "{string:?}"),
+            Self::Name(name) => write!(f, "`{name}`"),
+            Self::Index(idx) => write!(f, "#{}", idx + 1),
+            Self::Operator(operator) => f.write_str(operator),
+            Self::Open => f.write_str("("),
+            Self::Close => f.write_str(")"),
+            Self::Comma => f.write_str(","),
+        }
+    }
+}
+
+impl Parser {
+    fn expression(&mut self) -> Result<Predicate, Error> {
+        let mut predicate = self.and()?;
+        while self.keyword("or") {
+            predicate = predicate.or(self.and()?);
+        }
+        Ok(predicate)
+    }
+
+    fn and(&mut self) -> Result<Predicate, Error> {
+        let mut predicate = self.unary()?;
+        while self.keyword("and") {
+            predicate = predicate.and(self.unary()?);
+        }
+        Ok(predicate)
+    }
+
+    fn unary(&mut self) -> Result<Predicate, Error> {
+        if self.keyword("not") {
+            return Ok(self.unary()?.negate());
+        }
+        if self.next_if(&Lexeme::Open) {
+            let predicate = self.expression()?;
+            self.expect(&Lexeme::Close)?;
+            return Ok(predicate);
+        }
+        self.test()
+    }
+
+    fn test(&mut self) -> Result<Predicate, Error> {
+        let column = match self.next()? {
+            (_, Lexeme::Word(name) | Lexeme::Name(name)) => Column::Name(name),
+            (_, Lexeme::Index(idx)) => Column::Index(idx),
+            (at, lexeme) => {
+                return Err(Error::new(
+                    at,
+                    ErrorKind::Unexpected {
+                        lexeme: lexeme.to_string(),
+                    },
+                ))
+            }
+        };
+        let predicate = |test| Predicate::test(column.clone(), test);
+        let (at, lexeme) = self.next()?;
+        match lexeme {
+            Lexeme::Operator("==" | "=") => Ok(predicate(Test::Equal(self.value()?))),
+            Lexeme::Operator("!=") => Ok(predicate(Test::NotEqual(self.value()?))),
+            Lexeme::Operator(operator @ ("<" | "<=" | ">" | ">=")) => {
+                let comparison = match operator {
+                    "<" => Comparison::Less,
+                    "<=" => Comparison::LessOrEqual,
+                    ">" => Comparison::Greater,
+                    _ => Comparison::GreaterOrEqual,
+                };
+                let at = self.at();
+                let value = self.value()?;
+                let number = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::new(at, ErrorKind::NotANumber { value }))?;
+                Ok(predicate(Test::Compare(comparison, number)))
+            }
+            Lexeme::Operator("=~") => Ok(predicate(self.regex()?)),
+            Lexeme::Operator("!~") => Ok(predicate(self.regex()?).negate()),
+            Lexeme::Word(word) if word == "matches" => Ok(predicate(self.regex()?)),
+            Lexeme::Word(word) if word == "is" => {
+                let test = if self.keyword("not") {
+                    Test::NonEmpty
+                } else {
+                    Test::Empty
+                };
+                self.expect(&Lexeme::Word("empty".to_owned()))?;
+                Ok(predicate(test))
+            }
+            Lexeme::Word(word) if word == "in" => Ok(predicate(self.set()?)),
+            Lexeme::Word(word) if word == "not" => {
+                self.expect(&Lexeme::Word("in".to_owned()))?;
+                Ok(predicate(self.set()?).negate())
+            }
+            lexeme => Err(Error::new(
+                at,
+                ErrorKind::Unexpected {
+                    lexeme: lexeme.to_string(),
+                },
+            )),
+        }
+    }
+
+    fn value(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            (_, Lexeme::Word(value) | Lexeme::String(value)) => Ok(value),
+            (at, lexeme) => Err(Error::new(
+                at,
+                ErrorKind::Unexpected {
+                    lexeme: lexeme.to_string(),
+                },
+            )),
+        }
+    }
+
+    fn regex(&mut self) -> Result<Test, Error> {
+        let at = self.at();
+        let pattern = self.value()?;
+        Regex::new(&pattern).map(Test::Matches).map_err(|cause| {
+            Error::new(
+                at,
+                ErrorKind::Regex {
+                    message: cause.to_string(),
+                },
+            )
+        })
+    }
+
+    fn set(&mut self) -> Result<Test, Error> {
+        self.expect(&Lexeme::Open)?;
+        let mut values = HashSet::new();
+        loop {
+            values.insert(self.value()?);
+            if !self.next_if(&Lexeme::Comma) {
+                break;
+            }
+        }
+        self.expect(&Lexeme::Close)?;
+        Ok(Test::In(values))
+    }
+
+    fn at(&self) -> usize {
+        self.lexemes.get(self.pos).map_or(self.end, |(at, _)| *at)
+    }
+
+    fn next(&mut self) -> Result<(usize, Lexeme), Error> {
+        let lexeme = self
+            .lexemes
+            .get(self.pos)
+            .cloned()
+            .ok_or(Error::new(self.end, ErrorKind::UnexpectedEnd))?;
+        self.pos += 1;
+        Ok(lexeme)
+    }
+
+    fn next_if(&mut self, expected: &Lexeme) -> bool {
+        let matched = self
+            .lexemes
+            .get(self.pos)
+            .is_some_and(|(_, lexeme)| lexeme == expected);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.next_if(&Lexeme::Word(keyword.to_owned()))
+    }
+
+    fn expect(&mut self, expected: &Lexeme) -> Result<(), Error> {
+        match self.next()? {
+            (_, lexeme) if lexeme == *expected => Ok(()),
+            (at, lexeme) => Err(Error::new(
+                at,
+                ErrorKind::Unexpected {
+                    lexeme: lexeme.to_string(),
+                },
+            )),
+        }
+    }
+}
@@ -0,0 +1,559 @@
+//! Random access to records, [`Index`].
+//!
+//! An index holds the byte and character offsets of the start of every
+//! record, so that a reader can seek straight to a record and resume
+//! tokenizing from there, see [`tokenizer::Into::csv_tokens_at`]. It can be
+//! persisted to a sidecar file next to the indexed file, see
+//! [`sidecar_path`], and remembers the size and checksum of the file to tell
+//! when it is stale, checking only samples of the file when loaded so as not
+//! to read it all, and the encoding of the file to resume decoding at a
+//! record, see [`Chars::resume`], as well as the delimiter declared by a
+//! `sep=` directive, which is not read again when resuming.
+//!
+//! [`tokenizer::Into::csv_tokens_at`]: crate::tokenizer::Into::csv_tokens_at
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    chars::{Chars, Encoding},
+    tokenizer::{
+        self,
+        core::{self, Source},
+    },
+};
+
+/// The first bytes of an index file, ending with the format version.
+const MAGIC: &[u8; 8] = b"CSVIDX\x00\x04";
+
+/// Number of bytes checksummed at each sampled position.
+const SAMPLE_LEN: u64 = 4096;
+
+/// Most record starts sampled, besides the start and end of the input.
+const SAMPLE_RECORDS: usize = 64;
+
+/// The offsets of the start of every record.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Index {
+    size: u64,
+    checksum: u64,
+    /// The checksum of samples of the input, see [`Index::is_fresh`].
+    sample: u64,
+    encoding: Encoding,
+    delimiter: Option<char>,
+    offsets: Vec<Offset>,
+}
+
+/// Where a record starts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Offset {
+    pub byte: u64,
+    pub char: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Tokenizer(#[from] tokenizer::Error),
+    #[error("not an index file, or of an older version")]
+    Format,
+    #[error("the index is stale; the file changed since it was indexed")]
+    Stale,
+}
+
+/// A reader computing the size and [FNV-1a] checksum of what it reads.
+///
+/// [FNV-1a]: http://www.isthe.com/chongo/tech/comp/fnv/
+#[derive(Debug)]
+pub struct Checksum<R> {
+    inner: R,
+    size: u64,
+    hash: u64,
+}
+
+/// Characters along with the offsets of those following a line break, which
+/// are the candidate record starts.
+struct Tracked<'a, R> {
+    chars: &'a mut Chars<Checksum<R>>,
+    /// The byte offset of the next character, unknown until the byte order
+    /// mark, if any, has been skipped.
+    byte: Option<u64>,
+    idx: usize,
+    after_break: bool,
+    starts: &'a RefCell<VecDeque<Offset>>,
+}
+
+/// The path of the sidecar index of a file, which is the file's path with
+/// `.idx` appended.
+#[must_use]
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+impl Index {
+    /// Indexes the records of a reader.
+    ///
+    /// Record starts are found by tokenizing without keeping field contents,
+    /// so line breaks within quoted fields are skipped. Without an
+    /// `encoding`, it is detected as by [`Chars::new`].
+    ///
+    /// # Errors
+    ///
+    /// If reading or seeking fails, the input cannot be decoded, or it fails
+    /// to tokenize.
+    pub fn build<R>(
+        reader: R,
+        encoding: Option<Encoding>,
+        options: &tokenizer::Options,
+    ) -> Result<Self, Error>
+    where
+        R: Read + Seek,
+    {
+        let mut chars = match encoding {
+            Some(encoding) => Chars::with_encoding(Checksum::new(reader), encoding),
+            None => Chars::new(Checksum::new(reader)),
+        };
+        let starts = RefCell::new(VecDeque::new());
+        let mut iter = Source::new(Tracked {
+            chars: &mut chars,
+            byte: None,
+            idx: 0,
+            after_break: true,
+            starts: &starts,
+        });
+
+        let mut offsets = Vec::new();
+        let mut at_start = true;
+        while let Some(token) = core::skip(&mut iter, options) {
+            let token = token?;
+            if at_start {
+                let mut starts = starts.borrow_mut();
+                while let Some(start) = starts.pop_front() {
+                    if start.char == token.idx {
+                        offsets.push(start);
+                        break;
+                    }
+                }
+            }
+            at_start = token.is_line_break();
+        }
+        let delimiter = iter.declared_delimiter();
+        drop(iter);
+
+        if let Some(cause) = chars.take_error() {
+            return Err(cause.into());
+        }
+        let encoding = chars.encoding();
+        let checksum = chars.into_inner();
+        let mut index = Self {
+            size: checksum.size(),
+            checksum: checksum.hash(),
+            sample: 0,
+            encoding,
+            delimiter,
+            offsets,
+        };
+        index.sample = index.sample(checksum.into_inner())?;
+        Ok(index)
+    }
+
+    /// Indexes a file.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::build`].
+    pub fn build_file(
+        path: &Path,
+        encoding: Option<Encoding>,
+        options: &tokenizer::Options,
+    ) -> Result<Self, Error> {
+        Self::build(File::open(path)?, encoding, options)
+    }
+
+    /// Loads the sidecar index of a file, checking that it is not stale, see
+    /// [`Self::is_fresh`].
+    ///
+    /// # Errors
+    ///
+    /// If either file cannot be read, the sidecar is not an index, or the
+    /// index is stale.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let index = Self::read(BufReader::new(File::open(sidecar_path(path))?))?;
+        let file = File::open(path)?;
+        if file.metadata()?.len() == index.size && index.is_fresh(file)? {
+            Ok(index)
+        } else {
+            Err(Error::Stale)
+        }
+    }
+
+    /// Saves the index as the sidecar of a file.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(sidecar_path(path))?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Number of indexed records.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Size in bytes of the indexed input.
+    #[inline]
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn checksum(&self) -> u64 {
+        self.checksum
+    }
+
+    /// The encoding of the indexed input.
+    #[inline]
+    #[must_use]
+    pub const fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The delimiter declared by a `sep=` directive of the indexed input,
+    /// to be used when resuming tokenizing at a record.
+    #[inline]
+    #[must_use]
+    pub const fn delimiter(&self) -> Option<char> {
+        self.delimiter
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn offsets(&self) -> &[Offset] {
+        &self.offsets
+    }
+
+    /// The offset of a record, counting from zero.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self, record: usize) -> Option<Offset> {
+        self.offsets.get(record).copied()
+    }
+
+    /// Seeks a reader of the indexed input to the start of a record.
+    ///
+    /// Characters read from there on start at [`Offset::char`], which is
+    /// where tokenizing should resume, see
+    /// [`tokenizer::Into::csv_tokens_at`].
+    ///
+    /// # Errors
+    ///
+    /// If seeking fails.
+    pub fn seek<R>(&self, reader: &mut R, record: usize) -> io::Result<Option<Offset>>
+    where
+        R: Seek,
+    {
+        let Some(offset) = self.offset(record) else {
+            return Ok(None);
+        };
+        reader.seek(SeekFrom::Start(offset.byte))?;
+        Ok(Some(offset))
+    }
+
+    /// Whether the input still has the size it was indexed with, and the
+    /// same bytes at its start, at its end and at up to 64 record starts.
+    ///
+    /// Only a few kibibytes are read, however large the input, so that
+    /// changes elsewhere go unnoticed; see [`Self::verify`] for a full check.
+    ///
+    /// # Errors
+    ///
+    /// If reading or seeking fails.
+    pub fn is_fresh<R>(&self, mut reader: R) -> io::Result<bool>
+    where
+        R: Read + Seek,
+    {
+        Ok(reader.seek(SeekFrom::End(0))? == self.size && self.sample(reader)? == self.sample)
+    }
+
+    /// Whether the input still has the size and checksum it was indexed
+    /// with, reading all of it.
+    ///
+    /// # Errors
+    ///
+    /// If reading fails.
+    pub fn verify<R>(&self, reader: R) -> io::Result<bool>
+    where
+        R: Read,
+    {
+        let mut checksum = Checksum::new(reader);
+        io::copy(&mut checksum, &mut io::sink())?;
+        Ok(checksum.size() == self.size && checksum.hash() == self.checksum)
+    }
+
+    /// The checksum of the bytes of an input at its start, at its end and at
+    /// evenly spread record starts.
+    fn sample<R>(&self, reader: R) -> io::Result<u64>
+    where
+        R: Read + Seek,
+    {
+        let step = self.offsets.len().div_ceil(SAMPLE_RECORDS).max(1);
+        let records = self.offsets.iter().step_by(step).map(|offset| offset.byte);
+        let mut checksum = Checksum::new(reader);
+        for position in [0, self.size.saturating_sub(SAMPLE_LEN)]
+            .into_iter()
+            .chain(records)
+        {
+            checksum.inner.seek(SeekFrom::Start(position))?;
+            io::copy(&mut (&mut checksum).take(SAMPLE_LEN), &mut io::sink())?;
+        }
+        Ok(checksum.hash())
+    }
+
+    /// Writes the index: the bytes `CSVIDX\0\x04`, then the size, checksum,
+    /// sample checksum, encoding, declared delimiter or zero, and number of
+    /// records, then the byte and character offsets of every record, each a
+    /// little-endian `u64`.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn write<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.size.to_le_bytes())?;
+        writer.write_all(&self.checksum.to_le_bytes())?;
+        writer.write_all(&self.sample.to_le_bytes())?;
+        writer.write_all(&encoding_code(self.encoding).to_le_bytes())?;
+        writer.write_all(&self.delimiter.map_or(0, u64::from).to_le_bytes())?;
+        writer.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        for offset in &self.offsets {
+            writer.write_all(&offset.byte.to_le_bytes())?;
+            writer.write_all(&(offset.char as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads an index written by [`Self::write`].
+    ///
+    /// # Errors
+    ///
+    /// If reading fails or the input is not an index.
+    pub fn read<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(Error::Format);
+        }
+        let mut next = || -> Result<u64, Error> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes).map_err(|cause| {
+                if cause.kind() == io::ErrorKind::UnexpectedEof {
+                    Error::Format
+                } else {
+                    cause.into()
+                }
+            })?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+        let size = next()?;
+        let checksum = next()?;
+        let sample = next()?;
+        let encoding = encoding_from_code(next()?).ok_or(Error::Format)?;
+        let delimiter = match next()? {
+            0 => None,
+            code => Some(
+                u32::try_from(code)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(Error::Format)?,
+            ),
+        };
+        let len = next()?;
+        let mut offsets = Vec::new();
+        for _ in 0..len {
+            let byte = next()?;
+            let char = usize::try_from(next()?).map_err(|_| Error::Format)?;
+            offsets.push(Offset { byte, char });
+        }
+        Ok(Self {
+            size,
+            checksum,
+            sample,
+            encoding,
+            delimiter,
+            offsets,
+        })
+    }
+}
+
+const fn encoding_code(encoding: Encoding) -> u64 {
+    match encoding {
+        Encoding::Utf8 => 0,
+        Encoding::Utf16Le => 1,
+        Encoding::Utf16Be => 2,
+        Encoding::Latin1 => 3,
+        Encoding::Windows1252 => 4,
+    }
+}
+
+const fn encoding_from_code(code: u64) -> Option<Encoding> {
+    match code {
+        0 => Some(Encoding::Utf8),
+        1 => Some(Encoding::Utf16Le),
+        2 => Some(Encoding::Utf16Be),
+        3 => Some(Encoding::Latin1),
+        4 => Some(Encoding::Windows1252),
+        _ => None,
+    }
+}
+
+impl<R> Checksum<R> {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    #[inline]
+    #[must_use]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            size: 0,
+            hash: Self::OFFSET_BASIS,
+        }
+    }
+
+    /// Number of bytes read so far.
+    #[inline]
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Checksum of the bytes read so far.
+    #[inline]
+    #[must_use]
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R> Read for Checksum<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        for byte in &buf[..read] {
+            self.hash = (self.hash ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+        self.size += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R> Iterator for Tracked<'_, R>
+where
+    R: Read,
+{
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ch = self.chars.next()?;
+        let byte = self
+            .byte
+            .get_or_insert_with(|| self.chars.bom().map_or(0, |bom| bom.len() as u64));
+        if self.after_break {
+            self.starts.borrow_mut().push_back(Offset {
+                byte: *byte,
+                char: self.idx,
+            });
+        }
+        self.after_break = matches!(ch, '\r' | '\n');
+        *byte += self.chars.encoding().encoded_len(ch) as u64;
+        self.idx += 1;
+        Some(ch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use super::*;
+
+    fn records(count: usize) -> String {
+        (0..count)
+            .map(|idx| format!("{idx},value {idx}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn detects_stale_samples() {
+        let text = records(10_000);
+        let index = Index::build(Cursor::new(&text), None, &tokenizer::Options::default()).unwrap();
+        assert!(index.is_fresh(Cursor::new(&text)).unwrap());
+
+        // Every 157th record start is sampled, far from the start and end.
+        let changed = text.replacen("\n4710,", "\n4711,", 1);
+        assert_eq!(changed.len(), text.len());
+        assert!(!index.is_fresh(Cursor::new(&changed)).unwrap());
+        assert!(!index.verify(Cursor::new(&changed)).unwrap());
+
+        let longer = format!("{text}x,y\n");
+        assert!(!index.is_fresh(Cursor::new(&longer)).unwrap());
+    }
+
+    #[test]
+    fn load_rejects_stale_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let text = records(10_000);
+        fs::write(&path, &text).unwrap();
+        let index = Index::build_file(&path, None, &tokenizer::Options::default()).unwrap();
+        index.save(&path).unwrap();
+        assert_eq!(Index::load(&path).unwrap(), index);
+
+        let last = text.rfind("9999,").unwrap();
+        let mut changed = text.into_bytes();
+        changed[last] = b'8';
+        fs::write(&path, &changed).unwrap();
+        assert!(matches!(Index::load(&path), Err(Error::Stale)));
+
+        changed.pop();
+        fs::write(&path, &changed).unwrap();
+        assert!(matches!(Index::load(&path), Err(Error::Stale)));
+    }
+}
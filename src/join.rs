@@ -0,0 +1,482 @@
+//! Joining two record iterators on key columns, [`join`].
+//!
+//! The right side is read into a hash table keyed by its key columns, while
+//! the left side streams past it. Should the right side exceed
+//! [`Options::budget`], both sides are instead sorted by their keys, see
+//! [`sort`](crate::sort::sort), and merged.
+
+pub mod options;
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    io, iter,
+    path::Path,
+};
+
+pub use options::{Kind, Options};
+
+use crate::{
+    column::{self, Column},
+    records::Record,
+    sort::{self, options::Builder as _, Key, Order, Sorted},
+    ParseError,
+};
+
+/// Joined records, without the header.
+///
+/// A hash join yields records in the order of the left side, followed by the
+/// unmatched records of the right side. A sort-merge join yields records in
+/// the order of their keys.
+///
+/// Each record holds the left columns, then the right columns other than
+/// the right key columns. Left key columns of right records without a match
+/// are filled in with the right keys, and the other missing columns are
+/// empty.
+#[derive(Debug)]
+pub struct Joined<L> {
+    header: Option<Vec<String>>,
+    layout: Layout,
+    pending: VecDeque<Vec<String>>,
+    inner: Inner<L>,
+}
+
+#[derive(Debug)]
+struct Layout {
+    kind: Kind,
+    left_keys: Vec<usize>,
+    right_keys: Vec<usize>,
+    /// The width of the left header, or else of the first left record,
+    /// which no left record may exceed.
+    left_width: usize,
+    right_width: usize,
+}
+
+#[derive(Debug)]
+enum Inner<L> {
+    Hash {
+        left: L,
+        right: Vec<Vec<String>>,
+        table: HashMap<Vec<String>, Vec<usize>>,
+        matched: Vec<bool>,
+        unmatched: usize,
+        /// The number of left records read, including the header.
+        read: usize,
+    },
+    Merge {
+        left: Side,
+        right: Side,
+    },
+}
+
+/// A sorted side of a sort-merge join and its next record.
+#[derive(Debug)]
+struct Side {
+    records: Sorted,
+    head: Option<Vec<String>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Column(#[from] column::Error),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{left} left key columns do not pair with {right} right key columns")]
+    KeyCount { left: usize, right: usize },
+}
+
+/// Joins the records of `left` and `right` where their key columns are
+/// equal.
+///
+/// The key columns pair up in order, and names refer to the header of their
+/// side. With [`Options::header`], the first record of each side is set
+/// aside as its header and the joined header is available through
+/// [`Joined::header`]; names on both sides other than the paired keys get
+/// the suffix of their side.
+///
+/// # Errors
+///
+/// If there are no keys or they do not pair up, a record fails to parse, a
+/// left record is wider than the left header or the first left record, a
+/// key column cannot be resolved, or a temporary file cannot be written.
+pub fn join<L, R>(
+    left: L,
+    right: R,
+    left_keys: &[Column],
+    right_keys: &[Column],
+    options: &Options,
+) -> Result<Joined<iter::Peekable<L>>, Error>
+where
+    L: Iterator<Item = Result<Record, ParseError>>,
+    R: Iterator<Item = Result<Record, ParseError>>,
+{
+    if left_keys.is_empty() || left_keys.len() != right_keys.len() {
+        return Err(Error::KeyCount {
+            left: left_keys.len(),
+            right: right_keys.len(),
+        });
+    }
+    let mut left = left.peekable();
+    let mut right = right.peekable();
+    let left_header = take_header(&mut left, options)?;
+    let right_header = take_header(&mut right, options)?;
+    let resolve = |keys: &[Column], header: Option<&[String]>| {
+        keys.iter()
+            .map(|key| key.resolve(header))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let mut layout = Layout {
+        kind: options.kind(),
+        left_keys: resolve(left_keys, left_header.as_deref())?,
+        right_keys: resolve(right_keys, right_header.as_deref())?,
+        left_width: left_header.as_ref().map_or(0, Vec::len),
+        right_width: right_header.as_ref().map_or(0, Vec::len),
+    };
+    if left_header.is_none() {
+        if let Some(Ok(record)) = left.peek() {
+            layout.left_width = record.len();
+        }
+    }
+    let skipped = usize::from(left_header.is_some());
+    let header = options
+        .header()
+        .then(|| layout.header(left_header, right_header, options));
+
+    let mut rows = Vec::new();
+    let mut size = 0;
+    while size <= options.budget() {
+        let Some(record) = right.next() else {
+            break;
+        };
+        let fields = record?.into_fields();
+        size += sort::footprint(&fields);
+        layout.right_width = layout.right_width.max(fields.len());
+        rows.push(fields);
+    }
+
+    let inner = if right.peek().is_none() {
+        let mut table = HashMap::<_, Vec<_>>::new();
+        for (idx, fields) in rows.iter().enumerate() {
+            table
+                .entry(key(fields, &layout.right_keys))
+                .or_default()
+                .push(idx);
+        }
+        Inner::Hash {
+            left,
+            matched: vec![false; rows.len()],
+            right: rows,
+            table,
+            unmatched: 0,
+            read: skipped,
+        }
+    } else {
+        let sort_options = sort::Options::default()
+            .with_budget(options.budget())
+            .with_header(false)
+            .with_temp_dir(options.temp_dir().map(Path::to_path_buf));
+        let sort_keys = |keys: &[usize]| {
+            keys.iter()
+                .map(|idx| Key::new(Column::Index(*idx), Order::Lexical, false))
+                .collect::<Vec<_>>()
+        };
+        let mut right_width = layout.right_width;
+        let right = rows
+            .into_iter()
+            .map(|fields| Ok(Record::new(0, fields)))
+            .chain(right)
+            .inspect(|record| {
+                if let Ok(record) = record {
+                    right_width = right_width.max(record.len());
+                }
+            });
+        let right = sort::sort(right, &sort_keys(&layout.right_keys), &sort_options)?;
+        let left = left.by_ref().enumerate().map(|(at, record)| {
+            let record = record?;
+            layout.check_left(record.fields(), at + skipped)?;
+            Ok(record)
+        });
+        let left = sort::sort(left, &sort_keys(&layout.left_keys), &sort_options)?;
+        layout.right_width = right_width;
+        Inner::Merge {
+            left: Side::new(left)?,
+            right: Side::new(right)?,
+        }
+    };
+
+    Ok(Joined {
+        header,
+        layout,
+        pending: VecDeque::new(),
+        inner,
+    })
+}
+
+impl<L> Joined<L> {
+    #[inline]
+    #[must_use]
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+}
+
+impl<L> Iterator for Joined<iter::Peekable<L>>
+where
+    L: Iterator<Item = Result<Record, ParseError>>,
+{
+    type Item = Result<Vec<String>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                return Some(Ok(record));
+            }
+            match self.step() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(cause) => return Some(Err(cause)),
+            }
+        }
+    }
+}
+
+impl<L> Joined<iter::Peekable<L>>
+where
+    L: Iterator<Item = Result<Record, ParseError>>,
+{
+    /// Queues the records joined with the next left record or key, returning
+    /// whether there may be more.
+    fn step(&mut self) -> Result<bool, Error> {
+        let layout = &mut self.layout;
+        match &mut self.inner {
+            Inner::Hash {
+                left,
+                right,
+                table,
+                matched,
+                unmatched,
+                read,
+            } => {
+                if let Some(record) = left.next() {
+                    let fields = record?.into_fields();
+                    layout.check_left(&fields, *read)?;
+                    *read += 1;
+                    match table.get(&key(&fields, &layout.left_keys)) {
+                        Some(rows) => {
+                            for &row in rows {
+                                matched[row] = true;
+                                self.pending
+                                    .push_back(layout.combine(Some(&fields), Some(&right[row])));
+                            }
+                        }
+                        None if layout.kind.keeps_left() => {
+                            self.pending.push_back(layout.combine(Some(&fields), None));
+                        }
+                        None => {}
+                    }
+                    return Ok(true);
+                }
+                if !layout.kind.keeps_right() {
+                    return Ok(false);
+                }
+                let Some(row) = matched[*unmatched..].iter().position(|matched| !matched) else {
+                    *unmatched = matched.len();
+                    return Ok(false);
+                };
+                let row = *unmatched + row;
+                *unmatched = row + 1;
+                self.pending
+                    .push_back(layout.combine(None, Some(&right[row])));
+                Ok(true)
+            }
+            Inner::Merge { left, right } => {
+                let ordering = match (&left.head, &right.head) {
+                    (None, None) => return Ok(false),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(lhs), Some(rhs)) => layout.compare(lhs, rhs),
+                };
+                match ordering {
+                    Ordering::Less => {
+                        let Some(fields) = left.advance()? else {
+                            return Ok(false);
+                        };
+                        if layout.kind.keeps_left() {
+                            self.pending.push_back(layout.combine(Some(&fields), None));
+                        } else if right.head.is_none() {
+                            return Ok(false);
+                        }
+                    }
+                    Ordering::Greater => {
+                        let Some(fields) = right.advance()? else {
+                            return Ok(false);
+                        };
+                        if layout.kind.keeps_right() {
+                            self.pending.push_back(layout.combine(None, Some(&fields)));
+                        } else if left.head.is_none() {
+                            return Ok(false);
+                        }
+                    }
+                    Ordering::Equal => {
+                        let mut group = Vec::new();
+                        let first = right.advance()?.unwrap_or_default();
+                        let target = key(&first, &layout.right_keys);
+                        group.push(first);
+                        while right
+                            .head
+                            .as_ref()
+                            .is_some_and(|fields| key(fields, &layout.right_keys) == target)
+                        {
+                            group.extend(right.advance()?);
+                        }
+                        while left
+                            .head
+                            .as_ref()
+                            .is_some_and(|fields| key(fields, &layout.left_keys) == target)
+                        {
+                            let Some(fields) = left.advance()? else {
+                                break;
+                            };
+                            for other in &group {
+                                self.pending
+                                    .push_back(layout.combine(Some(&fields), Some(other)));
+                            }
+                        }
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl Layout {
+    /// The header of the joined records, suffixing names found on both
+    /// sides.
+    fn header(
+        &self,
+        left: Option<Vec<String>>,
+        right: Option<Vec<String>>,
+        options: &Options,
+    ) -> Vec<String> {
+        let left = left.unwrap_or_default();
+        let right = right
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.right_keys.contains(idx))
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        let suffixed = |name: &String, others: &[String], suffix: &str| {
+            if others.contains(name) {
+                format!("{name}{suffix}")
+            } else {
+                name.clone()
+            }
+        };
+        let mut header = left
+            .iter()
+            .map(|name| suffixed(name, &right, options.left_suffix()))
+            .collect::<Vec<_>>();
+        header.extend(
+            right
+                .iter()
+                .map(|name| suffixed(name, &left, options.right_suffix())),
+        );
+        header
+    }
+
+    /// Rejects a left record, the `at`th of its side, wider than the left
+    /// columns.
+    fn check_left(&self, fields: &[String], at: usize) -> Result<(), ParseError> {
+        if fields.len() > self.left_width {
+            return Err(ParseError::JaggedRecords {
+                expected: self.left_width,
+                at,
+                actual: fields.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn combine(&self, left: Option<&[String]>, right: Option<&[String]>) -> Vec<String> {
+        let field = |fields: Option<&[String]>, idx: usize| {
+            fields
+                .and_then(|fields| fields.get(idx))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let mut record = Vec::with_capacity(self.left_width + self.right_width);
+        for idx in 0..self.left_width {
+            let key = self.left_keys.iter().position(|key| *key == idx);
+            record.push(match (left, key) {
+                (None, Some(key)) => field(right, self.right_keys[key]),
+                _ => field(left, idx),
+            });
+        }
+        record.extend(
+            (0..self.right_width)
+                .filter(|idx| !self.right_keys.contains(idx))
+                .map(|idx| field(right, idx)),
+        );
+        record
+    }
+
+    fn compare(&self, left: &[String], right: &[String]) -> Ordering {
+        self.left_keys
+            .iter()
+            .zip(&self.right_keys)
+            .map(|(lhs, rhs)| {
+                let lhs = left.get(*lhs).map_or("", String::as_str);
+                let rhs = right.get(*rhs).map_or("", String::as_str);
+                lhs.cmp(rhs)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Side {
+    fn new(mut records: Sorted) -> Result<Self, Error> {
+        let head = records.next().transpose()?;
+        Ok(Self { records, head })
+    }
+
+    /// Takes the next record, reading the one after it.
+    fn advance(&mut self) -> Result<Option<Vec<String>>, Error> {
+        let next = self.records.next().transpose()?;
+        Ok(std::mem::replace(&mut self.head, next))
+    }
+}
+
+impl From<sort::Error> for Error {
+    fn from(value: sort::Error) -> Self {
+        match value {
+            sort::Error::Parse(cause) => Self::Parse(cause),
+            sort::Error::Column(cause) => Self::Column(cause),
+            sort::Error::Io(cause) => Self::Io(cause),
+        }
+    }
+}
+
+fn take_header<I>(
+    records: &mut iter::Peekable<I>,
+    options: &Options,
+) -> Result<Option<Vec<String>>, ParseError>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    match records.next_if(|_| options.header()) {
+        Some(record) => Ok(Some(record?.into_fields())),
+        None => Ok(None),
+    }
+}
+
+fn key(fields: &[String], keys: &[usize]) -> Vec<String> {
+    keys.iter()
+        .map(|idx| fields.get(*idx).cloned().unwrap_or_default())
+        .collect()
+}
@@ -0,0 +1,272 @@
+//! Record join, [`join`] options.
+//!
+//! [`join`]: super::join
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_budget(self, budget: usize) -> Self;
+
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_kind(self, kind: Kind) -> Self;
+
+    #[must_use]
+    fn with_left_suffix(self, left_suffix: Cow<'static, str>) -> Self;
+
+    #[must_use]
+    fn with_right_suffix(self, right_suffix: Cow<'static, str>) -> Self;
+
+    #[must_use]
+    fn with_temp_dir(self, temp_dir: Option<PathBuf>) -> Self;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    budget: usize,
+    header: bool,
+    kind: Kind,
+    left_suffix: Cow<'static, str>,
+    right_suffix: Cow<'static, str>,
+    temp_dir: Option<PathBuf>,
+}
+
+/// Which unmatched records are kept.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Kind {
+    /// Neither.
+    #[default]
+    Inner,
+    /// Those of the left side.
+    Left,
+    /// Those of the right side.
+    Right,
+    /// Those of both sides.
+    Full,
+}
+
+impl Kind {
+    #[inline]
+    #[must_use]
+    pub const fn keeps_left(self) -> bool {
+        matches!(self, Self::Left | Self::Full)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn keeps_right(self) -> bool {
+        matches!(self, Self::Right | Self::Full)
+    }
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        budget: 64 * 1024 * 1024,
+        header: true,
+        kind: Kind::Inner,
+        left_suffix: Cow::Borrowed("_left"),
+        right_suffix: Cow::Borrowed("_right"),
+        temp_dir: None,
+    };
+
+    /// Approximate number of bytes of right records to hash in memory,
+    /// past which both sides are sorted and merged instead.
+    #[inline]
+    #[must_use]
+    pub const fn budget(&self) -> usize {
+        self.budget
+    }
+
+    #[inline]
+    pub fn budget_mut(&mut self) -> &mut usize {
+        &mut self.budget
+    }
+
+    #[inline]
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Whether the first record of each side is a header.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn kind_mut(&mut self) -> &mut Kind {
+        &mut self.kind
+    }
+
+    #[inline]
+    pub fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind;
+    }
+
+    /// Appended to left header names that are also right header names.
+    #[inline]
+    #[must_use]
+    pub fn left_suffix(&self) -> &str {
+        &self.left_suffix
+    }
+
+    #[inline]
+    pub fn left_suffix_mut(&mut self) -> &mut Cow<'static, str> {
+        &mut self.left_suffix
+    }
+
+    #[inline]
+    pub fn set_left_suffix(&mut self, left_suffix: Cow<'static, str>) {
+        self.left_suffix = left_suffix;
+    }
+
+    /// Appended to right header names that are also left header names.
+    #[inline]
+    #[must_use]
+    pub fn right_suffix(&self) -> &str {
+        &self.right_suffix
+    }
+
+    #[inline]
+    pub fn right_suffix_mut(&mut self) -> &mut Cow<'static, str> {
+        &mut self.right_suffix
+    }
+
+    #[inline]
+    pub fn set_right_suffix(&mut self, right_suffix: Cow<'static, str>) {
+        self.right_suffix = right_suffix;
+    }
+
+    /// Directory of the temporary files of a sort-merge join.
+    #[inline]
+    #[must_use]
+    pub fn temp_dir(&self) -> Option<&Path> {
+        self.temp_dir.as_deref()
+    }
+
+    #[inline]
+    pub fn temp_dir_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.temp_dir
+    }
+
+    #[inline]
+    pub fn set_temp_dir(&mut self, temp_dir: Option<PathBuf>) {
+        self.temp_dir = temp_dir;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_budget(self, budget: usize) -> Self {
+        self.set_budget(budget);
+        self
+    }
+
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_kind(self, kind: Kind) -> Self {
+        self.set_kind(kind);
+        self
+    }
+
+    #[inline]
+    fn with_left_suffix(self, left_suffix: Cow<'static, str>) -> Self {
+        self.set_left_suffix(left_suffix);
+        self
+    }
+
+    #[inline]
+    fn with_right_suffix(self, right_suffix: Cow<'static, str>) -> Self {
+        self.set_right_suffix(right_suffix);
+        self
+    }
+
+    #[inline]
+    fn with_temp_dir(self, temp_dir: Option<PathBuf>) -> Self {
+        self.set_temp_dir(temp_dir);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_budget(mut self, budget: usize) -> Self {
+        self.set_budget(budget);
+        self
+    }
+
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_kind(mut self, kind: Kind) -> Self {
+        self.set_kind(kind);
+        self
+    }
+
+    #[inline]
+    fn with_left_suffix(mut self, left_suffix: Cow<'static, str>) -> Self {
+        self.set_left_suffix(left_suffix);
+        self
+    }
+
+    #[inline]
+    fn with_right_suffix(mut self, right_suffix: Cow<'static, str>) -> Self {
+        self.set_right_suffix(right_suffix);
+        self
+    }
+
+    #[inline]
+    fn with_temp_dir(mut self, temp_dir: Option<PathBuf>) -> Self {
+        self.set_temp_dir(temp_dir);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
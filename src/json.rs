@@ -0,0 +1,290 @@
+//! Conversion between records and JSON or [JSON Lines].
+//!
+//! Records are written as they are read and JSON elements are flattened one
+//! at a time, so neither direction holds the whole input in memory.
+//!
+//! [JSON Lines]: https://jsonlines.org
+
+use std::{borrow::Cow, collections::HashMap, fmt, io};
+
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::{
+    records::Record,
+    writer::{self, Writer},
+    ParseError,
+};
+
+/// The shape of each JSON element.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Layout {
+    /// Objects keyed by the header, which is the first record.
+    #[default]
+    Objects,
+    /// Arrays of fields, the header being written as the first array.
+    Arrays,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Write(#[from] writer::Error),
+    #[error("element {at} is neither an object nor an array of scalars")]
+    NotFlat { at: usize },
+    #[error("element {at} mixes objects and arrays with the previous elements")]
+    MixedShapes { at: usize },
+    #[error("element {at} has the key {key:?}, which is missing from the header")]
+    UnknownKey { at: usize, key: String },
+    #[error("expected a uniform length of {expected} at {at}, instead got {actual}")]
+    JaggedArrays {
+        expected: usize,
+        at: usize,
+        actual: usize,
+    },
+}
+
+/// Writes records as a JSON array.
+///
+/// # Errors
+///
+/// If a record fails to parse, if a record is not as long as the header when
+/// writing [`Layout::Objects`], or if writing fails.
+pub fn to_json<I, W>(records: I, mut writer: W, layout: Layout) -> Result<(), Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+    W: io::Write,
+{
+    writer.write_all(b"[")?;
+    let mut empty = true;
+    for_each_element(records, layout, |element| {
+        writer.write_all(if empty { b"\n" } else { b",\n" })?;
+        empty = false;
+        writer.write_all(element)
+    })?;
+    writer.write_all(if empty { b"]\n" } else { b"\n]\n" })?;
+    Ok(())
+}
+
+/// Writes records as JSON Lines, one element per line.
+///
+/// # Errors
+///
+/// See [`to_json`].
+pub fn to_json_lines<I, W>(records: I, mut writer: W, layout: Layout) -> Result<(), Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+    W: io::Write,
+{
+    for_each_element(records, layout, |element| {
+        writer.write_all(element)?;
+        writer.write_all(b"\n")
+    })
+}
+
+/// Writes a JSON array of flat objects or flat arrays as records.
+///
+/// The keys of the first object become the header. Later objects may omit
+/// keys, which are written as empty fields, but may not introduce new ones.
+/// `null` is written as an empty field; numbers and booleans as their JSON
+/// text.
+///
+/// # Errors
+///
+/// If the input is not such an array, or if writing fails.
+pub fn from_json<R, W>(reader: R, writer: &mut Writer<W>) -> Result<(), Error>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    let mut flattener = Flattener::default();
+    let mut error = None;
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let result = deserializer.deserialize_seq(Elements {
+        f: |element| flattener.write(element, writer),
+        error: &mut error,
+    });
+    if let Some(cause) = error {
+        return Err(cause);
+    }
+    result?;
+    deserializer.end()?;
+    Ok(())
+}
+
+/// Writes JSON Lines of flat objects or flat arrays as records.
+///
+/// # Errors
+///
+/// See [`from_json`].
+pub fn from_json_lines<R, W>(reader: R, writer: &mut Writer<W>) -> Result<(), Error>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    let mut flattener = Flattener::default();
+    for element in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        flattener.write(element?, writer)?;
+    }
+    Ok(())
+}
+
+fn for_each_element<I, F>(records: I, layout: Layout, mut f: F) -> Result<(), Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    let mut header: Option<Vec<String>> = None;
+    let mut buf = Vec::new();
+    for (at, record) in records.enumerate() {
+        let fields = record?.into_fields();
+        buf.clear();
+        match (layout, &header) {
+            (Layout::Arrays, _) => serde_json::to_writer(&mut buf, &fields)?,
+            (Layout::Objects, None) => {
+                header = Some(fields);
+                continue;
+            }
+            (Layout::Objects, Some(header)) => {
+                if header.len() != fields.len() {
+                    return Err(ParseError::JaggedRecords {
+                        expected: header.len(),
+                        at,
+                        actual: fields.len(),
+                    }
+                    .into());
+                }
+                buf.push(b'{');
+                for (idx, (key, value)) in header.iter().zip(&fields).enumerate() {
+                    if idx != 0 {
+                        buf.push(b',');
+                    }
+                    serde_json::to_writer(&mut buf, key)?;
+                    buf.push(b':');
+                    serde_json::to_writer(&mut buf, value)?;
+                }
+                buf.push(b'}');
+            }
+        }
+        f(&buf)?;
+    }
+    Ok(())
+}
+
+/// Visits the elements of a JSON array one at a time.
+///
+/// Errors from `f` abort deserialization and are kept aside, as they would
+/// otherwise be flattened into a [`serde_json::Error`] message.
+struct Elements<'a, F> {
+    f: F,
+    error: &'a mut Option<Error>,
+}
+
+impl<'de, F> Visitor<'de> for Elements<'_, F>
+where
+    F: FnMut(Value) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of flat objects or flat arrays")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while let Some(element) = seq.next_element::<Value>()? {
+            if let Err(cause) = (self.f)(element) {
+                *self.error = Some(cause);
+                return Err(de::Error::custom("aborted"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Flattener {
+    at: usize,
+    shape: Option<Shape>,
+    row: Vec<Cow<'static, str>>,
+}
+
+#[derive(Debug)]
+enum Shape {
+    Objects(HashMap<String, usize>),
+    Arrays(usize),
+}
+
+impl Flattener {
+    fn write<W>(&mut self, element: Value, writer: &mut Writer<W>) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        let at = self.at;
+        self.at += 1;
+        self.row.clear();
+        match (element, &self.shape) {
+            (Value::Object(object), None) => {
+                let mut header = Vec::with_capacity(object.len());
+                for (key, value) in object {
+                    header.push(key);
+                    self.row.push(cell(value, at)?);
+                }
+                writer.write_record(&header)?;
+                self.shape = Some(Shape::Objects(header.into_iter().zip(0..).collect()));
+            }
+            (Value::Object(object), Some(Shape::Objects(header))) => {
+                self.row.resize(header.len(), Cow::Borrowed(""));
+                for (key, value) in object {
+                    let Some(&idx) = header.get(&key) else {
+                        return Err(Error::UnknownKey { at, key });
+                    };
+                    self.row[idx] = cell(value, at)?;
+                }
+            }
+            (Value::Array(array), None) => {
+                self.shape = Some(Shape::Arrays(array.len()));
+                for value in array {
+                    self.row.push(cell(value, at)?);
+                }
+            }
+            (Value::Array(array), Some(Shape::Arrays(expected))) => {
+                let expected = *expected;
+                if array.len() != expected {
+                    return Err(Error::JaggedArrays {
+                        expected,
+                        at,
+                        actual: array.len(),
+                    });
+                }
+                for value in array {
+                    self.row.push(cell(value, at)?);
+                }
+            }
+            (Value::Object(_), Some(Shape::Arrays(_)))
+            | (Value::Array(_), Some(Shape::Objects(_))) => return Err(Error::MixedShapes { at }),
+            (_, _) => return Err(Error::NotFlat { at }),
+        }
+        writer.write_record(&self.row)?;
+        Ok(())
+    }
+}
+
+fn cell(value: Value, at: usize) -> Result<Cow<'static, str>, Error> {
+    match value {
+        Value::Null => Ok(Cow::Borrowed("")),
+        Value::Bool(true) => Ok(Cow::Borrowed("true")),
+        Value::Bool(false) => Ok(Cow::Borrowed("false")),
+        Value::Number(number) => Ok(Cow::Owned(number.to_string())),
+        Value::String(string) => Ok(Cow::Owned(string)),
+        Value::Array(_) | Value::Object(_) => Err(Error::NotFlat { at }),
+    }
+}
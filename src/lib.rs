@@ -1,9 +1,37 @@
 use recorder::Into;
+use records::Into as IntoRecords;
 use tokenizer::Into as IntoTokenizer;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod byte_record;
+pub mod bytes;
+pub mod chars;
+pub mod column;
+pub mod columnar;
+pub mod diagnostic;
+pub mod diff;
+pub mod filter;
+pub mod index;
+pub mod join;
+pub mod json;
+pub mod location;
+pub mod projection;
+pub mod push;
+pub mod query;
+pub mod reader;
 pub mod recorder;
+pub mod records;
+pub mod sort;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod string_record;
+pub mod table;
 pub mod token;
 pub mod tokenizer;
+pub mod value;
+pub mod writer;
 
 /// # Errors
 ///
@@ -37,6 +65,19 @@ pub fn parse(s: &str) -> Result<Vec<Vec<String>>, ParseError> {
     Ok(records)
 }
 
+/// Parses a whole input into columns, see [`columnar::parse`].
+///
+/// # Errors
+///
+/// If a record fails to parse, or has a different number of fields than the
+/// first.
+pub fn parse_columns(
+    s: &str,
+    options: &columnar::Options,
+) -> Result<columnar::Columns, ParseError> {
+    columnar::parse(s.chars().csv_tokens().csv_records(), options)
+}
+
 #[derive(Clone, Debug, thiserror::Error)]
 pub enum ParseError {
     #[error("{0}")]
@@ -50,3 +91,31 @@ pub enum ParseError {
         actual: usize,
     },
 }
+
+impl ParseError {
+    /// A stable code identifying the error, see [`diagnostic`].
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Tokenizer(cause) => cause.kind().code(),
+            Self::Recorder(cause) => cause.kind().code(),
+            Self::JaggedRecords { .. } => "CSV301",
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> diagnostic::Severity {
+        diagnostic::Severity::Error
+    }
+
+    /// The character index of the error, if known.
+    #[must_use]
+    pub const fn at(&self) -> Option<usize> {
+        match self {
+            Self::Tokenizer(cause) => Some(*cause.at()),
+            Self::Recorder(cause) => Some(*cause.at()),
+            Self::JaggedRecords { .. } => None,
+        }
+    }
+}
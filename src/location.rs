@@ -0,0 +1,182 @@
+//! Line and column lookup for character indices.
+//!
+//! Errors report positions as character indices into the input; a
+//! [`Locator`] translates those into human friendly line and column numbers,
+//! and a [`FieldLocator`] into record and field numbers.
+
+use std::fmt;
+
+use crate::{
+    token::{Kind, Token},
+    tokenizer::{self, Into as _},
+};
+
+/// A one-based line and column pair.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Character indices at which each line starts.
+///
+/// `"\r\n"`, `"\r"` and `"\n"` are all treated as line breaks regardless of
+/// the tokenizer options, as this is only used for display.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Locator {
+    line_starts: Vec<usize>,
+    /// The byte offset of every line start.
+    byte_starts: Vec<usize>,
+}
+
+/// A one-based record and field pair.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct FieldLocation {
+    pub record: usize,
+    pub field: usize,
+}
+
+/// Character indices at which each record and field starts, as tokenized.
+///
+/// Unlike lines, records may span several lines through quoted line breaks,
+/// and a `sep=` directive is not a record.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldLocator {
+    field_starts: Vec<usize>,
+    /// The index in `field_starts` of the first field of every record.
+    record_starts: Vec<usize>,
+}
+
+impl Location {
+    #[inline]
+    #[must_use]
+    pub const fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Locator {
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut byte_starts = vec![0];
+        let mut chars = text.char_indices().enumerate().peekable();
+        while let Some((idx, (byte, ch))) = chars.next() {
+            match ch {
+                '\r' => {
+                    let (end, byte) = chars
+                        .next_if(|(_, (_, ch))| *ch == '\n')
+                        .map_or((idx, byte), |(idx, (byte, _))| (idx, byte));
+                    line_starts.push(end + 1);
+                    byte_starts.push(byte + 1);
+                }
+                '\n' => {
+                    line_starts.push(idx + 1);
+                    byte_starts.push(byte + 1);
+                }
+                _ => {}
+            }
+        }
+        Self {
+            line_starts,
+            byte_starts,
+        }
+    }
+
+    #[must_use]
+    pub fn locate(&self, idx: usize) -> Location {
+        let line = self.line_starts.partition_point(|start| *start <= idx);
+        Location::new(line, idx - self.line_starts[line - 1] + 1)
+    }
+
+    /// Character index at which the given one-based line starts.
+    #[inline]
+    #[must_use]
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+impl Locator {
+    /// The text of the given one-based line, without its line break.
+    ///
+    /// `text` must be the text the locator was made from.
+    #[must_use]
+    pub fn line<'a>(&self, text: &'a str, line: usize) -> Option<&'a str> {
+        let start = *self.byte_starts.get(line.checked_sub(1)?)?;
+        let end = self.byte_starts.get(line).copied().unwrap_or(text.len());
+        Some(text[start..end].trim_end_matches(['\r', '\n']))
+    }
+}
+
+impl FieldLocation {
+    #[inline]
+    #[must_use]
+    pub const fn new(record: usize, field: usize) -> Self {
+        Self { record, field }
+    }
+}
+
+impl fmt::Display for FieldLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "record {}, field {}", self.record, self.field)
+    }
+}
+
+impl FieldLocator {
+    /// Tokenizes the text to find its records and fields, skipping past
+    /// errors where the options recover from them.
+    #[must_use]
+    pub fn new(text: &str, options: &tokenizer::Options) -> Self {
+        let mut field_starts = Vec::new();
+        let mut record_starts = Vec::new();
+        let mut record_start = true;
+        for token in text.chars().csv_tokens_custom(options) {
+            let (idx, kind) = match token {
+                Ok(Token { idx, kind }) => (idx, Some(kind)),
+                Err(cause) => (*cause.at(), None),
+            };
+            if record_start {
+                record_starts.push(field_starts.len());
+                field_starts.push(idx);
+                record_start = false;
+            }
+            match kind {
+                Some(Kind::Field(_)) | None => {}
+                Some(Kind::Delimiter) => field_starts.push(idx + 1),
+                Some(Kind::LineBreak) => record_start = true,
+            }
+        }
+        Self {
+            field_starts,
+            record_starts,
+        }
+    }
+
+    /// The record and field the character index is in, if it is not before
+    /// the first record.
+    #[must_use]
+    pub fn locate(&self, idx: usize) -> Option<FieldLocation> {
+        let field = self
+            .field_starts
+            .partition_point(|start| *start <= idx)
+            .checked_sub(1)?;
+        let record = self.record_starts.partition_point(|start| *start <= field);
+        Some(FieldLocation::new(
+            record,
+            field - self.record_starts[record - 1] + 1,
+        ))
+    }
+}
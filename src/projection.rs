@@ -0,0 +1,437 @@
+//! Column selection, [`Selection`], and projected records, [`Projected`].
+//!
+//! Once the columns are known, unselected fields are tokenized without
+//! being buffered, so they are neither unescaped nor allocated.
+
+use std::{borrow::Borrow, str::FromStr};
+
+use crate::{
+    recorder,
+    records::{self, Record},
+    token::{Kind, Token},
+    tokenizer::{
+        self,
+        core::{self, Source},
+    },
+    ParseError,
+};
+
+/// One part of a [`Selection`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Selector {
+    /// A zero-based column index.
+    Index(usize),
+    /// An inclusive range of zero-based column indices, open ended when
+    /// either bound is missing. Ranges may be descending.
+    Range(Option<usize>, Option<usize>),
+    /// The first column of the header with the given name.
+    Name(String),
+}
+
+/// Columns to pick, in order and possibly more than once.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Selection {
+    selectors: Vec<Selector>,
+}
+
+/// A [`Selection`] resolved against a header or a record length.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Projection {
+    columns: Vec<usize>,
+    keep: Vec<bool>,
+    take: Vec<bool>,
+}
+
+/// Records restricted to a [`Selection`].
+///
+/// The first record is read in full to resolve the selection; it is the
+/// header if one is expected.
+#[derive(Clone, Debug)]
+pub struct Projected<I, T, R>
+where
+    I: Iterator<Item = char>,
+{
+    iter: Source<I>,
+    tokenizer: T,
+    recorder: R,
+    selection: Selection,
+    header: bool,
+    projection: Option<Projection>,
+    at: usize,
+    /// Number of records read so far, including those failing to parse.
+    read: usize,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("no column is named {0:?}")]
+    UnknownName(String),
+    #[error("column {0:?} is selected by name, but there is no header")]
+    NameWithoutHeader(String),
+    #[error("column {column} is missing from record {at}, which has {len} fields")]
+    MissingColumn {
+        column: usize,
+        at: usize,
+        len: usize,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("invalid selection {selector:?}: {kind}")]
+pub struct SelectionError {
+    selector: String,
+    kind: SelectionErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display)]
+#[non_exhaustive]
+pub enum SelectionErrorKind {
+    #[strum(to_string = "selectors must not be empty")]
+    Empty,
+    #[strum(to_string = "column numbers start at 1")]
+    Zero,
+    #[strum(to_string = "column number is too large")]
+    TooLarge,
+    #[strum(to_string = "quoted name never ends")]
+    UnterminatedQuote,
+    #[strum(to_string = "text after the closing quote of a name")]
+    EarlyQuote,
+}
+
+/// Converting to a projected csv record iterator.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = char> + Sized,
+{
+    #[inline]
+    fn csv_projected<T, R>(
+        self,
+        tokenizer: T,
+        recorder: R,
+        selection: Selection,
+        header: bool,
+    ) -> Projected<Self, T, R> {
+        Projected {
+            iter: Source::new(self),
+            tokenizer,
+            recorder,
+            selection,
+            header,
+            projection: None,
+            at: 0,
+            read: 0,
+        }
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = char> {}
+
+impl SelectionError {
+    #[inline]
+    #[must_use]
+    pub const fn new(selector: String, kind: SelectionErrorKind) -> Self {
+        Self { selector, kind }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &SelectionErrorKind {
+        &self.kind
+    }
+}
+
+impl Selection {
+    #[inline]
+    #[must_use]
+    pub const fn new(selectors: Vec<Selector>) -> Self {
+        Self { selectors }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_names(&self) -> bool {
+        self.selectors
+            .iter()
+            .any(|selector| matches!(selector, Selector::Name(_)))
+    }
+
+    /// Resolves the selection against the first record.
+    ///
+    /// # Errors
+    ///
+    /// If a name is selected without a header, or no column has that name.
+    pub fn resolve(&self, first: &[String], header: bool) -> Result<Projection, Error> {
+        let last = first.len().saturating_sub(1);
+        let mut columns = Vec::new();
+        for selector in &self.selectors {
+            match selector {
+                Selector::Index(column) => columns.push(*column),
+                Selector::Range(start, end) => {
+                    let start = start.unwrap_or(0);
+                    let end = end.unwrap_or(last);
+                    if start <= end {
+                        columns.extend(start..=end);
+                    } else {
+                        columns.extend((end..=start).rev());
+                    }
+                }
+                Selector::Name(name) if header => {
+                    let column = first
+                        .iter()
+                        .position(|field| field == name)
+                        .ok_or_else(|| Error::UnknownName(name.clone()))?;
+                    columns.push(column);
+                }
+                Selector::Name(name) => return Err(Error::NameWithoutHeader(name.clone())),
+            }
+        }
+        Ok(Projection::new(columns))
+    }
+}
+
+/// Parses comma separated selectors: one-based column numbers such as `3`,
+/// inclusive ranges such as `2-5`, `4-` or `-2`, and header names.
+///
+/// Anything that is not a number or a range of numbers is a name. Names
+/// quoted with `"`, doubling the quotes within, are never numbers and may
+/// contain commas, such as `"1"` or `"a,b"`.
+impl FromStr for Selection {
+    type Err = SelectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selectors = Vec::new();
+        let mut rest = Some(s);
+        while let Some(s) = rest {
+            let selector;
+            (selector, rest) = if s.starts_with('"') {
+                parse_quoted(s)?
+            } else {
+                match s.split_once(',') {
+                    Some((selector, rest)) => (parse_selector(selector)?, Some(rest)),
+                    None => (parse_selector(s)?, None),
+                }
+            };
+            selectors.push(selector);
+        }
+        Ok(Self::new(selectors))
+    }
+}
+
+/// Parses a quoted name at the start of `s`, returning what follows the
+/// comma after it, if any.
+fn parse_quoted(s: &str) -> Result<(Selector, Option<&str>), SelectionError> {
+    let mut name = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((idx, ch)) = chars.next() {
+        if ch != '"' {
+            name.push(ch);
+            continue;
+        }
+        let rest = &s[idx + 1..];
+        if rest.starts_with('"') {
+            chars.next();
+            name.push(ch);
+        } else if rest.is_empty() {
+            return Ok((Selector::Name(name), None));
+        } else if let Some(rest) = rest.strip_prefix(',') {
+            return Ok((Selector::Name(name), Some(rest)));
+        } else {
+            let end = idx + 1 + rest.find(',').unwrap_or(rest.len());
+            let selector = s[..end].to_owned();
+            return Err(SelectionError::new(
+                selector,
+                SelectionErrorKind::EarlyQuote,
+            ));
+        }
+    }
+    Err(SelectionError::new(
+        s.to_owned(),
+        SelectionErrorKind::UnterminatedQuote,
+    ))
+}
+
+fn parse_selector(s: &str) -> Result<Selector, SelectionError> {
+    let error = |kind| SelectionError::new(s.to_owned(), kind);
+    let number = |s: &str| -> Result<Option<usize>, SelectionError> {
+        match s.parse::<usize>() {
+            Ok(0) => Err(error(SelectionErrorKind::Zero)),
+            Ok(number) => Ok(Some(number - 1)),
+            Err(_) if s.is_empty() => Ok(None),
+            Err(_) => Err(error(SelectionErrorKind::TooLarge)),
+        }
+    };
+
+    if s.is_empty() {
+        return Err(error(SelectionErrorKind::Empty));
+    }
+    if s.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Ok(Selector::Index(number(s)?.expect("not empty")));
+    }
+    if let Some((start, end)) = s.split_once('-') {
+        let digits = |s: &str| s.bytes().all(|byte| byte.is_ascii_digit());
+        if digits(start) && digits(end) && s != "-" {
+            return Ok(Selector::Range(number(start)?, number(end)?));
+        }
+    }
+    Ok(Selector::Name(s.to_owned()))
+}
+
+impl Projection {
+    /// Projects records onto the given zero-based columns.
+    #[must_use]
+    pub fn new(columns: Vec<usize>) -> Self {
+        let len = columns.iter().max().map_or(0, |max| max + 1);
+        let mut keep = vec![false; len];
+        let mut take = vec![false; columns.len()];
+        for (idx, column) in columns.iter().enumerate().rev() {
+            take[idx] = !keep[*column];
+            keep[*column] = true;
+        }
+        Self {
+            columns,
+            keep,
+            take,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Whether the column is part of the projection at all.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, column: usize) -> bool {
+        self.keep.get(column).copied().unwrap_or(false)
+    }
+
+    /// Picks the selected fields out of a record.
+    ///
+    /// # Errors
+    ///
+    /// If a selected column is missing, returning that column.
+    pub fn apply(&self, mut fields: Vec<String>) -> Result<Vec<String>, usize> {
+        if let Some(column) = self.columns.iter().find(|column| **column >= fields.len()) {
+            return Err(*column);
+        }
+        Ok(self
+            .columns
+            .iter()
+            .zip(&self.take)
+            .map(|(column, take)| {
+                if *take {
+                    std::mem::take(&mut fields[*column])
+                } else {
+                    fields[*column].clone()
+                }
+            })
+            .collect())
+    }
+}
+
+impl<I, T, R> Projected<I, T, R>
+where
+    I: Iterator<Item = char>,
+    T: Borrow<tokenizer::Options>,
+    R: Borrow<recorder::Options>,
+{
+    /// The resolved projection, once the first record has been read.
+    #[inline]
+    #[must_use]
+    pub const fn projection(&self) -> Option<&Projection> {
+        self.projection.as_ref()
+    }
+
+    fn next_record(&mut self) -> Option<Result<Record, ParseError>> {
+        let tokens = Tokens {
+            iter: &mut self.iter,
+            options: self.tokenizer.borrow(),
+            projection: self.projection.as_ref(),
+            column: 0,
+        };
+        records::next(
+            &mut tokens.peekable(),
+            self.recorder.borrow(),
+            &mut self.read,
+        )
+    }
+}
+
+impl<I, T, R> Iterator for Projected<I, T, R>
+where
+    I: Iterator<Item = char>,
+    T: Borrow<tokenizer::Options>,
+    R: Borrow<recorder::Options>,
+{
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.next_record()? {
+            Ok(record) => record,
+            Err(cause) => return Some(Err(cause.into())),
+        };
+        let at = self.at;
+        self.at += 1;
+
+        let projection = match &self.projection {
+            Some(projection) => projection,
+            None => match self.selection.resolve(record.fields(), self.header) {
+                Ok(projection) => self.projection.insert(projection),
+                Err(cause) => return Some(Err(cause)),
+            },
+        };
+        let len = record.len();
+        Some(
+            projection
+                .apply(record.fields)
+                .map(|fields| Record::new(record.idx, fields))
+                .map_err(|column| Error::MissingColumn { column, at, len }),
+        )
+    }
+}
+
+/// Tokens of a single record, skipping the contents of unselected fields.
+struct Tokens<'a, I>
+where
+    I: Iterator<Item = char>,
+{
+    iter: &'a mut Source<I>,
+    options: &'a tokenizer::Options,
+    projection: Option<&'a Projection>,
+    column: usize,
+}
+
+impl<I> Iterator for Tokens<'_, I>
+where
+    I: Iterator<Item = char>,
+{
+    type Item = Result<Token, tokenizer::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = match self.projection {
+            Some(projection) if !projection.contains(self.column) => {
+                core::skip(self.iter, self.options)
+            }
+            _ => core::next(self.iter, self.options),
+        };
+        if let Some(Ok(Token { kind, .. })) = &token {
+            match kind {
+                Kind::Delimiter => self.column += 1,
+                Kind::LineBreak => self.column = 0,
+                Kind::Field(_) => {}
+            }
+        }
+        token
+    }
+}
@@ -0,0 +1,577 @@
+//! Incremental parsing of input pushed in chunks, [`Tokenizer`] and
+//! [`Parser`].
+//!
+//! Unlike the pull-based [`tokenizer::Tokenizer`], which needs the whole
+//! input as an iterator, these are fed chunks as they arrive. Chunks may end
+//! anywhere, even within a UTF-8 character, a CRLF pair or a doubled quote;
+//! whatever is left undecided waits for the next chunk, or for `finish`.
+//!
+//! Both yield exactly what their pull-based counterparts would for the
+//! whole input, including how a quoted field left open is handled, see
+//! [`Unterminated`].
+
+use std::{mem, vec};
+
+use crate::{
+    recorder,
+    records::{self, Record},
+    token::{Kind, Token},
+    tokenizer::{
+        self,
+        core::{self, Directive},
+        options::{LineBreak, Unterminated},
+        ErrorKind, Warning, WarningKind,
+    },
+    ParseError,
+};
+
+/// A tokenizer fed one chunk of characters at a time.
+#[derive(Clone, Debug)]
+pub struct Tokenizer {
+    options: tokenizer::Options,
+    /// The options as configured, while those with the delimiter declared
+    /// by a `sep=` directive are in use.
+    configured: Option<tokenizer::Options>,
+    /// The characters at the start of the input, while they may still be a
+    /// `sep=` directive.
+    pending: Option<Vec<char>>,
+    idx: usize,
+    state: State,
+    tokens: Vec<Result<Token, tokenizer::Error>>,
+    warnings: Vec<Warning>,
+}
+
+/// A record parser fed one chunk of bytes at a time.
+#[derive(Clone, Debug)]
+pub struct Parser {
+    tokenizer: Tokenizer,
+    options: recorder::Options,
+    /// The start of a UTF-8 character split by the end of a chunk.
+    partial: Vec<u8>,
+    /// Bytes decoded so far.
+    bytes: u64,
+    /// Tokens of the current line.
+    line: Vec<Result<Token, tokenizer::Error>>,
+    records: Vec<Result<Record, Error>>,
+    /// Number of records read so far.
+    read: usize,
+    failed: bool,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("invalid UTF-8 at byte {at}")]
+    Utf8 { at: u64 },
+}
+
+/// What the characters so far leave undecided.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+enum State {
+    /// Nothing.
+    #[default]
+    Start,
+    /// An unquoted field, ended by a delimiter or line break.
+    Unquoted { idx: usize, buf: String, len: usize },
+    /// A quoted field.
+    Quoted(Quoted),
+    /// A quoted field followed by a carriage return, which may be followed
+    /// by a line feed.
+    QuotedCarriageReturn { at: usize, field: Quoted },
+    /// A quoted field followed by a quote, either doubled or closing it.
+    Quote { at: usize, field: Quoted },
+    /// A carriage return, which may be followed by a line feed.
+    CarriageReturn { idx: usize },
+}
+
+/// A quoted field being read.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Quoted {
+    /// The index of the opening quote.
+    idx: usize,
+    buf: String,
+    /// The characters following the opening quote, only kept when
+    /// recovering, see [`Unterminated::Recover`].
+    raw: String,
+    len: usize,
+    line_breaks: usize,
+}
+
+impl Tokenizer {
+    #[inline]
+    #[must_use]
+    pub const fn new(options: tokenizer::Options) -> Self {
+        let pending = if options.sep_directive() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+        Self {
+            options,
+            configured: None,
+            pending,
+            idx: 0,
+            state: State::Start,
+            tokens: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// The options in use, with the delimiter declared by a `sep=`
+    /// directive, if any.
+    #[inline]
+    #[must_use]
+    pub const fn options(&self) -> &tokenizer::Options {
+        &self.options
+    }
+
+    /// The delimiter declared by a `sep=` directive at the start of the
+    /// input, used instead of the configured one once read.
+    #[inline]
+    #[must_use]
+    pub fn declared_delimiter(&self) -> Option<char> {
+        self.configured.as_ref().map(|_| self.options.delimiter())
+    }
+
+    /// Tokenizes a chunk, yielding the tokens it completes.
+    pub fn feed(&mut self, chunk: &str) -> vec::Drain<'_, Result<Token, tokenizer::Error>> {
+        for ch in chunk.chars() {
+            self.push(ch);
+        }
+        self.tokens.drain(..)
+    }
+
+    /// Takes the warnings of the characters fed so far, which are kept until
+    /// taken.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        mem::take(&mut self.warnings)
+    }
+
+    /// Ends the input, yielding the last tokens, after which the tokenizer
+    /// starts over with a new input.
+    ///
+    /// A quoted field left open is handled as set by
+    /// [`tokenizer::Options::unterminated`].
+    pub fn finish(&mut self) -> vec::Drain<'_, Result<Token, tokenizer::Error>> {
+        self.sniff(true);
+        self.end();
+        self.idx = 0;
+        if let Some(configured) = self.configured.take() {
+            self.options = configured;
+        }
+        if self.options.sep_directive() {
+            self.pending = Some(Vec::new());
+        }
+        self.tokens.drain(..)
+    }
+
+    fn push(&mut self, ch: char) {
+        let idx = self.idx;
+        self.idx += 1;
+        if let Some(pending) = &mut self.pending {
+            pending.push(ch);
+            self.sniff(false);
+        } else {
+            self.step(idx, ch);
+        }
+    }
+
+    /// Reads a `sep=` directive at the start of the input once the pending
+    /// characters tell whether there is one, and tokenizes the rest of them.
+    fn sniff(&mut self, end: bool) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        let (delimiter, mut len) = match core::directive(pending, self.options.line_break(), end) {
+            Directive::Undecided => return,
+            Directive::Absent => (None, 0),
+            Directive::Declared { delimiter, len } => (Some(delimiter), len),
+        };
+        let pending = self.pending.take().unwrap_or_default();
+        if let Some(delimiter) = delimiter {
+            let mut declared = self.options.clone();
+            if declared.set_delimiter(delimiter).is_ok() {
+                self.configured = Some(mem::replace(&mut self.options, declared));
+            } else {
+                // The line break is left to end the line of the error.
+                self.error(core::SEP.len(), ErrorKind::SepDirective);
+                len = core::SEP.len() + 1;
+            }
+        }
+        for (idx, ch) in pending.into_iter().enumerate().skip(len) {
+            self.step(idx, ch);
+        }
+    }
+
+    fn step(&mut self, idx: usize, ch: char) {
+        let options = &self.options;
+        match mem::take(&mut self.state) {
+            State::Start => self.start(idx, ch),
+            State::Unquoted {
+                idx: start,
+                mut buf,
+                len,
+            } => {
+                if ch == options.delimiter() || options.line_break().contains(ch) {
+                    self.token(start, Kind::Field(buf));
+                    self.start(idx, ch);
+                } else if options.is_quote(ch) && options.quoting().is_strict() {
+                    self.error(idx, ErrorKind::LateQuote);
+                } else if !options.allow_controls() && ch.is_control() {
+                    let kind = match ch {
+                        '\r' => ErrorKind::CarriageReturn,
+                        '\n' => ErrorKind::LineFeed,
+                        _ch => ErrorKind::Control,
+                    };
+                    self.error(idx, kind);
+                } else {
+                    if options.is_quote(ch) {
+                        self.warn(idx, WarningKind::LateQuote);
+                    }
+                    if self.options.max_field_len() == Some(len) {
+                        self.error(idx, ErrorKind::FieldTooLong { max: len });
+                    } else {
+                        buf.push(ch);
+                        self.state = State::Unquoted {
+                            idx: start,
+                            buf,
+                            len: len + 1,
+                        };
+                    }
+                }
+            }
+            State::Quoted(mut field) => {
+                if options.unterminated().is_recover() {
+                    field.raw.push(ch);
+                }
+                if ch == '\r' {
+                    if options.line_break().contains('\r') {
+                        field.buf.push(ch);
+                        field.len += 1;
+                        self.state = State::QuotedCarriageReturn { at: idx, field };
+                    } else {
+                        self.error(idx, ErrorKind::CarriageReturn);
+                    }
+                } else if ch == '\n' {
+                    if options.line_break().contains('\n') {
+                        field.buf.push(ch);
+                        field.len += 1;
+                        field.line_breaks += 1;
+                        self.check(idx, field);
+                    } else {
+                        self.error(idx, ErrorKind::LineFeed);
+                    }
+                } else if ch.is_control() && ch != options.delimiter() {
+                    self.error(idx, ErrorKind::Control);
+                } else if ch == options.quote() {
+                    self.state = State::Quote { at: idx, field };
+                } else {
+                    field.buf.push(ch);
+                    field.len += 1;
+                    self.check(idx, field);
+                }
+            }
+            State::QuotedCarriageReturn { at, mut field } => {
+                match (options.line_break(), ch == '\n') {
+                    (LineBreak::Crlf | LineBreak::Any, true) => {
+                        if options.unterminated().is_recover() {
+                            field.raw.push(ch);
+                        }
+                        field.buf.push(ch);
+                        field.len += 1;
+                        field.line_breaks += 1;
+                        self.check(at, field);
+                    }
+                    (LineBreak::Cr | LineBreak::Any, false) => {
+                        field.line_breaks += 1;
+                        if self.check(at, field) {
+                            self.step(idx, ch);
+                        } else {
+                            self.start(idx, ch);
+                        }
+                    }
+                    (LineBreak::Lf, _) => unreachable!(),
+                    (LineBreak::Crlf, false) => {
+                        self.error(at, ErrorKind::CarriageReturn);
+                        self.start(idx, ch);
+                    }
+                    (LineBreak::Cr, true) => self.error(idx, ErrorKind::LineFeed),
+                }
+            }
+            State::Quote { at, mut field } => {
+                if ch == options.quote() {
+                    if options.unterminated().is_recover() {
+                        field.raw.push(ch);
+                    }
+                    field.buf.push(ch);
+                    field.len += 1;
+                    self.check(at, field);
+                } else if ch != options.delimiter() && !matches!(ch, '\r' | '\n') {
+                    if options.quoting().is_strict() {
+                        self.error(idx, ErrorKind::EarlyQuote);
+                    } else {
+                        self.warn(idx, WarningKind::EarlyQuote);
+                        self.state = State::Unquoted {
+                            idx: field.idx,
+                            buf: field.buf,
+                            len: field.len,
+                        };
+                        self.step(idx, ch);
+                    }
+                } else {
+                    self.token(field.idx, Kind::Field(field.buf));
+                    self.start(idx, ch);
+                }
+            }
+            State::CarriageReturn { idx: at } => match (options.line_break(), ch == '\n') {
+                (LineBreak::Crlf | LineBreak::Any, true) => self.token(at, Kind::LineBreak),
+                (LineBreak::Cr | LineBreak::Any, false) => {
+                    self.token(at, Kind::LineBreak);
+                    self.start(idx, ch);
+                }
+                (LineBreak::Lf, _) => unreachable!(),
+                (LineBreak::Crlf, false) => {
+                    self.error(at, ErrorKind::CarriageReturn);
+                    self.start(idx, ch);
+                }
+                (LineBreak::Cr, true) => self.error(idx, ErrorKind::LineFeed),
+            },
+        }
+    }
+
+    /// Starts a token.
+    fn start(&mut self, idx: usize, ch: char) {
+        let options = &self.options;
+        if options.is_quote(ch) {
+            self.state = State::Quoted(Quoted {
+                idx,
+                ..Quoted::default()
+            });
+        } else if ch == options.delimiter() {
+            self.token(idx, Kind::Delimiter);
+        } else if ch == '\r' {
+            if options.line_break().contains('\r') {
+                self.state = State::CarriageReturn { idx };
+            } else {
+                self.error(idx, ErrorKind::CarriageReturn);
+            }
+        } else if ch == '\n' {
+            if options.line_break().contains('\n') {
+                self.token(idx, Kind::LineBreak);
+            } else {
+                self.error(idx, ErrorKind::LineFeed);
+            }
+        } else if options.max_field_len() == Some(0) {
+            self.error(idx, ErrorKind::FieldTooLong { max: 0 });
+        } else {
+            self.state = State::Unquoted {
+                idx,
+                buf: ch.to_string(),
+                len: 1,
+            };
+        }
+    }
+
+    /// Continues a quoted field within the limits, or reports the first one
+    /// exceeded at `at`.
+    fn check(&mut self, at: usize, field: Quoted) -> bool {
+        let options = &self.options;
+        if let Some(max) = options
+            .max_quoted_line_breaks()
+            .filter(|max| field.line_breaks > *max)
+        {
+            self.error(at, ErrorKind::QuotedLineBreaks { max });
+            false
+        } else if let Some(max) = options.max_field_len().filter(|max| field.len > *max) {
+            self.error(at, ErrorKind::FieldTooLong { max });
+            false
+        } else {
+            self.state = State::Quoted(field);
+            true
+        }
+    }
+
+    fn end(&mut self) {
+        // Recovering tokenizes the following lines again, which may leave
+        // another quoted field open.
+        loop {
+            match mem::take(&mut self.state) {
+                State::Start => {}
+                State::Unquoted { idx, buf, .. } => self.token(idx, Kind::Field(buf)),
+                State::Quote { field, .. } => self.token(field.idx, Kind::Field(field.buf)),
+                State::QuotedCarriageReturn { at, .. } if self.options.line_break().is_crlf() => {
+                    self.error(at, ErrorKind::CarriageReturn);
+                }
+                State::QuotedCarriageReturn { at, mut field } => {
+                    field.line_breaks += 1;
+                    if self.check(at, field) {
+                        continue;
+                    }
+                }
+                State::Quoted(field) => match self.options.unterminated() {
+                    Unterminated::Strict => self.error(field.idx, ErrorKind::UnterminatedQuote),
+                    Unterminated::Lenient => self.token(field.idx, Kind::Field(field.buf)),
+                    Unterminated::Recover => {
+                        self.recover(&field);
+                        continue;
+                    }
+                },
+                State::CarriageReturn { idx } => {
+                    if self.options.line_break().is_crlf() {
+                        self.error(idx, ErrorKind::CarriageReturn);
+                    } else {
+                        self.token(idx, Kind::LineBreak);
+                    }
+                }
+            }
+            break;
+        }
+    }
+
+    /// Takes the rest of the line of a quoted field left open as the field,
+    /// and tokenizes the following lines again.
+    fn recover(&mut self, field: &Quoted) {
+        let line_break = self.options.line_break();
+        let end = field
+            .raw
+            .find(|ch| line_break.contains(ch))
+            .unwrap_or(field.raw.len());
+        self.token(field.idx, Kind::Field(field.raw[..end].to_owned()));
+        let skipped = field.raw[..end].chars().count();
+        for (offset, ch) in field.raw[end..].chars().enumerate() {
+            self.step(field.idx + 1 + skipped + offset, ch);
+        }
+    }
+
+    #[inline]
+    fn token(&mut self, idx: usize, kind: Kind) {
+        self.tokens.push(Ok(Token::new(idx, kind)));
+    }
+
+    #[inline]
+    fn error(&mut self, idx: usize, kind: ErrorKind) {
+        self.tokens.push(Err(tokenizer::Error::new(idx, kind)));
+    }
+
+    #[inline]
+    fn warn(&mut self, idx: usize, kind: WarningKind) {
+        if self.options.quoting().warns() {
+            self.warnings.push(Warning::new(idx, kind));
+        }
+    }
+}
+
+impl Parser {
+    #[inline]
+    #[must_use]
+    pub const fn new(tokenizer: tokenizer::Options, recorder: recorder::Options) -> Self {
+        Self {
+            tokenizer: Tokenizer::new(tokenizer),
+            options: recorder,
+            partial: Vec::new(),
+            bytes: 0,
+            line: Vec::new(),
+            records: Vec::new(),
+            read: 0,
+            failed: false,
+        }
+    }
+
+    /// Parses a chunk of UTF-8, yielding the records it completes.
+    ///
+    /// Invalid UTF-8 is reported once, after which the rest of the input is
+    /// ignored.
+    pub fn feed(&mut self, chunk: &[u8]) -> vec::Drain<'_, Result<Record, Error>> {
+        if self.failed {
+            return self.records.drain(..);
+        }
+        let mut chunk = chunk;
+        let mut joined;
+        if !self.partial.is_empty() {
+            joined = mem::take(&mut self.partial);
+            joined.extend_from_slice(chunk);
+            chunk = &joined;
+        }
+        let valid = match std::str::from_utf8(chunk) {
+            Ok(text) => text.len(),
+            Err(cause) => {
+                if cause.error_len().is_some() {
+                    self.failed = true;
+                    self.records.push(Err(Error::Utf8 {
+                        at: self.bytes + cause.valid_up_to() as u64,
+                    }));
+                } else {
+                    self.partial
+                        .extend_from_slice(&chunk[cause.valid_up_to()..]);
+                }
+                cause.valid_up_to()
+            }
+        };
+        let text = std::str::from_utf8(&chunk[..valid]).expect("validated above");
+        self.bytes += valid as u64;
+        self.feed_text(text);
+        self.records.drain(..)
+    }
+
+    /// Parses a chunk of text, yielding the records it completes.
+    pub fn feed_str(&mut self, chunk: &str) -> vec::Drain<'_, Result<Record, Error>> {
+        if self.failed || !self.partial.is_empty() {
+            return self.feed(chunk.as_bytes());
+        }
+        self.bytes += chunk.len() as u64;
+        self.feed_text(chunk);
+        self.records.drain(..)
+    }
+
+    /// Takes the warnings of the bytes fed so far, which are kept until
+    /// taken.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.tokenizer.take_warnings()
+    }
+
+    /// Ends the input, yielding the last records, after which the parser
+    /// starts over with a new input.
+    ///
+    /// A UTF-8 character cut short is reported, as are quoted fields left
+    /// open unless set otherwise, see [`tokenizer::Options::unterminated`].
+    pub fn finish(&mut self) -> vec::Drain<'_, Result<Record, Error>> {
+        if self.failed {
+            let options = self
+                .tokenizer
+                .configured
+                .take()
+                .unwrap_or_else(|| self.tokenizer.options.clone());
+            self.tokenizer = Tokenizer::new(options);
+        } else {
+            let records = &mut self.records;
+            self.line.extend(self.tokenizer.finish());
+            let mut line = self.line.drain(..).peekable();
+            while let Some(record) = records::next(&mut line, &self.options, &mut self.read) {
+                records.push(record.map_err(Error::from));
+            }
+            if !self.partial.is_empty() {
+                records.push(Err(Error::Utf8 { at: self.bytes }));
+            }
+        }
+        self.partial.clear();
+        self.bytes = 0;
+        self.line.clear();
+        self.read = 0;
+        self.failed = false;
+        self.records.drain(..)
+    }
+
+    fn feed_text(&mut self, text: &str) {
+        for token in self.tokenizer.feed(text) {
+            let line_break = matches!(&token, Ok(token) if token.is_line_break());
+            self.line.push(token);
+            if line_break {
+                let mut line = self.line.drain(..).peekable();
+                while let Some(record) = records::next(&mut line, &self.options, &mut self.read) {
+                    self.records.push(record.map_err(Error::from));
+                }
+            }
+        }
+    }
+}
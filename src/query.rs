@@ -0,0 +1,953 @@
+//! SQL-like queries over records, [`Query`] and [`execute`].
+//!
+//! A query selects expressions of the columns of each record, kept by
+//! `WHERE`, grouped by `GROUP BY` with the aggregates `count`, `sum`, `avg`,
+//! `min` and `max`, kept by `HAVING`, sorted by `ORDER BY` and cut by
+//! `LIMIT`, see [`parse`] for the syntax.
+//!
+//! Records are written as they are read, and reading stops once `LIMIT`
+//! records are written, unless the query groups or sorts them. Sorting with
+//! a limit holds no more than about twice the limit in memory.
+//!
+//! Fields are text, read as numbers or booleans where an operator needs one,
+//! see [`Value::detect`]. Empty fields are `NULL`, as are missing fields of
+//! short records, and `NULL` is written as an empty field.
+
+pub mod parse;
+
+use std::{cmp::Ordering, collections::HashMap, io, iter, mem};
+
+use crate::{
+    column::{self, Column},
+    records::Record,
+    value::Value,
+    writer::{self, Writer},
+    ParseError,
+};
+
+/// A parsed query, see [`parse`].
+#[derive(Clone, Debug)]
+pub struct Query {
+    items: Vec<Item>,
+    from: Option<String>,
+    filter: Option<Expr>,
+    group_by: Vec<Expr>,
+    having: Option<Expr>,
+    order_by: Vec<SortKey>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Column(#[from] column::Error),
+    #[error("{0}")]
+    Write(#[from] writer::Error),
+    #[error("aggregate functions are not allowed in {clause}")]
+    Aggregate { clause: &'static str },
+    #[error("ORDER BY position {position} is not the position of a selected column")]
+    Position { position: i64 },
+}
+
+#[derive(Clone, Debug)]
+enum Item {
+    /// Every column, `*`.
+    Wildcard,
+    /// An expression, named by its alias or as written.
+    Expr { expr: Expr, name: String },
+}
+
+#[derive(Clone, Debug)]
+struct SortKey {
+    expr: Expr,
+    descending: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Column(Column),
+    Literal(Scalar),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    In {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        negated: bool,
+    },
+    Function(Function, Vec<Expr>),
+    /// An aggregate of the argument, or of every record when `None`.
+    Aggregate(Aggregate, Option<Box<Expr>>),
+    /// The value of an aggregate, by its index in the plan.
+    Accumulated(usize),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Concat,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+enum Function {
+    Lower,
+    Upper,
+    Length,
+    Trim,
+    Abs,
+    Round,
+    Coalesce,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// The value of an expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Scalar {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+/// A query bound to the columns of its input.
+#[derive(Debug)]
+struct Plan {
+    names: Vec<String>,
+    items: Vec<Expr>,
+    filter: Option<Expr>,
+    group_by: Vec<Expr>,
+    having: Option<Expr>,
+    order_by: Vec<SortKey>,
+    limit: Option<usize>,
+    aggregates: Vec<(Aggregate, Option<Expr>)>,
+}
+
+/// Resolves columns to indices and extracts aggregates into the plan.
+struct Binder<'a> {
+    header: Option<&'a [String]>,
+    aggregates: Vec<(Aggregate, Option<Expr>)>,
+}
+
+#[derive(Clone, Debug)]
+enum Accumulator {
+    Count(i64),
+    Sum(Scalar),
+    Avg { sum: f64, count: usize },
+    Min(Scalar),
+    Max(Scalar),
+}
+
+/// A group of records, with the first of them to evaluate the columns that
+/// are not aggregated.
+struct Group {
+    fields: Vec<String>,
+    accumulators: Vec<Accumulator>,
+}
+
+/// Output rows sorted by their keys, keeping only the first `limit` rows.
+struct Sorter<'a> {
+    keys: &'a [SortKey],
+    limit: Option<usize>,
+    rows: Vec<(Vec<Scalar>, Vec<String>)>,
+}
+
+/// Runs a query over records, writing its result headed by the names of the
+/// selected columns, and returns the number of records written.
+///
+/// With a header, the first record names the columns; without one, `*`
+/// selects as many columns as the first record has.
+///
+/// # Errors
+///
+/// If a record fails to parse, a column cannot be resolved, an aggregate is
+/// used where it is not allowed, or if writing fails.
+pub fn execute<I, W>(
+    query: &Query,
+    records: I,
+    header: bool,
+    writer: &mut Writer<W>,
+) -> Result<usize, Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+    W: io::Write,
+{
+    let mut records = records
+        .map(|record| record.map(Record::into_fields))
+        .peekable();
+    let header = if header {
+        Some(records.next().transpose()?.unwrap_or_default())
+    } else {
+        None
+    };
+    let width = match (&header, records.peek()) {
+        (Some(header), _) => header.len(),
+        (None, Some(Ok(fields))) => fields.len(),
+        (None, _) => 0,
+    };
+    let plan = Plan::new(query, header.as_deref(), width)?;
+    writer.write_record(&plan.names)?;
+    if plan.is_grouped() {
+        plan.grouped(records, writer)
+    } else {
+        plan.stream(records, writer)
+    }
+}
+
+impl Query {
+    /// The source named by `FROM`, if any.
+    #[inline]
+    #[must_use]
+    pub fn from(&self) -> Option<&str> {
+        self.from.as_deref()
+    }
+}
+
+impl Plan {
+    fn new(query: &Query, header: Option<&[String]>, width: usize) -> Result<Self, Error> {
+        let (names, mut items): (Vec<_>, Vec<_>) = query
+            .items
+            .iter()
+            .flat_map(|item| match item {
+                Item::Wildcard => (0..width)
+                    .map(|idx| {
+                        let name = header
+                            .and_then(|header| header.get(idx))
+                            .cloned()
+                            .unwrap_or_else(|| format!("#{}", idx + 1));
+                        (name, Expr::Column(Column::Index(idx)))
+                    })
+                    .collect(),
+                Item::Expr { expr, name } => vec![(name.clone(), expr.clone())],
+            })
+            .unzip();
+        let mut order_by = query
+            .order_by
+            .iter()
+            .map(|key| {
+                let expr = match &key.expr {
+                    Expr::Literal(Scalar::Integer(position)) => usize::try_from(*position - 1)
+                        .ok()
+                        .and_then(|idx| items.get(idx))
+                        .cloned()
+                        .ok_or(Error::Position {
+                            position: *position,
+                        })?,
+                    expr => {
+                        let mut expr = expr.clone();
+                        expr.substitute(&names, &items);
+                        expr
+                    }
+                };
+                Ok(SortKey {
+                    expr,
+                    descending: key.descending,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut filter = query.filter.clone();
+        let mut group_by = query.group_by.clone();
+        let mut having = query.having.clone();
+        if let Some(having) = &mut having {
+            having.substitute(&names, &items);
+        }
+
+        let mut binder = Binder {
+            header,
+            aggregates: Vec::new(),
+        };
+        for expr in &mut items {
+            binder.bind(expr, None)?;
+        }
+        if let Some(filter) = &mut filter {
+            binder.bind(filter, Some("WHERE"))?;
+        }
+        for expr in &mut group_by {
+            binder.bind(expr, Some("GROUP BY"))?;
+        }
+        if let Some(having) = &mut having {
+            binder.bind(having, None)?;
+        }
+        for key in &mut order_by {
+            binder.bind(&mut key.expr, None)?;
+        }
+        Ok(Self {
+            names,
+            items,
+            filter,
+            group_by,
+            having,
+            order_by,
+            limit: query.limit,
+            aggregates: binder.aggregates,
+        })
+    }
+
+    fn is_grouped(&self) -> bool {
+        !self.group_by.is_empty() || !self.aggregates.is_empty() || self.having.is_some()
+    }
+
+    fn keeps(&self, fields: &[String]) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.eval(fields, &[]).truth() == Some(true))
+    }
+
+    fn row(&self, fields: &[String], accumulated: &[Scalar]) -> Vec<String> {
+        self.items
+            .iter()
+            .map(|expr| expr.eval(fields, accumulated).into_field())
+            .collect()
+    }
+
+    fn sort_key(&self, fields: &[String], accumulated: &[Scalar]) -> Vec<Scalar> {
+        self.order_by
+            .iter()
+            .map(|key| key.expr.eval(fields, accumulated))
+            .collect()
+    }
+
+    /// Writes each kept record as it is read, unless sorting.
+    fn stream<I, W>(&self, records: I, writer: &mut Writer<W>) -> Result<usize, Error>
+    where
+        I: Iterator<Item = Result<Vec<String>, ParseError>>,
+        W: io::Write,
+    {
+        if self.limit == Some(0) {
+            return Ok(0);
+        }
+        let mut sorter = Sorter::new(&self.order_by, self.limit);
+        let mut written = 0;
+        for fields in records {
+            let fields = fields?;
+            if !self.keeps(&fields) {
+                continue;
+            }
+            let row = self.row(&fields, &[]);
+            if self.order_by.is_empty() {
+                writer.write_record(&row)?;
+                written += 1;
+                if self.limit == Some(written) {
+                    break;
+                }
+            } else {
+                sorter.push(self.sort_key(&fields, &[]), row);
+            }
+        }
+        for row in sorter.finish() {
+            writer.write_record(&row)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Aggregates the kept records by group, then writes a record per group,
+    /// or a single record when aggregating without grouping.
+    fn grouped<I, W>(&self, records: I, writer: &mut Writer<W>) -> Result<usize, Error>
+    where
+        I: Iterator<Item = Result<Vec<String>, ParseError>>,
+        W: io::Write,
+    {
+        let mut index = HashMap::new();
+        let mut groups = Vec::new();
+        for fields in records {
+            let fields = fields?;
+            if !self.keeps(&fields) {
+                continue;
+            }
+            let key = self
+                .group_by
+                .iter()
+                .map(|expr| match expr.eval(&fields, &[]) {
+                    Scalar::Null => None,
+                    value => Some(value.into_field()),
+                })
+                .collect::<Vec<_>>();
+            let idx = *index.entry(key).or_insert_with(|| {
+                groups.push(self.group(fields.clone()));
+                groups.len() - 1
+            });
+            let group: &mut Group = &mut groups[idx];
+            for (accumulator, (_, argument)) in group.accumulators.iter_mut().zip(&self.aggregates)
+            {
+                accumulator.add(
+                    argument
+                        .as_ref()
+                        .map(|argument| argument.eval(&fields, &[])),
+                );
+            }
+        }
+        if groups.is_empty() && self.group_by.is_empty() {
+            groups.push(self.group(Vec::new()));
+        }
+
+        let mut sorter = Sorter::new(&self.order_by, self.limit);
+        for group in groups {
+            let accumulated = group
+                .accumulators
+                .into_iter()
+                .map(Accumulator::finish)
+                .collect::<Vec<_>>();
+            let kept = self.having.as_ref().is_none_or(|having| {
+                having.eval(&group.fields, &accumulated).truth() == Some(true)
+            });
+            if kept {
+                sorter.push(
+                    self.sort_key(&group.fields, &accumulated),
+                    self.row(&group.fields, &accumulated),
+                );
+            }
+        }
+        let mut written = 0;
+        for row in sorter.finish() {
+            writer.write_record(&row)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    fn group(&self, fields: Vec<String>) -> Group {
+        Group {
+            fields,
+            accumulators: self
+                .aggregates
+                .iter()
+                .map(|(aggregate, _)| Accumulator::new(*aggregate))
+                .collect(),
+        }
+    }
+}
+
+impl Binder<'_> {
+    /// Binds an expression, where aggregates are not allowed if the clause
+    /// is given.
+    fn bind(&mut self, expr: &mut Expr, clause: Option<&'static str>) -> Result<(), Error> {
+        match expr {
+            Expr::Column(column) => {
+                *column = Column::Index(column.resolve(self.header)?);
+                Ok(())
+            }
+            Expr::Aggregate(aggregate, argument) => {
+                if let Some(clause) = clause {
+                    return Err(Error::Aggregate { clause });
+                }
+                let aggregate = *aggregate;
+                let mut argument = argument.take().map(|argument| *argument);
+                if let Some(argument) = &mut argument {
+                    self.bind(argument, Some("an aggregate"))?;
+                }
+                *expr = Expr::Accumulated(self.aggregates.len());
+                self.aggregates.push((aggregate, argument));
+                Ok(())
+            }
+            expr => expr
+                .children_mut()
+                .into_iter()
+                .try_for_each(|child| self.bind(child, clause)),
+        }
+    }
+}
+
+impl Expr {
+    /// Replaces the columns named as a selected column by its expression, so
+    /// that `HAVING` and `ORDER BY` may refer to aliases.
+    fn substitute(&mut self, names: &[String], items: &[Self]) {
+        if let Self::Column(Column::Name(name)) = self {
+            if let Some(idx) = names.iter().position(|selected| selected == name) {
+                *self = items[idx].clone();
+            }
+            return;
+        }
+        for child in self.children_mut() {
+            child.substitute(names, items);
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            Self::Column(_) | Self::Literal(_) | Self::Accumulated(_) => Vec::new(),
+            Self::Negate(expr) | Self::Not(expr) | Self::IsNull { expr, .. } => vec![&mut **expr],
+            Self::Binary(lhs, _, rhs) => vec![&mut **lhs, &mut **rhs],
+            Self::In { expr, list, .. } => iter::once(&mut **expr).chain(list).collect(),
+            Self::Between {
+                expr, low, high, ..
+            } => vec![&mut **expr, &mut **low, &mut **high],
+            Self::Like { expr, pattern, .. } => vec![&mut **expr, &mut **pattern],
+            Self::Function(_, arguments) => arguments.iter_mut().collect(),
+            Self::Aggregate(_, argument) => argument
+                .iter_mut()
+                .map(|argument| &mut **argument)
+                .collect(),
+        }
+    }
+
+    /// Evaluates the expression over the fields of a record and the values
+    /// of the aggregates of its group.
+    fn eval(&self, fields: &[String], accumulated: &[Scalar]) -> Scalar {
+        let eval = |expr: &Self| expr.eval(fields, accumulated);
+        match self {
+            Self::Column(Column::Index(idx)) => match fields.get(*idx) {
+                Some(field) if !field.is_empty() => Scalar::Text(field.clone()),
+                _ => Scalar::Null,
+            },
+            Self::Column(Column::Name(_)) | Self::Aggregate(..) => Scalar::Null,
+            Self::Literal(value) => value.clone(),
+            Self::Accumulated(idx) => accumulated.get(*idx).cloned().unwrap_or(Scalar::Null),
+            Self::Negate(expr) => Scalar::Integer(0).arithmetic(Operator::Subtract, &eval(expr)),
+            Self::Not(expr) => Scalar::from(eval(expr).truth().map(|truth| !truth)),
+            Self::Binary(lhs, Operator::And, rhs) => {
+                Scalar::from(and(eval(lhs).truth(), || eval(rhs).truth()))
+            }
+            Self::Binary(lhs, Operator::Or, rhs) => {
+                Scalar::from(not(and(not(eval(lhs).truth()), || not(eval(rhs).truth()))))
+            }
+            Self::Binary(lhs, operator, rhs) => eval(lhs).arithmetic(*operator, &eval(rhs)),
+            Self::IsNull { expr, negated } => {
+                Scalar::Boolean((eval(expr) == Scalar::Null) != *negated)
+            }
+            Self::In {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = eval(expr);
+                if value == Scalar::Null {
+                    return Scalar::Null;
+                }
+                let found = list
+                    .iter()
+                    .any(|item| value.compare(&eval(item)) == Some(Ordering::Equal));
+                Scalar::Boolean(found != *negated)
+            }
+            Self::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = eval(expr);
+                let between = and(value.compare(&eval(low)).map(Ordering::is_ge), || {
+                    value.compare(&eval(high)).map(Ordering::is_le)
+                });
+                Scalar::from(between.map(|between| between != *negated))
+            }
+            Self::Like {
+                expr,
+                pattern,
+                negated,
+            } => match (eval(expr), eval(pattern)) {
+                (Scalar::Null, _) | (_, Scalar::Null) => Scalar::Null,
+                (value, pattern) => {
+                    Scalar::Boolean(like(&value.into_field(), &pattern.into_field()) != *negated)
+                }
+            },
+            Self::Function(function, arguments) => {
+                function.call(arguments.iter().map(eval).collect())
+            }
+        }
+    }
+}
+
+impl Function {
+    /// Whether the function takes that many arguments.
+    fn takes(self, count: usize) -> bool {
+        match self {
+            Self::Round => matches!(count, 1 | 2),
+            Self::Coalesce => count >= 1,
+            _ => count == 1,
+        }
+    }
+
+    fn call(self, mut arguments: Vec<Scalar>) -> Scalar {
+        if self == Self::Coalesce {
+            return arguments
+                .into_iter()
+                .find(|argument| *argument != Scalar::Null)
+                .unwrap_or(Scalar::Null);
+        }
+        let value = mem::replace(&mut arguments[0], Scalar::Null);
+        if value == Scalar::Null {
+            return Scalar::Null;
+        }
+        match self {
+            Self::Lower => Scalar::Text(value.into_field().to_lowercase()),
+            Self::Upper => Scalar::Text(value.into_field().to_uppercase()),
+            Self::Length => Scalar::Integer(
+                i64::try_from(value.into_field().chars().count()).unwrap_or(i64::MAX),
+            ),
+            Self::Trim => Scalar::Text(value.into_field().trim().to_owned()),
+            Self::Abs => match value.value() {
+                Value::Integer(integer) => {
+                    integer.checked_abs().map_or(Scalar::Null, Scalar::Integer)
+                }
+                Value::Float(float) => Scalar::Float(float.abs()),
+                _ => Scalar::Null,
+            },
+            Self::Round => {
+                let digits = match arguments.get(1).map(Scalar::value) {
+                    None => 0,
+                    Some(Value::Integer(digits)) => {
+                        i32::try_from(digits.clamp(-308, 308)).unwrap_or_default()
+                    }
+                    Some(_) => return Scalar::Null,
+                };
+                let scale = 10_f64.powi(digits);
+                value.value().as_f64().map_or(Scalar::Null, |number| {
+                    Scalar::float((number * scale).round() / scale)
+                })
+            }
+            Self::Coalesce => unreachable!(),
+        }
+    }
+}
+
+impl Scalar {
+    /// A float, or `NULL` if it is not finite.
+    fn float(float: f64) -> Self {
+        if float.is_finite() {
+            Self::Float(float)
+        } else {
+            Self::Null
+        }
+    }
+
+    /// The value, text being detected as a number or boolean.
+    fn value(&self) -> Value<'_> {
+        match self {
+            Self::Null => Value::Empty,
+            Self::Boolean(boolean) => Value::Boolean(*boolean),
+            Self::Integer(integer) => Value::Integer(*integer),
+            Self::Float(float) => Value::Float(*float),
+            Self::Text(text) => Value::detect(text),
+        }
+    }
+
+    /// Whether the value is true, `NULL` being neither true nor false.
+    fn truth(&self) -> Option<bool> {
+        if *self == Self::Null {
+            return None;
+        }
+        Some(match self.value() {
+            Value::Boolean(boolean) => boolean,
+            Value::Integer(integer) => integer != 0,
+            Value::Float(float) => float != 0.0,
+            Value::Empty | Value::Text(_) => false,
+        })
+    }
+
+    /// Compares two values, as numbers if both are numbers, as booleans if
+    /// both are booleans, and as text otherwise, `NULL` comparing to nothing.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        if *self == Self::Null || *other == Self::Null {
+            return None;
+        }
+        match (self.value(), other.value()) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Some(lhs.cmp(&rhs)),
+            (Value::Boolean(lhs), Value::Boolean(rhs)) => Some(lhs.cmp(&rhs)),
+            (lhs, rhs) => match (lhs.as_f64(), rhs.as_f64()) {
+                (Some(lhs), Some(rhs)) => Some(lhs.total_cmp(&rhs)),
+                _ => Some(self.to_field().cmp(&other.to_field())),
+            },
+        }
+    }
+
+    /// Orders `NULL` first, then numbers, then anything else, as
+    /// [`Order::Numeric`](crate::sort::Order::Numeric) does.
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        let rank = |value: &Self| match value {
+            Self::Null => 0,
+            value if value.value().as_f64().is_some() => 1,
+            _ => 2,
+        };
+        rank(self)
+            .cmp(&rank(other))
+            .then_with(|| self.compare(other).unwrap_or(Ordering::Equal))
+    }
+
+    /// Applies a binary operator other than `AND` and `OR`.
+    ///
+    /// Arithmetic is on integers unless either operand is a float or the
+    /// result overflows, except for division, which is always on floats.
+    /// Arithmetic on anything but numbers, and division by zero, is `NULL`.
+    fn arithmetic(&self, operator: Operator, other: &Self) -> Self {
+        let comparison = |test: fn(Ordering) -> bool| Self::from(self.compare(other).map(test));
+        match operator {
+            Operator::Equal => return comparison(Ordering::is_eq),
+            Operator::NotEqual => return comparison(Ordering::is_ne),
+            Operator::Less => return comparison(Ordering::is_lt),
+            Operator::LessOrEqual => return comparison(Ordering::is_le),
+            Operator::Greater => return comparison(Ordering::is_gt),
+            Operator::GreaterOrEqual => return comparison(Ordering::is_ge),
+            Operator::Concat => {
+                return match (self, other) {
+                    (Self::Null, _) | (_, Self::Null) => Self::Null,
+                    (lhs, rhs) => Self::Text(lhs.to_field() + &rhs.to_field()),
+                };
+            }
+            Operator::And | Operator::Or => return Self::Null,
+            _ => (),
+        }
+        let (lhs, rhs) = (self.value(), other.value());
+        if let (Value::Integer(lhs), Value::Integer(rhs)) = (lhs, rhs) {
+            let integer = match operator {
+                Operator::Add => lhs.checked_add(rhs),
+                Operator::Subtract => lhs.checked_sub(rhs),
+                Operator::Multiply => lhs.checked_mul(rhs),
+                Operator::Remainder if rhs == 0 => return Self::Null,
+                Operator::Remainder => lhs.checked_rem(rhs),
+                _ => None,
+            };
+            if let Some(integer) = integer {
+                return Self::Integer(integer);
+            }
+        }
+        let (Some(lhs), Some(rhs)) = (lhs.as_f64(), rhs.as_f64()) else {
+            return Self::Null;
+        };
+        Self::float(match operator {
+            Operator::Add => lhs + rhs,
+            Operator::Subtract => lhs - rhs,
+            Operator::Multiply => lhs * rhs,
+            Operator::Divide => lhs / rhs,
+            _ => lhs % rhs,
+        })
+    }
+
+    fn to_field(&self) -> String {
+        self.clone().into_field()
+    }
+
+    fn into_field(self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Boolean(boolean) => boolean.to_string(),
+            Self::Integer(integer) => integer.to_string(),
+            Self::Float(float) => float.to_string(),
+            Self::Text(text) => text,
+        }
+    }
+}
+
+impl From<Option<bool>> for Scalar {
+    fn from(value: Option<bool>) -> Self {
+        value.map_or(Self::Null, Self::Boolean)
+    }
+}
+
+impl From<Value<'_>> for Scalar {
+    fn from(value: Value<'_>) -> Self {
+        match value {
+            Value::Empty => Self::Null,
+            Value::Boolean(boolean) => Self::Boolean(boolean),
+            Value::Integer(integer) => Self::Integer(integer),
+            Value::Float(float) => Self::Float(float),
+            Value::Text(text) => Self::Text(text.to_owned()),
+        }
+    }
+}
+
+impl Accumulator {
+    const fn new(aggregate: Aggregate) -> Self {
+        match aggregate {
+            Aggregate::Count => Self::Count(0),
+            Aggregate::Sum => Self::Sum(Scalar::Null),
+            Aggregate::Avg => Self::Avg { sum: 0.0, count: 0 },
+            Aggregate::Min => Self::Min(Scalar::Null),
+            Aggregate::Max => Self::Max(Scalar::Null),
+        }
+    }
+
+    /// Adds the value of the argument for a record, or the record itself for
+    /// `count(*)`.
+    ///
+    /// `NULL` is ignored, and so are values other than numbers by `sum` and
+    /// `avg`.
+    fn add(&mut self, value: Option<Scalar>) {
+        let Some(value) = value else {
+            if let Self::Count(count) = self {
+                *count += 1;
+            }
+            return;
+        };
+        if value == Scalar::Null {
+            return;
+        }
+        match self {
+            Self::Count(count) => *count += 1,
+            Self::Sum(sum) => {
+                if value.value().as_f64().is_some() {
+                    *sum = if *sum == Scalar::Null {
+                        Scalar::from(value.value())
+                    } else {
+                        sum.arithmetic(Operator::Add, &value)
+                    };
+                }
+            }
+            Self::Avg { sum, count } => {
+                if let Some(number) = value.value().as_f64() {
+                    *sum += number;
+                    *count += 1;
+                }
+            }
+            Self::Min(min) => {
+                if *min == Scalar::Null || value.total_cmp(min).is_lt() {
+                    *min = value;
+                }
+            }
+            Self::Max(max) => {
+                if *max == Scalar::Null || value.total_cmp(max).is_gt() {
+                    *max = value;
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn finish(self) -> Scalar {
+        match self {
+            Self::Count(count) => Scalar::Integer(count),
+            Self::Avg { count: 0, .. } => Scalar::Null,
+            Self::Avg { sum, count } => Scalar::float(sum / count as f64),
+            Self::Sum(value) | Self::Min(value) | Self::Max(value) => value,
+        }
+    }
+}
+
+impl<'a> Sorter<'a> {
+    const fn new(keys: &'a [SortKey], limit: Option<usize>) -> Self {
+        Self {
+            keys,
+            limit,
+            rows: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, key: Vec<Scalar>, row: Vec<String>) {
+        self.rows.push((key, row));
+        if let Some(limit) = self.limit {
+            if self.rows.len() >= limit.saturating_mul(2).max(64) {
+                self.trim();
+            }
+        }
+    }
+
+    /// Sorts the rows, stably, and drops those past the limit.
+    fn trim(&mut self) {
+        if !self.keys.is_empty() {
+            self.rows.sort_by(|(lhs, _), (rhs, _)| {
+                iter::zip(self.keys, iter::zip(lhs, rhs))
+                    .map(|(key, (lhs, rhs))| {
+                        let ordering = lhs.total_cmp(rhs);
+                        if key.descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        if let Some(limit) = self.limit {
+            self.rows.truncate(limit);
+        }
+    }
+
+    fn finish(mut self) -> impl Iterator<Item = Vec<String>> {
+        self.trim();
+        self.rows.into_iter().map(|(_, row)| row)
+    }
+}
+
+/// Three-valued `AND`, evaluating the right-hand side only if needed.
+fn and(lhs: Option<bool>, rhs: impl FnOnce() -> Option<bool>) -> Option<bool> {
+    match lhs {
+        Some(false) => Some(false),
+        lhs => match (lhs, rhs()) {
+            (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+    }
+}
+
+/// Three-valued `NOT`.
+fn not(value: Option<bool>) -> Option<bool> {
+    value.map(|value| !value)
+}
+
+/// Whether text matches a `LIKE` pattern, where `%` matches any run of
+/// characters and `_` any one character, ignoring ASCII case.
+fn like(text: &str, pattern: &str) -> bool {
+    let text = text
+        .chars()
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let pattern = pattern
+        .chars()
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let (mut t, mut p) = (0, 0);
+    // The position after the last `%` and the text it has matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&ch) if ch == '_' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((after, matched)) => {
+                    p = after;
+                    t = matched + 1;
+                    backtrack = Some((after, t));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '%')
+}
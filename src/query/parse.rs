@@ -0,0 +1,654 @@
+//! Parsing queries into a [`Query`].
+//!
+//! ```text
+//! query      := "select" item ("," item)* ["from" source]
+//!               ["where" expression]
+//!               ["group" "by" expression ("," expression)*]
+//!               ["having" expression]
+//!               ["order" "by" expression ["asc" | "desc"] ("," ...)*]
+//!               ["limit" integer] [";"]
+//! item       := "*" | expression [["as"] name]
+//! source     := name | 'string'
+//! expression := and ("or" and)*
+//! and        := not ("and" not)*
+//! not        := "not" not | comparison
+//! comparison := sum [("=" | "==" | "!=" | "<>" | "<" | "<=" | ">" | ">=") sum
+//!                   | "is" ["not"] "null"
+//!                   | ["not"] "in" "(" expression ("," expression)* ")"
+//!                   | ["not"] "like" sum
+//!                   | ["not"] "between" sum "and" sum]
+//! sum        := product (("+" | "-") product)*
+//! product    := concat (("*" | "/" | "%") concat)*
+//! concat     := unary ("||" unary)*
+//! unary      := ("-" | "+") unary | primary
+//! primary    := number | 'string' | "true" | "false" | "null"
+//!             | function "(" [expression ("," expression)*] ")"
+//!             | aggregate "(" ("*" | expression) ")"
+//!             | column | "(" expression ")"
+//! column     := name | #number
+//! name       := word | "name" | `name`
+//! ```
+//!
+//! Keywords are case-insensitive, unlike column names, and name columns only
+//! when quoted. The functions are `lower`, `upper`, `length`, `trim`, `abs`,
+//! `round` and `coalesce`, and the aggregates `count`, `sum`, `avg`, `min`
+//! and `max`. `count(*)` counts records. Column numbers start at 1, and so do
+//! positions in `ORDER BY`; `HAVING` and `ORDER BY` may also name a selected
+//! column by its alias. Quotes within strings and quoted names are escaped by
+//! doubling them.
+
+use std::str::FromStr;
+
+use super::{Aggregate, Column, Expr, Function, Item, Operator, Query, Scalar, SortKey};
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("invalid query at {at}: {kind}")]
+pub struct Error {
+    at: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, strum::Display)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    #[strum(to_string = "unterminated string")]
+    UnterminatedString,
+    #[strum(to_string = "unexpected character '{ch}'")]
+    UnexpectedChar { ch: char },
+    #[strum(to_string = "unexpected end of the query")]
+    UnexpectedEnd,
+    #[strum(to_string = "unexpected '{lexeme}'")]
+    Unexpected { lexeme: String },
+    #[strum(to_string = "'{value}' is not a number")]
+    NotANumber { value: String },
+    #[strum(to_string = "column numbers start at 1")]
+    Zero,
+    #[strum(to_string = "no function is named '{name}'")]
+    UnknownFunction { name: String },
+    #[strum(to_string = "{function} does not take {count} arguments")]
+    Arguments { function: String, count: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Lexeme {
+    Word(String),
+    Name(String),
+    String(String),
+    Number(String),
+    Index(usize),
+    Operator(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Words that are not column names unless quoted.
+const KEYWORDS: [&str; 21] = [
+    "select", "from", "where", "group", "by", "having", "order", "asc", "desc", "limit", "as",
+    "and", "or", "not", "is", "null", "in", "like", "between", "true", "false",
+];
+
+const OPERATORS: [&str; 15] = [
+    "<=", ">=", "<>", "!=", "==", "||", "=", "<", ">", "+", "-", "*", "/", "%", ";",
+];
+
+struct Parser<'a> {
+    source: &'a str,
+    /// Lexemes with the character positions they start and end at.
+    lexemes: Vec<(usize, usize, Lexeme)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Error {
+    #[inline]
+    #[must_use]
+    pub const fn new(at: usize, kind: ErrorKind) -> Self {
+        Self { at, kind }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn at(&self) -> &usize {
+        &self.at
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            source: s,
+            lexemes: lex(s)?,
+            pos: 0,
+            end: s.chars().count(),
+        };
+        let query = parser.query()?;
+        parser.next_if(&Lexeme::Operator(";"));
+        match parser.lexemes.get(parser.pos) {
+            Some((at, _, lexeme)) => Err(Error::new(
+                *at,
+                ErrorKind::Unexpected {
+                    lexeme: lexeme.to_string(),
+                },
+            )),
+            None => Ok(query),
+        }
+    }
+}
+
+fn lex(s: &str) -> Result<Vec<(usize, usize, Lexeme)>, Error> {
+    let mut lexemes = Vec::new();
+    let mut chars = s.chars().enumerate().peekable();
+    while let Some(&(at, ch)) = chars.peek() {
+        let lexeme = match ch {
+            ch if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                match ch {
+                    '(' => Lexeme::Open,
+                    ')' => Lexeme::Close,
+                    _ => Lexeme::Comma,
+                }
+            }
+            '\'' | '"' | '`' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == ch => {
+                            if chars.next_if(|(_, next)| *next == ch).is_none() {
+                                break;
+                            }
+                            string.push(ch);
+                        }
+                        Some((_, ch)) => string.push(ch),
+                        None => return Err(Error::new(at, ErrorKind::UnterminatedString)),
+                    }
+                }
+                if ch == '\'' {
+                    Lexeme::String(string)
+                } else {
+                    Lexeme::Name(string)
+                }
+            }
+            '#' => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                    digits.push(ch);
+                }
+                match digits.parse::<usize>() {
+                    Ok(0) => return Err(Error::new(at, ErrorKind::Zero)),
+                    Ok(number) => Lexeme::Index(number - 1),
+                    Err(_) => return Err(Error::new(at, ErrorKind::NotANumber { value: digits })),
+                }
+            }
+            ch if ch.is_ascii_digit()
+                || ch == '.'
+                    && chars
+                        .clone()
+                        .nth(1)
+                        .is_some_and(|(_, ch)| ch.is_ascii_digit()) =>
+            {
+                let mut number = String::new();
+                while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_digit() || *ch == '.')
+                {
+                    number.push(ch);
+                }
+                if let Some((_, e)) = chars.next_if(|(_, ch)| matches!(ch, 'e' | 'E')) {
+                    number.push(e);
+                    if let Some((_, sign)) = chars.next_if(|(_, ch)| matches!(ch, '+' | '-')) {
+                        number.push(sign);
+                    }
+                    while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                        number.push(ch);
+                    }
+                }
+                Lexeme::Number(number)
+            }
+            ch if ch.is_alphabetic() || ch == '_' => {
+                let mut word = String::new();
+                while let Some((_, ch)) =
+                    chars.next_if(|(_, ch)| ch.is_alphanumeric() || matches!(ch, '_' | '.'))
+                {
+                    word.push(ch);
+                }
+                Lexeme::Word(word)
+            }
+            _ => {
+                let ahead = chars.clone().take(2).map(|(_, ch)| ch).collect::<String>();
+                let Some(operator) = OPERATORS
+                    .iter()
+                    .find(|operator| ahead.starts_with(**operator))
+                else {
+                    return Err(Error::new(at, ErrorKind::UnexpectedChar { ch }));
+                };
+                for _ in 0..operator.len() {
+                    chars.next();
+                }
+                Lexeme::Operator(operator)
+            }
+        };
+        let end = chars.peek().map_or(s.chars().count(), |(end, _)| *end);
+        lexemes.push((at, end, lexeme));
+    }
+    Ok(lexemes)
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+impl std::fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Word(word) | Self::Number(word) => f.write_str(word),
+            Self::Name(name) => write!(f, "\"{}\"", name.replace('"', "\"\"")),
+            Self::String(string) => write!(f, "'{}'", string.replace('\'', "''")),
+            Self::Index(idx) => write!(f, "#{}", idx + 1),
+            Self::Operator(operator) => f.write_str(operator),
+            Self::Open => f.write_str("("),
+            Self::Close => f.write_str(")"),
+            Self::Comma => f.write_str(","),
+        }
+    }
+}
+
+impl Parser<'_> {
+    fn query(&mut self) -> Result<Query, Error> {
+        self.expect_keyword("select")?;
+        let mut items = vec![self.item()?];
+        while self.next_if(&Lexeme::Comma) {
+            items.push(self.item()?);
+        }
+        let from = if self.keyword("from") {
+            match self.next()? {
+                (_, _, Lexeme::Word(source) | Lexeme::Name(source) | Lexeme::String(source)) => {
+                    Some(source)
+                }
+                (at, _, lexeme) => return Err(unexpected(at, &lexeme)),
+            }
+        } else {
+            None
+        };
+        let filter = if self.keyword("where") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        let mut group_by = Vec::new();
+        if self.keyword("group") {
+            self.expect_keyword("by")?;
+            group_by.push(self.expression()?);
+            while self.next_if(&Lexeme::Comma) {
+                group_by.push(self.expression()?);
+            }
+        }
+        let having = if self.keyword("having") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        let mut order_by = Vec::new();
+        if self.keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let expr = self.expression()?;
+                let descending = if self.keyword("desc") {
+                    true
+                } else {
+                    self.keyword("asc");
+                    false
+                };
+                order_by.push(SortKey { expr, descending });
+                if !self.next_if(&Lexeme::Comma) {
+                    break;
+                }
+            }
+        }
+        let limit = if self.keyword("limit") {
+            let at = self.at();
+            match self.next()? {
+                (_, _, Lexeme::Number(number)) => Some(
+                    number
+                        .parse()
+                        .map_err(|_| Error::new(at, ErrorKind::NotANumber { value: number }))?,
+                ),
+                (at, _, lexeme) => return Err(unexpected(at, &lexeme)),
+            }
+        } else {
+            None
+        };
+        Ok(Query {
+            items,
+            from,
+            filter,
+            group_by,
+            having,
+            order_by,
+            limit,
+        })
+    }
+
+    fn item(&mut self) -> Result<Item, Error> {
+        if self.next_if(&Lexeme::Operator("*")) {
+            return Ok(Item::Wildcard);
+        }
+        let start = self.at();
+        let expr = self.expression()?;
+        let end = self.lexemes[self.pos - 1].1;
+        let alias = match self.lexemes.get(self.pos) {
+            Some((_, _, Lexeme::Word(word))) if word.eq_ignore_ascii_case("as") => {
+                self.pos += 1;
+                match self.next()? {
+                    (_, _, Lexeme::Word(name) | Lexeme::Name(name)) => Some(name),
+                    (at, _, lexeme) => return Err(unexpected(at, &lexeme)),
+                }
+            }
+            Some((_, _, Lexeme::Word(name))) if !is_keyword(name) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            }
+            Some((_, _, Lexeme::Name(name))) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            }
+            _ => None,
+        };
+        let name = alias.unwrap_or_else(|| match &expr {
+            Expr::Column(Column::Name(name)) => name.clone(),
+            _ => self.source.chars().skip(start).take(end - start).collect(),
+        });
+        Ok(Item::Expr { expr, name })
+    }
+
+    fn expression(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = binary(expr, Operator::Or, self.and()?);
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = binary(expr, Operator::And, self.not()?);
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, Error> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, Error> {
+        let expr = self.sum()?;
+        let operator = match self.lexemes.get(self.pos) {
+            Some((_, _, Lexeme::Operator(operator))) => match *operator {
+                "=" | "==" => Some(Operator::Equal),
+                "!=" | "<>" => Some(Operator::NotEqual),
+                "<" => Some(Operator::Less),
+                "<=" => Some(Operator::LessOrEqual),
+                ">" => Some(Operator::Greater),
+                ">=" => Some(Operator::GreaterOrEqual),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(operator) = operator {
+            self.pos += 1;
+            return Ok(binary(expr, operator, self.sum()?));
+        }
+        if self.keyword("is") {
+            let negated = self.keyword("not");
+            self.expect_keyword("null")?;
+            return Ok(Expr::IsNull {
+                expr: Box::new(expr),
+                negated,
+            });
+        }
+        let negated = self.keyword("not");
+        let expr = Box::new(expr);
+        if self.keyword("in") {
+            self.expect(&Lexeme::Open)?;
+            let mut list = vec![self.expression()?];
+            while self.next_if(&Lexeme::Comma) {
+                list.push(self.expression()?);
+            }
+            self.expect(&Lexeme::Close)?;
+            Ok(Expr::In {
+                expr,
+                list,
+                negated,
+            })
+        } else if self.keyword("like") {
+            Ok(Expr::Like {
+                expr,
+                pattern: Box::new(self.sum()?),
+                negated,
+            })
+        } else if self.keyword("between") {
+            let low = Box::new(self.sum()?);
+            self.expect_keyword("and")?;
+            Ok(Expr::Between {
+                expr,
+                low,
+                high: Box::new(self.sum()?),
+                negated,
+            })
+        } else if negated {
+            let (at, _, lexeme) = self.next()?;
+            Err(unexpected(at, &lexeme))
+        } else {
+            Ok(*expr)
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.product()?;
+        loop {
+            let operator = if self.next_if(&Lexeme::Operator("+")) {
+                Operator::Add
+            } else if self.next_if(&Lexeme::Operator("-")) {
+                Operator::Subtract
+            } else {
+                return Ok(expr);
+            };
+            expr = binary(expr, operator, self.product()?);
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.concat()?;
+        loop {
+            let operator = if self.next_if(&Lexeme::Operator("*")) {
+                Operator::Multiply
+            } else if self.next_if(&Lexeme::Operator("/")) {
+                Operator::Divide
+            } else if self.next_if(&Lexeme::Operator("%")) {
+                Operator::Remainder
+            } else {
+                return Ok(expr);
+            };
+            expr = binary(expr, operator, self.concat()?);
+        }
+    }
+
+    fn concat(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.unary()?;
+        while self.next_if(&Lexeme::Operator("||")) {
+            expr = binary(expr, Operator::Concat, self.unary()?);
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if self.next_if(&Lexeme::Operator("-")) {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.next_if(&Lexeme::Operator("+")) {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let (at, _, lexeme) = self.next()?;
+        match lexeme {
+            Lexeme::Number(number) => {
+                let value = if let Ok(integer) = number.parse() {
+                    Scalar::Integer(integer)
+                } else if let Ok(float) = number.parse() {
+                    Scalar::Float(float)
+                } else {
+                    return Err(Error::new(at, ErrorKind::NotANumber { value: number }));
+                };
+                Ok(Expr::Literal(value))
+            }
+            Lexeme::String(string) => Ok(Expr::Literal(Scalar::Text(string))),
+            Lexeme::Index(idx) => Ok(Expr::Column(Column::Index(idx))),
+            Lexeme::Name(name) => Ok(Expr::Column(Column::Name(name))),
+            Lexeme::Open => {
+                let expr = self.expression()?;
+                self.expect(&Lexeme::Close)?;
+                Ok(expr)
+            }
+            Lexeme::Word(word) if self.next_if(&Lexeme::Open) => self.call(at, &word),
+            Lexeme::Word(word) if word.eq_ignore_ascii_case("true") => {
+                Ok(Expr::Literal(Scalar::Boolean(true)))
+            }
+            Lexeme::Word(word) if word.eq_ignore_ascii_case("false") => {
+                Ok(Expr::Literal(Scalar::Boolean(false)))
+            }
+            Lexeme::Word(word) if word.eq_ignore_ascii_case("null") => {
+                Ok(Expr::Literal(Scalar::Null))
+            }
+            Lexeme::Word(word) if !is_keyword(&word) => Ok(Expr::Column(Column::Name(word))),
+            lexeme => Err(unexpected(at, &lexeme)),
+        }
+    }
+
+    /// Parses the arguments of a call, after its opening parenthesis.
+    fn call(&mut self, at: usize, name: &str) -> Result<Expr, Error> {
+        if let Ok(aggregate) = name.parse::<Aggregate>() {
+            let argument = if aggregate == Aggregate::Count && self.next_if(&Lexeme::Operator("*"))
+            {
+                None
+            } else {
+                Some(Box::new(self.expression()?))
+            };
+            self.expect(&Lexeme::Close)?;
+            return Ok(Expr::Aggregate(aggregate, argument));
+        }
+        let function = name.parse::<Function>().map_err(|_| {
+            Error::new(
+                at,
+                ErrorKind::UnknownFunction {
+                    name: name.to_owned(),
+                },
+            )
+        })?;
+        let mut arguments = Vec::new();
+        if !self.next_if(&Lexeme::Close) {
+            arguments.push(self.expression()?);
+            while self.next_if(&Lexeme::Comma) {
+                arguments.push(self.expression()?);
+            }
+            self.expect(&Lexeme::Close)?;
+        }
+        if !function.takes(arguments.len()) {
+            return Err(Error::new(
+                at,
+                ErrorKind::Arguments {
+                    function: function.to_string(),
+                    count: arguments.len(),
+                },
+            ));
+        }
+        Ok(Expr::Function(function, arguments))
+    }
+
+    fn at(&self) -> usize {
+        self.lexemes
+            .get(self.pos)
+            .map_or(self.end, |(at, _, _)| *at)
+    }
+
+    fn next(&mut self) -> Result<(usize, usize, Lexeme), Error> {
+        let lexeme = self
+            .lexemes
+            .get(self.pos)
+            .cloned()
+            .ok_or(Error::new(self.end, ErrorKind::UnexpectedEnd))?;
+        self.pos += 1;
+        Ok(lexeme)
+    }
+
+    fn next_if(&mut self, expected: &Lexeme) -> bool {
+        let matched = self
+            .lexemes
+            .get(self.pos)
+            .is_some_and(|(_, _, lexeme)| lexeme == expected);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.lexemes.get(self.pos).is_some_and(
+            |(_, _, lexeme)| matches!(lexeme, Lexeme::Word(word) if word.eq_ignore_ascii_case(keyword)),
+        );
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), Error> {
+        if self.keyword(keyword) {
+            return Ok(());
+        }
+        let (at, _, lexeme) = self.next()?;
+        Err(unexpected(at, &lexeme))
+    }
+
+    fn expect(&mut self, expected: &Lexeme) -> Result<(), Error> {
+        match self.next()? {
+            (_, _, lexeme) if lexeme == *expected => Ok(()),
+            (at, _, lexeme) => Err(unexpected(at, &lexeme)),
+        }
+    }
+}
+
+fn binary(lhs: Expr, operator: Operator, rhs: Expr) -> Expr {
+    Expr::Binary(Box::new(lhs), operator, Box::new(rhs))
+}
+
+fn unexpected(at: usize, lexeme: &Lexeme) -> Error {
+    Error::new(
+        at,
+        ErrorKind::Unexpected {
+            lexeme: lexeme.to_string(),
+        },
+    )
+}
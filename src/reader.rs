@@ -0,0 +1,175 @@
+//! Record reader reusing its allocations, [`Reader`].
+//!
+//! [`crate::records::Records`] yields every record as newly allocated
+//! strings. A reader instead tokenizes fields straight into a
+//! [`StringRecord`], which can be read into again and again without
+//! allocating once it has grown to fit the records.
+
+use std::{borrow::Borrow, cell::Cell};
+
+use crate::{
+    recorder, records,
+    string_record::{Ends, StringRecord},
+    token::Token,
+    tokenizer::{
+        self,
+        core::{self, Source},
+        Warning,
+    },
+    ParseError,
+};
+
+/// Reads records of characters into a [`StringRecord`].
+#[derive(Clone, Debug)]
+pub struct Reader<I, T, R>
+where
+    I: Iterator<Item = char>,
+{
+    iter: Source<I>,
+    tokenizer: T,
+    recorder: R,
+    /// Number of records read so far.
+    read: usize,
+    /// The length of field contents counted against the record length
+    /// limit.
+    measure: fn(&str) -> usize,
+}
+
+/// Converting to a csv record reader.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = char> + Sized,
+{
+    #[inline]
+    fn csv_reader(self) -> Reader<Self, &'static tokenizer::Options, &'static recorder::Options> {
+        self.csv_reader_custom(&tokenizer::Options::DEFAULT, &recorder::Options::DEFAULT)
+    }
+
+    #[inline]
+    fn csv_reader_custom<T, R>(self, tokenizer: T, recorder: R) -> Reader<Self, T, R> {
+        Reader {
+            iter: Source::new(self),
+            tokenizer,
+            recorder,
+            read: 0,
+            measure: str::len,
+        }
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = char> {}
+
+impl<I, T, R> Reader<I, T, R>
+where
+    I: Iterator<Item = char>,
+{
+    /// Counts field contents against the record length limit as measured,
+    /// rather than in UTF-8 bytes, such as in the bytes they were read from.
+    #[inline]
+    #[must_use]
+    pub(crate) fn with_measure(mut self, measure: fn(&str) -> usize) -> Self {
+        self.measure = measure;
+        self
+    }
+}
+
+/// Tokens whose field contents are appended to a buffer, the length of
+/// which is kept up to date in `len`, and its length as measured in `size`.
+struct Appending<'a, I>
+where
+    I: Iterator<Item = char>,
+{
+    iter: &'a mut Source<I>,
+    options: &'a tokenizer::Options,
+    buf: &'a mut String,
+    len: &'a Cell<usize>,
+    measure: fn(&str) -> usize,
+    size: &'a Cell<usize>,
+}
+
+impl<I, T, R> Reader<I, T, R>
+where
+    I: Iterator<Item = char>,
+    T: Borrow<tokenizer::Options>,
+    R: Borrow<recorder::Options>,
+{
+    /// Reads the next record into `record`, replacing its fields, and
+    /// returns whether there was one.
+    ///
+    /// Records are read as by [`crate::records::Records`], including
+    /// resuming at the next line after an error.
+    ///
+    /// # Errors
+    ///
+    /// If the record fails to parse, in which case `record` is cleared.
+    pub fn read_record(&mut self, record: &mut StringRecord) -> Result<bool, ParseError> {
+        record.clear();
+        let (idx, buf, ends) = record.parts_mut();
+        let len = Cell::new(0);
+        let size = Cell::new(0);
+        let tokens = Appending {
+            iter: &mut self.iter,
+            options: self.tokenizer.borrow(),
+            buf,
+            len: &len,
+            measure: self.measure,
+            size: &size,
+        };
+        let mut ends = Ends {
+            ends,
+            len: &len,
+            size: &size,
+            pushed: 0,
+        };
+        let result = records::next_into(
+            &mut tokens.peekable(),
+            self.recorder.borrow(),
+            &mut self.read,
+            &mut ends,
+        );
+        match result {
+            None => Ok(false),
+            Some(Ok(at)) => {
+                *idx = at;
+                Ok(true)
+            }
+            Some(Err(cause)) => {
+                record.clear();
+                Err(cause)
+            }
+        }
+    }
+
+    /// Takes the warnings of the records read so far, which are kept until
+    /// taken.
+    #[inline]
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.iter.take_warnings()
+    }
+
+    /// The delimiter declared by a `sep=` directive at the start of the
+    /// input, used instead of the configured one once read.
+    #[inline]
+    #[must_use]
+    pub fn declared_delimiter(&self) -> Option<char> {
+        self.iter.declared_delimiter()
+    }
+}
+
+impl<I> Iterator for Appending<'_, I>
+where
+    I: Iterator<Item = char>,
+{
+    type Item = Result<Token, tokenizer::Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.buf.len();
+        let token = core::next_into(self.iter, self.options, self.buf);
+        let added = (self.measure)(&self.buf[start..]);
+        self.size.set(self.size.get() + added);
+        self.len.set(self.buf.len());
+        token
+    }
+}
@@ -1,7 +1,7 @@
 pub mod core;
 pub mod options;
 
-use crate::token::Token;
+use crate::{diagnostic::Severity, token::Token};
 use core::Position;
 pub use options::Options;
 use std::iter::{FusedIterator, Peekable};
@@ -35,6 +35,12 @@ pub enum ErrorKind {
         to_string = "field tokens needs to be separated. Either insert a delimiter or a line break."
     )]
     UndelimitedFields,
+    #[strum(to_string = "record has more fields than the maximum of {max}")]
+    TooManyFields { max: usize },
+    #[strum(to_string = "record is longer than the maximum record length of {max} bytes")]
+    RecordTooLong { max: usize },
+    #[strum(to_string = "input has more records than the maximum of {max}")]
+    TooManyRecords { max: usize },
 }
 
 /// Converting to a csv token iterator.
@@ -69,6 +75,12 @@ impl Error {
         Self { at, kind }
     }
 
+    #[inline]
+    #[must_use]
+    pub const fn at(&self) -> &usize {
+        &self.at
+    }
+
     #[inline]
     #[must_use]
     pub const fn kind(&self) -> &ErrorKind {
@@ -76,6 +88,71 @@ impl Error {
     }
 }
 
+impl ErrorKind {
+    /// A stable code identifying the kind, see [`crate::diagnostic`].
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::TrailingDelimiter => "CSV101",
+            Self::UndelimitedFields => "CSV102",
+            Self::TooManyFields { .. } => "CSV103",
+            Self::RecordTooLong { .. } => "CSV104",
+            Self::TooManyRecords { .. } => "CSV105",
+        }
+    }
+
+    /// The configured maximum exceeded, if the kind is of a limit.
+    #[inline]
+    #[must_use]
+    pub const fn max(&self) -> Option<usize> {
+        match self {
+            Self::TooManyFields { max }
+            | Self::RecordTooLong { max }
+            | Self::TooManyRecords { max } => Some(*max),
+            Self::TrailingDelimiter | Self::UndelimitedFields => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// What went wrong, without the resolution the [`Display`] output goes
+    /// on with, which is the [`Self::hint`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self {
+            Self::TrailingDelimiter => "delimiter at the end of a line".to_owned(),
+            Self::UndelimitedFields => "field tokens need to be separated".to_owned(),
+            Self::TooManyFields { .. }
+            | Self::RecordTooLong { .. }
+            | Self::TooManyRecords { .. } => self.to_string(),
+        }
+    }
+
+    /// How the error may be resolved, if there is more to say than the
+    /// message.
+    #[must_use]
+    pub const fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::TrailingDelimiter => Some(
+                "to resolve, either:
+ - quote the empty field
+ - enable trailing delimiters
+ - remove the trailing delimiter",
+            ),
+            Self::UndelimitedFields => Some("either insert a delimiter or a line break"),
+            Self::TooManyFields { .. }
+            | Self::RecordTooLong { .. }
+            | Self::TooManyRecords { .. } => None,
+        }
+    }
+}
+
 impl<I, O> FusedIterator for Recorder<I, O>
 where
     I: Iterator<Item = Token>,
@@ -83,7 +160,7 @@ where
 {
 }
 
-impl<'a, I> Iterator for Recorder<I, &'a Options>
+impl<I> Iterator for Recorder<I, &Options>
 where
     I: Iterator<Item = Token>,
 {
@@ -94,7 +171,7 @@ where
     }
 }
 
-impl<'a, I> Iterator for Recorder<I, &'a mut Options>
+impl<I> Iterator for Recorder<I, &mut Options>
 where
     I: Iterator<Item = Token>,
 {
@@ -17,6 +17,15 @@ pub trait Builder: private_builder::Sealed {
     #[must_use]
     fn with_at_least_one(self, at_least_one: bool) -> Self;
 
+    #[must_use]
+    fn with_max_fields(self, max_fields: Option<usize>) -> Self;
+
+    #[must_use]
+    fn with_max_record_bytes(self, max_record_bytes: Option<usize>) -> Self;
+
+    #[must_use]
+    fn with_max_records(self, max_records: Option<usize>) -> Self;
+
     #[must_use]
     fn with_trailing_delimiter(self, trailing_delimiter: bool) -> Self;
 }
@@ -24,12 +33,21 @@ pub trait Builder: private_builder::Sealed {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Options {
     at_least_one: bool,
+    /// Maximum number of fields of a record.
+    max_fields: Option<usize>,
+    /// Maximum number of bytes of the fields of a record, after unescaping.
+    max_record_bytes: Option<usize>,
+    /// Maximum number of records, after which reading stops.
+    max_records: Option<usize>,
     trailing_delimiter: bool,
 }
 
 impl Options {
     pub const DEFAULT: Self = Self {
         at_least_one: false,
+        max_fields: None,
+        max_record_bytes: None,
+        max_records: None,
         trailing_delimiter: false,
     };
 
@@ -49,6 +67,54 @@ impl Options {
         self.at_least_one = at_least_one;
     }
 
+    #[inline]
+    #[must_use]
+    pub const fn max_fields(&self) -> Option<usize> {
+        self.max_fields
+    }
+
+    #[inline]
+    pub fn max_fields_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_fields
+    }
+
+    #[inline]
+    pub fn set_max_fields(&mut self, max_fields: Option<usize>) {
+        self.max_fields = max_fields;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn max_record_bytes(&self) -> Option<usize> {
+        self.max_record_bytes
+    }
+
+    #[inline]
+    pub fn max_record_bytes_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_record_bytes
+    }
+
+    #[inline]
+    pub fn set_max_record_bytes(&mut self, max_record_bytes: Option<usize>) {
+        self.max_record_bytes = max_record_bytes;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn max_records(&self) -> Option<usize> {
+        self.max_records
+    }
+
+    #[inline]
+    pub fn max_records_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_records
+    }
+
+    #[inline]
+    pub fn set_max_records(&mut self, max_records: Option<usize>) {
+        self.max_records = max_records;
+    }
+
     #[inline]
     #[must_use]
     pub const fn trailing_delimiter(&self) -> bool {
@@ -73,6 +139,24 @@ impl Builder for &mut Options {
         self
     }
 
+    #[inline]
+    fn with_max_fields(self, max_fields: Option<usize>) -> Self {
+        self.set_max_fields(max_fields);
+        self
+    }
+
+    #[inline]
+    fn with_max_record_bytes(self, max_record_bytes: Option<usize>) -> Self {
+        self.set_max_record_bytes(max_record_bytes);
+        self
+    }
+
+    #[inline]
+    fn with_max_records(self, max_records: Option<usize>) -> Self {
+        self.set_max_records(max_records);
+        self
+    }
+
     #[inline]
     fn with_trailing_delimiter(self, trailing_delimiter: bool) -> Self {
         self.set_trailing_delimiter(trailing_delimiter);
@@ -87,6 +171,24 @@ impl Builder for Options {
         self
     }
 
+    #[inline]
+    fn with_max_fields(mut self, max_fields: Option<usize>) -> Self {
+        self.set_max_fields(max_fields);
+        self
+    }
+
+    #[inline]
+    fn with_max_record_bytes(mut self, max_record_bytes: Option<usize>) -> Self {
+        self.set_max_record_bytes(max_record_bytes);
+        self
+    }
+
+    #[inline]
+    fn with_max_records(mut self, max_records: Option<usize>) -> Self {
+        self.set_max_records(max_records);
+        self
+    }
+
     #[inline]
     fn with_trailing_delimiter(mut self, trailing_delimiter: bool) -> Self {
         self.set_trailing_delimiter(trailing_delimiter);
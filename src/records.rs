@@ -0,0 +1,328 @@
+//! Record iterator, [`Records`].
+//!
+//! Groups a stream of tokenizer results into whole records without
+//! collecting the entire input first, unlike [`crate::parse`].
+
+use std::{cell::Cell, iter::Peekable};
+
+use crate::{
+    recorder::{self, ErrorKind, Into as _},
+    token::{Kind, Token},
+    tokenizer, ParseError,
+};
+
+/// A record along with the index of its first character.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Record {
+    pub idx: usize,
+    pub fields: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Records<I, O>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    iter: Peekable<I>,
+    options: O,
+    /// Number of records read so far.
+    read: usize,
+}
+
+/// Converting to a csv record iterator.
+#[allow(private_bounds)]
+pub trait Into
+where
+    Self: Iterator<Item = Result<Token, tokenizer::Error>> + Sized,
+{
+    #[inline]
+    fn csv_records(self) -> Records<Self, &'static recorder::Options> {
+        self.csv_records_custom(&recorder::Options::DEFAULT)
+    }
+
+    #[inline]
+    fn csv_records_custom<O>(self, options: O) -> Records<Self, O> {
+        Records {
+            iter: self.peekable(),
+            options,
+            read: 0,
+        }
+    }
+}
+
+impl<I> Into for I where I: Iterator<Item = Result<Token, tokenizer::Error>> {}
+
+impl Record {
+    #[inline]
+    #[must_use]
+    pub const fn new(idx: usize, fields: Vec<String>) -> Self {
+        Self { idx, fields }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn idx(&self) -> usize {
+        self.idx
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_fields(self) -> Vec<String> {
+        self.fields
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl<I> Iterator for Records<I, &recorder::Options>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    type Item = Result<Record, ParseError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        next(&mut self.iter, self.options, &mut self.read)
+    }
+}
+
+impl<I> Iterator for Records<I, &mut recorder::Options>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    type Item = Result<Record, ParseError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        next(&mut self.iter, self.options, &mut self.read)
+    }
+}
+
+impl<I> Iterator for Records<I, recorder::Options>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    type Item = Result<Record, ParseError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        next(&mut self.iter, &self.options, &mut self.read)
+    }
+}
+
+/// Where the fields of a record being read go.
+pub(crate) trait Fields {
+    /// Number of fields kept.
+    fn len(&self) -> usize;
+
+    /// Keeps the field just read, returning its length in bytes.
+    ///
+    /// The contents of fields may have been put aside as they were
+    /// tokenized, in which case `field` is empty.
+    fn push(&mut self, field: String) -> usize;
+
+    /// Keeps an empty field that is not even quoted, such as between two
+    /// delimiters, returning its length in bytes.
+    #[inline]
+    fn push_bare(&mut self) -> usize {
+        self.push(String::new())
+    }
+
+    /// Drops the field last kept.
+    fn pop(&mut self);
+}
+
+impl Fields for Vec<String> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn push(&mut self, field: String) -> usize {
+        let len = field.len();
+        self.push(field);
+        len
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        self.pop();
+    }
+}
+
+/// Reads the next record, counting it in `read`.
+///
+/// After an error, the rest of the offending line is skipped so that
+/// iteration resumes at the next record instead of reporting its leftovers.
+/// Once [`recorder::Options::max_records`] is exceeded, that is reported and
+/// nothing is read anymore.
+pub(crate) fn next<I>(
+    iter: &mut Peekable<I>,
+    options: &recorder::Options,
+    read: &mut usize,
+) -> Option<Result<Record, ParseError>>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    let mut fields = Vec::new();
+    let idx = next_into(iter, options, read, &mut fields)?;
+    Some(idx.map(|idx| Record::new(idx, fields)))
+}
+
+/// Reads the next record like [`next`], but into `fields`, returning the
+/// index of its first character.
+///
+/// On error, `fields` holds whatever was kept before it.
+pub(crate) fn next_into<I, F>(
+    iter: &mut Peekable<I>,
+    options: &recorder::Options,
+    read: &mut usize,
+    fields: &mut F,
+) -> Option<Result<usize, ParseError>>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+    F: Fields,
+{
+    let idx = match iter.peek()? {
+        Ok(token) => token.idx(),
+        Err(cause) => *cause.at(),
+    };
+    if let Some(max) = options.max_records() {
+        if *read > max {
+            return None;
+        }
+        if *read == max {
+            *read += 1;
+            let cause = recorder::Error::new(idx, ErrorKind::TooManyRecords { max });
+            return Some(Err(cause.into()));
+        }
+    }
+    *read += 1;
+    if iter.peek()?.is_err() {
+        let cause = iter.next()?.err()?;
+        skip_line(iter);
+        return Some(Err(cause.into()));
+    }
+
+    let max_fields = options.max_fields().unwrap_or(usize::MAX);
+    let max_bytes = options.max_record_bytes().unwrap_or(usize::MAX);
+    let mut token_error = None;
+    let mut record_error = None;
+    let mut bytes = 0_usize;
+    let last = Cell::new(idx);
+    let tokenized = Cell::new(0);
+    let mut kept = 0;
+    let shunt = Shunt {
+        iter: &mut *iter,
+        error: &mut token_error,
+        last: &last,
+        fields: &tokenized,
+    };
+    // Fields past a limit are still read to the end of the record, but not
+    // kept.
+    for field in shunt.csv_record_custom(options) {
+        // The recorder makes up the fields of no token, which are bare.
+        let bare = tokenized.get() == kept;
+        kept = tokenized.get();
+        match field {
+            Ok(_) if record_error.is_some() => {}
+            Ok(field) => {
+                let len = if bare {
+                    fields.push_bare()
+                } else {
+                    fields.push(field)
+                };
+                bytes = bytes.saturating_add(len);
+                let kind = if fields.len() > max_fields {
+                    ErrorKind::TooManyFields { max: max_fields }
+                } else if bytes > max_bytes {
+                    ErrorKind::RecordTooLong { max: max_bytes }
+                } else {
+                    continue;
+                };
+                fields.pop();
+                record_error = Some(recorder::Error::new(last.get(), kind));
+            }
+            Err(cause) => {
+                record_error.get_or_insert(cause);
+            }
+        }
+    }
+
+    if let Some(cause) = token_error {
+        skip_line(iter);
+        Some(Err(cause.into()))
+    } else if let Some(cause) = record_error {
+        Some(Err(cause.into()))
+    } else {
+        Some(Ok(idx))
+    }
+}
+
+fn skip_line<I>(iter: &mut I)
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    for token in iter {
+        if let Ok(Token {
+            idx: _,
+            kind: Kind::LineBreak,
+        }) = token
+        {
+            break;
+        }
+    }
+}
+
+/// Yields tokens until the first tokenizer error, which is stored instead.
+struct Shunt<'a, I> {
+    iter: &'a mut I,
+    error: &'a mut Option<tokenizer::Error>,
+    /// The index of the last token yielded.
+    last: &'a Cell<usize>,
+    /// Number of field tokens yielded.
+    fields: &'a Cell<usize>,
+}
+
+impl<I> Iterator for Shunt<'_, I>
+where
+    I: Iterator<Item = Result<Token, tokenizer::Error>>,
+{
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.iter.next()? {
+            Ok(token) => {
+                self.last.set(token.idx());
+                if token.kind().is_field() {
+                    self.fields.set(self.fields.get() + 1);
+                }
+                Some(token)
+            }
+            Err(cause) => {
+                *self.error = Some(cause);
+                None
+            }
+        }
+    }
+}
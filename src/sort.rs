@@ -0,0 +1,466 @@
+//! Sorting records by key columns, [`sort`].
+//!
+//! Records are sorted in memory until [`Options::budget`] is exceeded, after
+//! which sorted runs are spilled to temporary files and merged back. Runs are
+//! kept closed until merged, so that only the runs of one merge are open at
+//! a time.
+
+pub mod options;
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    convert::Infallible,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem,
+    rc::Rc,
+    str::FromStr,
+};
+
+use tempfile::{NamedTempFile, TempPath};
+
+pub use options::Options;
+
+use crate::{
+    column::{self, Column},
+    records::Record,
+    value::Value,
+    ParseError,
+};
+
+/// Most runs merged at once, so as to bound the number of open files.
+const FAN_IN: usize = 64;
+
+/// How the fields of a key column compare.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Order {
+    /// By their characters.
+    #[default]
+    Lexical,
+    /// By their value as a number, non-numeric fields being ordered first.
+    Numeric,
+    /// By their characters, except that runs of digits compare as numbers,
+    /// so that `file2` comes before `file10`.
+    Natural,
+}
+
+/// A column to sort by.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Key {
+    pub column: Column,
+    pub order: Order,
+    pub descending: bool,
+}
+
+/// Sorted records, without the header.
+#[derive(Debug)]
+pub struct Sorted {
+    header: Option<Vec<String>>,
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Memory(std::vec::IntoIter<Vec<String>>),
+    Merge(Merge),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Column(#[from] column::Error),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// A [`Key`] resolved to a column index.
+#[derive(Clone, Copy, Debug)]
+struct Resolved {
+    idx: usize,
+    order: Order,
+    descending: bool,
+}
+
+/// Sorts records by the given keys, or by whole records when there are none.
+///
+/// The sort is stable. With [`Options::header`], the first record is set
+/// aside as the header and names in the keys refer to it.
+///
+/// # Errors
+///
+/// If a record fails to parse, a key column cannot be resolved, or a
+/// temporary file cannot be written.
+pub fn sort<I>(records: I, keys: &[Key], options: &Options) -> Result<Sorted, Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    let mut records = records.peekable();
+    let header = match records.next_if(|_| options.header()) {
+        Some(record) => Some(record?.into_fields()),
+        None => None,
+    };
+    let keys = keys
+        .iter()
+        .map(|key| {
+            Ok(Resolved {
+                idx: key.column.resolve(header.as_deref())?,
+                order: key.order,
+                descending: key.descending,
+            })
+        })
+        .collect::<Result<Rc<[_]>, column::Error>>()?;
+
+    let mut chunk = Vec::new();
+    let mut size = 0;
+    let mut runs = Vec::new();
+    for record in records {
+        let fields = record?.into_fields();
+        size += footprint(&fields);
+        chunk.push(fields);
+        if size > options.budget() {
+            chunk.sort_by(|lhs, rhs| compare(&keys, lhs, rhs));
+            runs.push(spill(chunk.drain(..), options)?);
+            size = 0;
+        }
+    }
+    chunk.sort_by(|lhs, rhs| compare(&keys, lhs, rhs));
+    if runs.is_empty() {
+        return Ok(Sorted {
+            header,
+            inner: Inner::Memory(chunk.into_iter()),
+        });
+    }
+    if !chunk.is_empty() {
+        runs.push(spill(chunk.into_iter(), options)?);
+    }
+
+    while runs.len() > FAN_IN {
+        let mut merged = Vec::with_capacity(runs.len().div_ceil(FAN_IN));
+        let mut groups = runs.into_iter();
+        loop {
+            let group = groups.by_ref().take(FAN_IN).collect::<Vec<_>>();
+            if group.is_empty() {
+                break;
+            }
+            let merge = Merge::new(group, Rc::clone(&keys))?;
+            merged.push(spill_results(merge, options)?);
+        }
+        runs = merged;
+    }
+    Ok(Sorted {
+        header,
+        inner: Inner::Merge(Merge::new(runs, keys)?),
+    })
+}
+
+impl Order {
+    #[must_use]
+    pub fn compare(self, lhs: &str, rhs: &str) -> Ordering {
+        match self {
+            Self::Lexical => lhs.cmp(rhs),
+            Self::Numeric => {
+                let number = |field| Value::detect(field).as_f64();
+                match (number(lhs), number(rhs)) {
+                    (Some(lhs), Some(rhs)) => lhs.total_cmp(&rhs),
+                    (None, Some(_)) => Ordering::Less,
+                    (Some(_), None) => Ordering::Greater,
+                    (None, None) => lhs.cmp(rhs),
+                }
+            }
+            Self::Natural => natural(lhs, rhs),
+        }
+    }
+}
+
+impl Key {
+    #[inline]
+    #[must_use]
+    pub const fn new(column: Column, order: Order, descending: bool) -> Self {
+        Self {
+            column,
+            order,
+            descending,
+        }
+    }
+}
+
+/// Parses a column, see [`Column::from_str`], optionally followed by
+/// `:lexical`, `:numeric` or `:natural` and by `:asc` or `:desc`.
+impl FromStr for Key {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut column = s;
+        let mut order = None;
+        let mut descending = None;
+        while let Some((rest, modifier)) = column.rsplit_once(':') {
+            match modifier {
+                "lexical" if order.is_none() => order = Some(Order::Lexical),
+                "numeric" if order.is_none() => order = Some(Order::Numeric),
+                "natural" if order.is_none() => order = Some(Order::Natural),
+                "asc" if descending.is_none() && order.is_none() => descending = Some(false),
+                "desc" if descending.is_none() && order.is_none() => descending = Some(true),
+                _ => break,
+            }
+            column = rest;
+        }
+        Ok(Self::new(
+            column.parse()?,
+            order.unwrap_or_default(),
+            descending.unwrap_or_default(),
+        ))
+    }
+}
+
+impl Sorted {
+    #[inline]
+    #[must_use]
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+}
+
+impl Iterator for Sorted {
+    type Item = Result<Vec<String>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Memory(records) => records.next().map(Ok),
+            Inner::Merge(merge) => merge.next().map(|record| record.map_err(Error::from)),
+        }
+    }
+}
+
+fn compare(keys: &[Resolved], lhs: &[String], rhs: &[String]) -> Ordering {
+    if keys.is_empty() {
+        return lhs.cmp(rhs);
+    }
+    for key in keys {
+        let lhs = lhs.get(key.idx).map_or("", String::as_str);
+        let rhs = rhs.get(key.idx).map_or("", String::as_str);
+        let ordering = key.order.compare(lhs, rhs);
+        if ordering.is_ne() {
+            return if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+        }
+    }
+    Ordering::Equal
+}
+
+fn natural(lhs: &str, rhs: &str) -> Ordering {
+    let mut lhs = lhs.chars().peekable();
+    let mut rhs = rhs.chars().peekable();
+    loop {
+        match (lhs.peek(), rhs.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(ch) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(ch);
+                    }
+                    digits
+                };
+                let (l, r) = (digits(&mut lhs), digits(&mut rhs));
+                let (l, r) = (l.trim_start_matches('0'), r.trim_start_matches('0'));
+                let ordering = l.len().cmp(&r.len()).then_with(|| l.cmp(r));
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                let ordering = l.cmp(r);
+                if ordering.is_ne() {
+                    return ordering;
+                }
+                lhs.next();
+                rhs.next();
+            }
+        }
+    }
+}
+
+/// Rough number of bytes a record takes in memory.
+pub(crate) fn footprint(fields: &[String]) -> usize {
+    mem::size_of::<Vec<String>>()
+        + fields
+            .iter()
+            .map(|field| mem::size_of::<String>() + field.capacity())
+            .sum::<usize>()
+}
+
+fn temp_file(options: &Options) -> io::Result<NamedTempFile> {
+    match options.temp_dir() {
+        Some(dir) => NamedTempFile::new_in(dir),
+        None => NamedTempFile::new(),
+    }
+}
+
+/// Writes sorted records to a temporary file, closed until merged and
+/// removed once dropped.
+fn spill<I>(records: I, options: &Options) -> io::Result<TempPath>
+where
+    I: Iterator<Item = Vec<String>>,
+{
+    spill_results(records.map(Ok), options)
+}
+
+fn spill_results<I>(records: I, options: &Options) -> io::Result<TempPath>
+where
+    I: Iterator<Item = io::Result<Vec<String>>>,
+{
+    let mut writer = BufWriter::new(temp_file(options)?);
+    for record in records {
+        let record = record?;
+        writer.write_all(&(record.len() as u64).to_le_bytes())?;
+        for field in &record {
+            writer.write_all(&(field.len() as u64).to_le_bytes())?;
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    let file = writer
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    Ok(file.into_temp_path())
+}
+
+fn read_record(reader: &mut BufReader<File>) -> io::Result<Option<Vec<String>>> {
+    let mut len = [0; 8];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(cause) if cause.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(cause) => return Err(cause),
+    }
+    let len = usize::try_from(u64::from_le_bytes(len))
+        .map_err(|cause| io::Error::new(io::ErrorKind::InvalidData, cause))?;
+    let mut record = Vec::with_capacity(len);
+    for _ in 0..len {
+        let mut field_len = [0; 8];
+        reader.read_exact(&mut field_len)?;
+        let field_len = usize::try_from(u64::from_le_bytes(field_len))
+            .map_err(|cause| io::Error::new(io::ErrorKind::InvalidData, cause))?;
+        let mut field = vec![0; field_len];
+        reader.read_exact(&mut field)?;
+        record.push(
+            String::from_utf8(field)
+                .map_err(|cause| io::Error::new(io::ErrorKind::InvalidData, cause))?,
+        );
+    }
+    Ok(Some(record))
+}
+
+/// K-way merge of sorted runs.
+#[derive(Debug)]
+struct Merge {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Head>,
+    /// The files of the runs, removed once the merge is dropped.
+    _runs: Vec<TempPath>,
+}
+
+/// The smallest unmerged record of a run.
+#[derive(Debug)]
+struct Head {
+    record: Vec<String>,
+    run: usize,
+    keys: Rc<[Resolved]>,
+}
+
+impl Merge {
+    fn new(runs: Vec<TempPath>, keys: Rc<[Resolved]>) -> io::Result<Self> {
+        let mut readers = runs
+            .iter()
+            .map(|run| File::open(run).map(BufReader::new))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (run, reader) in readers.iter_mut().enumerate() {
+            if let Some(record) = read_record(reader)? {
+                heap.push(Head {
+                    record,
+                    run,
+                    keys: Rc::clone(&keys),
+                });
+            }
+        }
+        Ok(Self {
+            readers,
+            heap,
+            _runs: runs,
+        })
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Head { record, run, keys } = self.heap.pop()?;
+        match read_record(&mut self.readers[run]) {
+            Ok(Some(next)) => self.heap.push(Head {
+                record: next,
+                run,
+                keys,
+            }),
+            Ok(None) => {}
+            Err(cause) => return Some(Err(cause)),
+        }
+        Some(Ok(record))
+    }
+}
+
+/// Ordered so that [`BinaryHeap`] pops the smallest record, and among equal
+/// records the one from the earliest run, keeping the merge stable.
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.keys, &other.record, &self.record).then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Head {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::options::Builder as _;
+
+    #[test]
+    fn merges_more_runs_than_fan_in() {
+        let count = FAN_IN * 3 + 5;
+        let records = (0..count).map(|idx| {
+            let fields = vec![(idx % 7).to_string(), idx.to_string()];
+            Ok(Record::new(idx, fields))
+        });
+        let keys = [Key::new(Column::Index(0), Order::Numeric, false)];
+        let options = Options::default().with_header(false).with_budget(0);
+        let sorted = sort(records, &keys, &options)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut expected = (0..count)
+            .map(|idx| vec![(idx % 7).to_string(), idx.to_string()])
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|fields| fields[0].parse::<usize>().unwrap());
+        assert_eq!(sorted, expected);
+    }
+}
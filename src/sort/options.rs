@@ -0,0 +1,140 @@
+//! External sort, [`sort`] options.
+//!
+//! [`sort`]: super::sort
+
+use std::path::{Path, PathBuf};
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_budget(self, budget: usize) -> Self;
+
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_temp_dir(self, temp_dir: Option<PathBuf>) -> Self;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    budget: usize,
+    header: bool,
+    temp_dir: Option<PathBuf>,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        budget: 64 * 1024 * 1024,
+        header: true,
+        temp_dir: None,
+    };
+
+    /// Approximate number of bytes of records to hold in memory before
+    /// sorting them into a temporary file.
+    #[inline]
+    #[must_use]
+    pub const fn budget(&self) -> usize {
+        self.budget
+    }
+
+    #[inline]
+    pub fn budget_mut(&mut self) -> &mut usize {
+        &mut self.budget
+    }
+
+    #[inline]
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Whether the first record is a header, which is kept first.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    /// Directory of the temporary files, defaulting to the system's.
+    #[inline]
+    #[must_use]
+    pub fn temp_dir(&self) -> Option<&Path> {
+        self.temp_dir.as_deref()
+    }
+
+    #[inline]
+    pub fn temp_dir_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.temp_dir
+    }
+
+    #[inline]
+    pub fn set_temp_dir(&mut self, temp_dir: Option<PathBuf>) {
+        self.temp_dir = temp_dir;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_budget(self, budget: usize) -> Self {
+        self.set_budget(budget);
+        self
+    }
+
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_temp_dir(self, temp_dir: Option<PathBuf>) -> Self {
+        self.set_temp_dir(temp_dir);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_budget(mut self, budget: usize) -> Self {
+        self.set_budget(budget);
+        self
+    }
+
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_temp_dir(mut self, temp_dir: Option<PathBuf>) -> Self {
+        self.set_temp_dir(temp_dir);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
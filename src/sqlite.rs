@@ -0,0 +1,204 @@
+//! Importing records into SQLite tables and exporting query results as
+//! records.
+//!
+//! Imports create the table from the header, typed by the records read ahead,
+//! and insert every record in a single transaction, so that a failed import
+//! leaves the database as it was.
+
+pub mod options;
+
+use std::{io, iter};
+
+use rusqlite::{
+    params_from_iter,
+    types::{self, ToSqlOutput, ValueRef},
+    Connection,
+};
+
+pub use options::{IfExists, Options};
+
+use crate::{
+    records::Record,
+    value::{Type, Value},
+    writer::{self, Writer},
+    ParseError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("{0}")]
+    Write(#[from] writer::Error),
+    #[error("no header nor records to create the table from")]
+    Empty,
+}
+
+/// Imports records into a table, returning the number of rows inserted.
+///
+/// Columns are named by the header, or `column_1`, `column_2` and so on,
+/// and typed as the common type of their non-empty fields among the first
+/// records, see [`Value::detect`]: `INTEGER`, `REAL`, `BOOLEAN` or `TEXT`.
+/// Booleans are stored as `1` and `0`. Empty fields are `NULL` in typed
+/// columns and empty strings in text columns. Fields of a later record that
+/// do not fit the type of their column are stored as text, as SQLite allows.
+///
+/// # Errors
+///
+/// If a record fails to parse or is not as long as the first, if there is
+/// nothing to create the table from, or if a statement fails, such as
+/// creating a table that already exists with [`IfExists::Fail`].
+pub fn import<I>(
+    records: I,
+    connection: &mut Connection,
+    table: &str,
+    options: &Options,
+) -> Result<usize, Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    let mut records = records
+        .map(|record| record.map(Record::into_fields))
+        .enumerate();
+    let header = if options.header() {
+        records.next().map(|(_, record)| record).transpose()?
+    } else {
+        None
+    };
+    let mut ahead = Vec::new();
+    for (at, record) in records.by_ref().take(options.infer_records()) {
+        ahead.push((at, record?));
+    }
+    let width = match (&header, ahead.first()) {
+        (Some(header), _) => header.len(),
+        (None, Some((_, fields))) => fields.len(),
+        (None, None) => return Err(Error::Empty),
+    };
+    let mut kinds = vec![Type::Empty; width];
+    for (_, fields) in &ahead {
+        for (kind, field) in kinds.iter_mut().zip(fields) {
+            *kind = kind.merge(Value::detect(field).kind());
+        }
+    }
+    let names = header
+        .into_iter()
+        .flatten()
+        .chain((1..).map(|column| format!("column_{column}")));
+    let columns = iter::zip(names, &kinds)
+        .map(|(name, kind)| {
+            let declared = match kind {
+                Type::Boolean => "BOOLEAN",
+                Type::Integer => "INTEGER",
+                Type::Float => "REAL",
+                Type::Empty | Type::Text => "TEXT",
+            };
+            format!("{} {declared}", quote(&name))
+        })
+        .collect::<Vec<_>>();
+
+    let transaction = connection.transaction()?;
+    let create = match options.if_exists() {
+        IfExists::Fail => "CREATE TABLE",
+        IfExists::Append => "CREATE TABLE IF NOT EXISTS",
+        IfExists::Replace => {
+            transaction.execute(&format!("DROP TABLE IF EXISTS {}", quote(table)), [])?;
+            "CREATE TABLE"
+        }
+    };
+    transaction.execute(
+        &format!("{create} {} ({})", quote(table), columns.join(", ")),
+        [],
+    )?;
+    let mut inserted = 0;
+    {
+        let mut statement = transaction.prepare(&format!(
+            "INSERT INTO {} VALUES ({})",
+            quote(table),
+            vec!["?"; width].join(", ")
+        ))?;
+        let rest = records.map(|(at, record)| record.map(|fields| (at, fields)));
+        for row in ahead.into_iter().map(Ok).chain(rest) {
+            let (at, fields) = row?;
+            if fields.len() != width {
+                return Err(ParseError::JaggedRecords {
+                    expected: width,
+                    at,
+                    actual: fields.len(),
+                }
+                .into());
+            }
+            let values = iter::zip(&kinds, &fields).map(|(kind, field)| bind(*kind, field));
+            statement.execute(params_from_iter(values))?;
+            inserted += 1;
+        }
+    }
+    transaction.commit()?;
+    Ok(inserted)
+}
+
+/// Writes the result of a query as records, headed by its column names, and
+/// returns the number of rows written.
+///
+/// `NULL` is written as an empty field, and blobs in lowercase hexadecimal.
+///
+/// # Errors
+///
+/// If the query fails, or if writing fails.
+pub fn export<W>(
+    connection: &Connection,
+    query: &str,
+    writer: &mut Writer<W>,
+) -> Result<usize, Error>
+where
+    W: io::Write,
+{
+    let mut statement = connection.prepare(query)?;
+    let width = statement.column_count();
+    if width != 0 {
+        writer.write_record(statement.column_names())?;
+    }
+    let mut rows = statement.query([])?;
+    let mut fields = Vec::with_capacity(width);
+    let mut written = 0;
+    while let Some(row) = rows.next()? {
+        if width == 0 {
+            continue;
+        }
+        fields.clear();
+        for idx in 0..width {
+            fields.push(match row.get_ref(idx)? {
+                ValueRef::Null => String::new(),
+                ValueRef::Integer(integer) => integer.to_string(),
+                ValueRef::Real(real) => format!("{real:?}"),
+                ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+                ValueRef::Blob(blob) => blob.iter().map(|byte| format!("{byte:02x}")).collect(),
+            });
+        }
+        writer.write_record(&fields)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// The value to store for a field of a column of the type.
+fn bind(kind: Type, field: &str) -> ToSqlOutput<'_> {
+    let value = match (kind, Value::detect(field)) {
+        (Type::Empty | Type::Text, _) => None,
+        (_, Value::Empty) => Some(types::Value::Null),
+        (Type::Boolean, Value::Boolean(boolean)) => Some(types::Value::Integer(i64::from(boolean))),
+        (Type::Integer, Value::Integer(integer)) => Some(types::Value::Integer(integer)),
+        (Type::Float, value) => value.as_f64().map(types::Value::Real),
+        _ => None,
+    };
+    value.map_or(
+        ToSqlOutput::Borrowed(ValueRef::Text(field.as_bytes())),
+        ToSqlOutput::Owned,
+    )
+}
+
+/// Quotes an identifier, doubling the quotes within it.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -0,0 +1,148 @@
+//! SQLite import, [`import`] options.
+//!
+//! [`import`]: super::import
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_if_exists(self, if_exists: IfExists) -> Self;
+
+    #[must_use]
+    fn with_infer_records(self, infer_records: usize) -> Self;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+    header: bool,
+    if_exists: IfExists,
+    infer_records: usize,
+}
+
+/// What to do when the table to import into already exists.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum IfExists {
+    /// Fail without importing anything.
+    #[default]
+    Fail,
+    /// Insert into the existing table, which must have as many columns.
+    Append,
+    /// Drop the existing table and create it anew.
+    Replace,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        header: true,
+        if_exists: IfExists::Fail,
+        infer_records: 1000,
+    };
+
+    /// Whether the first record is a header naming the columns.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn if_exists(&self) -> IfExists {
+        self.if_exists
+    }
+
+    #[inline]
+    pub fn if_exists_mut(&mut self) -> &mut IfExists {
+        &mut self.if_exists
+    }
+
+    #[inline]
+    pub fn set_if_exists(&mut self, if_exists: IfExists) {
+        self.if_exists = if_exists;
+    }
+
+    /// Number of records read ahead to infer the column types.
+    #[inline]
+    #[must_use]
+    pub const fn infer_records(&self) -> usize {
+        self.infer_records
+    }
+
+    #[inline]
+    pub fn infer_records_mut(&mut self) -> &mut usize {
+        &mut self.infer_records
+    }
+
+    #[inline]
+    pub fn set_infer_records(&mut self, infer_records: usize) {
+        self.infer_records = infer_records;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_if_exists(self, if_exists: IfExists) -> Self {
+        self.set_if_exists(if_exists);
+        self
+    }
+
+    #[inline]
+    fn with_infer_records(self, infer_records: usize) -> Self {
+        self.set_infer_records(infer_records);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_if_exists(mut self, if_exists: IfExists) -> Self {
+        self.set_if_exists(if_exists);
+        self
+    }
+
+    #[inline]
+    fn with_infer_records(mut self, infer_records: usize) -> Self {
+        self.set_infer_records(infer_records);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -0,0 +1,315 @@
+//! Column statistics, [`profile`] and [`Profiler`].
+//!
+//! Records are summarized one at a time as they are read. Counting distinct
+//! and frequent values is either exact or, for large inputs, approximate in
+//! bounded memory, see [`Distinct`].
+
+mod hyper_log_log;
+pub mod options;
+
+use std::{cmp::Reverse, collections::HashMap};
+
+pub use hyper_log_log::HyperLogLog;
+pub use options::{Distinct, Options};
+
+use crate::{
+    records::Record,
+    value::{Type, Value},
+    ParseError,
+};
+
+/// Number of counters kept per top value when counting approximately.
+const COUNTERS_PER_TOP: usize = 16;
+
+/// Statistics of a column.
+///
+/// Empty fields, including missing fields of short records, only count
+/// toward [`Self::count`] and [`Self::empty`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// The header name of the column, if any.
+    pub name: Option<String>,
+    /// The common type of the non-empty values, see [`Type::merge`].
+    pub kind: Type,
+    pub count: u64,
+    pub empty: u64,
+    pub distinct: u64,
+    /// Whether [`Self::distinct`] and the counts of [`Self::top`] are exact.
+    pub exact: bool,
+    /// The smallest value, compared as numbers in numeric columns and as
+    /// text otherwise.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// The mean of a numeric column.
+    pub mean: Option<f64>,
+    /// The sample standard deviation of a numeric column.
+    pub stddev: Option<f64>,
+    /// The first of the values with the most characters.
+    pub longest: Option<String>,
+    /// The most frequent values and their counts, most frequent first.
+    pub top: Vec<(String, u64)>,
+}
+
+/// Accumulates the statistics of each column, one record at a time.
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    options: Options,
+    header: Option<Vec<String>>,
+    records: u64,
+    columns: Vec<Accumulator>,
+}
+
+#[derive(Clone, Debug)]
+struct Accumulator {
+    count: u64,
+    empty: u64,
+    kind: Type,
+    counter: Counter,
+    min: Option<String>,
+    max: Option<String>,
+    min_number: Option<(f64, String)>,
+    max_number: Option<(f64, String)>,
+    numbers: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean, as per Welford.
+    squares: f64,
+    longest: Option<(usize, String)>,
+}
+
+#[derive(Clone, Debug)]
+enum Counter {
+    Exact(HashMap<String, u64>),
+    Approximate {
+        distinct: HyperLogLog,
+        frequent: SpaceSaving,
+    },
+}
+
+/// The space-saving algorithm: a bounded set of counters, where a new value
+/// replaces the least frequent one and inherits its count.
+#[derive(Clone, Debug)]
+struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<String, u64>,
+}
+
+/// Profiles the columns of the records.
+///
+/// # Errors
+///
+/// If a record fails to parse.
+pub fn profile<I>(records: I, options: &Options) -> Result<Vec<Summary>, ParseError>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+{
+    let mut records = records.peekable();
+    let header = match records.next_if(|_| options.header()) {
+        Some(record) => Some(record?.into_fields()),
+        None => None,
+    };
+    let mut profiler = Profiler::new(header, *options);
+    for record in records {
+        profiler.add(record?.fields());
+    }
+    Ok(profiler.finish())
+}
+
+impl Profiler {
+    /// Creates a profiler of records following the given header, if any.
+    ///
+    /// [`Options::header`] is only used by [`profile`].
+    #[must_use]
+    pub fn new(header: Option<Vec<String>>, options: Options) -> Self {
+        let columns = header.as_ref().map_or(0, Vec::len);
+        let mut profiler = Self {
+            options,
+            header,
+            records: 0,
+            columns: Vec::new(),
+        };
+        profiler.widen(columns);
+        profiler
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    /// Number of records added so far.
+    #[inline]
+    #[must_use]
+    pub const fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn add(&mut self, fields: &[String]) {
+        self.widen(fields.len());
+        self.records += 1;
+        for (idx, column) in self.columns.iter_mut().enumerate() {
+            column.add(fields.get(idx).map_or("", String::as_str));
+        }
+    }
+
+    #[must_use]
+    pub fn finish(self) -> Vec<Summary> {
+        let top = self.options.top();
+        let mut names = self.header.unwrap_or_default().into_iter();
+        self.columns
+            .into_iter()
+            .map(|column| column.finish(names.next(), top))
+            .collect()
+    }
+
+    /// Adds columns up to the given width, as if empty in earlier records.
+    fn widen(&mut self, width: usize) {
+        while self.columns.len() < width {
+            let counter = match self.options.distinct() {
+                Distinct::Exact => Counter::Exact(HashMap::new()),
+                Distinct::Approximate { precision } => Counter::Approximate {
+                    distinct: HyperLogLog::new(precision),
+                    frequent: SpaceSaving {
+                        capacity: self.options.top().max(1) * COUNTERS_PER_TOP,
+                        counts: HashMap::new(),
+                    },
+                },
+            };
+            self.columns.push(Accumulator {
+                count: self.records,
+                empty: self.records,
+                kind: Type::Empty,
+                counter,
+                min: None,
+                max: None,
+                min_number: None,
+                max_number: None,
+                numbers: 0,
+                mean: 0.0,
+                squares: 0.0,
+                longest: None,
+            });
+        }
+    }
+}
+
+impl Accumulator {
+    #[allow(clippy::cast_precision_loss)]
+    fn add(&mut self, field: &str) {
+        self.count += 1;
+        let value = Value::detect(field);
+        if value == Value::Empty {
+            self.empty += 1;
+            return;
+        }
+        self.kind = self.kind.merge(value.kind());
+
+        match &mut self.counter {
+            Counter::Exact(counts) => match counts.get_mut(field) {
+                Some(count) => *count += 1,
+                None => {
+                    counts.insert(field.to_owned(), 1);
+                }
+            },
+            Counter::Approximate { distinct, frequent } => {
+                distinct.insert(field);
+                frequent.insert(field);
+            }
+        }
+
+        if self.min.as_deref().is_none_or(|min| field < min) {
+            self.min = Some(field.to_owned());
+        }
+        if self.max.as_deref().is_none_or(|max| field > max) {
+            self.max = Some(field.to_owned());
+        }
+        if let Some(number) = value.as_f64() {
+            if self
+                .min_number
+                .as_ref()
+                .is_none_or(|(min, _)| number < *min)
+            {
+                self.min_number = Some((number, field.to_owned()));
+            }
+            if self
+                .max_number
+                .as_ref()
+                .is_none_or(|(max, _)| number > *max)
+            {
+                self.max_number = Some((number, field.to_owned()));
+            }
+            self.numbers += 1;
+            let delta = number - self.mean;
+            self.mean += delta / self.numbers as f64;
+            self.squares += delta * (number - self.mean);
+        }
+        let len = field.chars().count();
+        if self
+            .longest
+            .as_ref()
+            .is_none_or(|(longest, _)| len > *longest)
+        {
+            self.longest = Some((len, field.to_owned()));
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn finish(self, name: Option<String>, top: usize) -> Summary {
+        let numeric = self.kind.is_numeric();
+        let (distinct, exact, counts) = match self.counter {
+            Counter::Exact(counts) => (counts.len() as u64, true, counts),
+            Counter::Approximate { distinct, frequent } => {
+                (distinct.estimate(), false, frequent.counts)
+            }
+        };
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_unstable_by(|(lhs, lhs_count), (rhs, rhs_count)| {
+            Reverse(lhs_count)
+                .cmp(&Reverse(rhs_count))
+                .then(lhs.cmp(rhs))
+        });
+        counts.truncate(top);
+
+        Summary {
+            name,
+            kind: self.kind,
+            count: self.count,
+            empty: self.empty,
+            distinct,
+            exact,
+            min: if numeric {
+                self.min_number.map(|(_, field)| field)
+            } else {
+                self.min
+            },
+            max: if numeric {
+                self.max_number.map(|(_, field)| field)
+            } else {
+                self.max
+            },
+            mean: numeric.then_some(self.mean),
+            stddev: (numeric && self.numbers > 1)
+                .then(|| (self.squares / (self.numbers - 1) as f64).sqrt()),
+            longest: self.longest.map(|(_, field)| field),
+            top: counts,
+        }
+    }
+}
+
+impl SpaceSaving {
+    fn insert(&mut self, value: &str) {
+        if let Some(count) = self.counts.get_mut(value) {
+            *count += 1;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(value.to_owned(), 1);
+        } else if let Some((least, count)) = self
+            .counts
+            .iter()
+            .min_by_key(|(_, count)| **count)
+            .map(|(least, count)| (least.clone(), *count))
+        {
+            self.counts.remove(&least);
+            self.counts.insert(value.to_owned(), count + 1);
+        }
+    }
+}
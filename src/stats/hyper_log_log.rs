@@ -0,0 +1,101 @@
+//! Approximate distinct counting, [`HyperLogLog`].
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// A HyperLogLog sketch, estimating the number of distinct values added to
+/// it in a fixed amount of memory.
+///
+/// With a precision of `p`, the sketch takes `2^p` bytes and its standard
+/// error is about `1.04 / sqrt(2^p)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub const MIN_PRECISION: u8 = 4;
+    pub const MAX_PRECISION: u8 = 18;
+
+    /// # Panics
+    ///
+    /// If the precision is not within [`Self::MIN_PRECISION`] and
+    /// [`Self::MAX_PRECISION`].
+    #[must_use]
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (Self::MIN_PRECISION..=Self::MAX_PRECISION).contains(&precision),
+            "precision {precision} is out of range"
+        );
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn insert<T>(&mut self, value: &T)
+    where
+        T: Hash + ?Sized,
+    {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+        let idx = usize::try_from(hash >> (64 - self.precision)).unwrap_or_default();
+        // The remaining bits, with a sentinel bounding the count of zeros.
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = u8::try_from(rest.leading_zeros() + 1).unwrap_or(u8::MAX);
+        self.registers[idx] = self.registers[idx].max(rank);
+    }
+
+    /// Adds the values of another sketch of the same precision.
+    ///
+    /// # Panics
+    ///
+    /// If the precisions differ.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(self.precision, other.precision, "precisions differ");
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum = self
+            .registers
+            .iter()
+            .map(|register| (-f64::from(*register)).exp2())
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+        estimate.round() as u64
+    }
+}
@@ -0,0 +1,149 @@
+//! Column profiling, [`profile`] options.
+//!
+//! [`profile`]: super::profile
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_distinct(self, distinct: Distinct) -> Self;
+
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_top(self, top: usize) -> Self;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Options {
+    distinct: Distinct,
+    header: bool,
+    top: usize,
+}
+
+/// How distinct and frequent values are counted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Distinct {
+    /// Exactly, keeping every distinct value of every column in memory.
+    #[default]
+    Exact,
+    /// In bounded memory: distinct values with a
+    /// [`HyperLogLog`](super::HyperLogLog) of the given precision, and
+    /// frequent values with a fixed number of counters, so that the counts
+    /// of the top values may be overestimated.
+    Approximate { precision: u8 },
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        distinct: Distinct::Exact,
+        header: true,
+        top: 5,
+    };
+
+    #[inline]
+    #[must_use]
+    pub const fn distinct(&self) -> Distinct {
+        self.distinct
+    }
+
+    #[inline]
+    pub fn distinct_mut(&mut self) -> &mut Distinct {
+        &mut self.distinct
+    }
+
+    #[inline]
+    pub fn set_distinct(&mut self, distinct: Distinct) {
+        self.distinct = distinct;
+    }
+
+    /// Whether the first record is a header naming the columns.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    /// Number of most frequent values to report per column.
+    #[inline]
+    #[must_use]
+    pub const fn top(&self) -> usize {
+        self.top
+    }
+
+    #[inline]
+    pub fn top_mut(&mut self) -> &mut usize {
+        &mut self.top
+    }
+
+    #[inline]
+    pub fn set_top(&mut self, top: usize) {
+        self.top = top;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_distinct(self, distinct: Distinct) -> Self {
+        self.set_distinct(distinct);
+        self
+    }
+
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_top(self, top: usize) -> Self {
+        self.set_top(top);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_distinct(mut self, distinct: Distinct) -> Self {
+        self.set_distinct(distinct);
+        self
+    }
+
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_top(mut self, top: usize) -> Self {
+        self.set_top(top);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
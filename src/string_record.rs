@@ -0,0 +1,226 @@
+//! A record in one buffer, [`StringRecord`].
+//!
+//! Where a [`Record`] allocates a [`String`] per field, a string record keeps
+//! all fields in one buffer along with where each ends, so that it can be
+//! cleared and filled again without allocating, see
+//! [`crate::reader::Reader::read_record`].
+
+use std::{cell::Cell, iter::FusedIterator, ops};
+
+use crate::records::{Fields, Record};
+
+/// The fields of a record, stored contiguously.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StringRecord {
+    idx: usize,
+    buf: String,
+    /// The end of every field in `buf`.
+    ends: Vec<usize>,
+}
+
+/// Iterator over the fields of a [`StringRecord`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    record: &'a StringRecord,
+    front: usize,
+    back: usize,
+}
+
+/// The fields of a [`StringRecord`] being read, whose contents are put in
+/// its buffer as they are tokenized, up to `len`, measuring `size` in all.
+pub(crate) struct Ends<'a> {
+    pub ends: &'a mut Vec<usize>,
+    pub len: &'a Cell<usize>,
+    pub size: &'a Cell<usize>,
+    /// The size of the contents of the fields pushed so far.
+    pub pushed: usize,
+}
+
+impl StringRecord {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            idx: 0,
+            buf: String::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// A record with room for `fields` fields of `bytes` bytes in total.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(bytes: usize, fields: usize) -> Self {
+        Self {
+            idx: 0,
+            buf: String::with_capacity(bytes),
+            ends: Vec::with_capacity(fields),
+        }
+    }
+
+    /// The index of the first character of the record.
+    #[inline]
+    #[must_use]
+    pub const fn idx(&self) -> usize {
+        self.idx
+    }
+
+    #[inline]
+    pub fn set_idx(&mut self, idx: usize) {
+        self.idx = idx;
+    }
+
+    /// Number of fields.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The field at `field`, counting from zero.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, field: usize) -> Option<&str> {
+        let end = *self.ends.get(field)?;
+        let start = field.checked_sub(1).map_or(0, |prev| self.ends[prev]);
+        Some(&self.buf[start..end])
+    }
+
+    /// All fields, concatenated.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            record: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    /// Appends a field.
+    #[inline]
+    pub fn push_field(&mut self, field: &str) {
+        self.buf.push_str(field);
+        self.ends.push(self.buf.len());
+    }
+
+    /// Removes all fields, keeping the allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.idx = 0;
+        self.buf.clear();
+        self.ends.clear();
+    }
+
+    /// Copies the fields into a [`Record`].
+    #[must_use]
+    pub fn to_record(&self) -> Record {
+        Record::new(self.idx, self.iter().map(str::to_owned).collect())
+    }
+
+    /// The buffer and field ends, to be filled by a reader.
+    pub(crate) fn parts_mut(&mut self) -> (&mut usize, &mut String, &mut Vec<usize>) {
+        (&mut self.idx, &mut self.buf, &mut self.ends)
+    }
+}
+
+impl From<Record> for StringRecord {
+    fn from(value: Record) -> Self {
+        let bytes = value.fields().iter().map(String::len).sum();
+        let mut record = Self::with_capacity(bytes, value.len());
+        record.set_idx(value.idx());
+        for field in value.fields() {
+            record.push_field(field);
+        }
+        record
+    }
+}
+
+impl ops::Index<usize> for StringRecord {
+    type Output = str;
+
+    /// # Panics
+    ///
+    /// If there is no such field.
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("field index out of bounds")
+    }
+}
+
+impl<'a> IntoIterator for &'a StringRecord {
+    type Item = &'a str;
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.record.get(self.front - 1)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.record.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+impl Fields for Ends<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    #[inline]
+    fn push(&mut self, _field: String) -> usize {
+        self.ends.push(self.len.get());
+        let size = self.size.get();
+        let len = size - self.pushed;
+        self.pushed = size;
+        len
+    }
+
+    #[inline]
+    fn pop(&mut self) {
+        self.ends.pop();
+    }
+}
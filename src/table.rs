@@ -0,0 +1,186 @@
+//! Aligned table rendering, [`render`].
+//!
+//! Only the first [`Options::rows`] records are read, so that the start of
+//! a huge input is shown without scanning the rest of it.
+
+pub mod options;
+
+use std::{borrow::Cow, fmt::Write as _, io};
+
+pub use options::Options;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{records::Record, ParseError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// Writes records as a table of aligned columns.
+///
+/// Widths are measured in terminal columns. Control characters, including
+/// line breaks from quoted fields, are shown as escape sequences, and cells
+/// wider than their column are truncated with an ellipsis. Shorter records
+/// are padded with empty cells.
+///
+/// # Errors
+///
+/// If a record fails to parse or writing fails.
+pub fn render<I, W>(records: I, mut writer: W, options: &Options) -> Result<(), Error>
+where
+    I: Iterator<Item = Result<Record, ParseError>>,
+    W: io::Write,
+{
+    let limit = options.rows().map_or(usize::MAX, |rows| {
+        rows.saturating_add(options.header().into())
+    });
+    let mut rows = Vec::new();
+    for record in records.take(limit) {
+        let row = record?
+            .fields()
+            .iter()
+            .map(|field| escape(field).into_owned())
+            .collect::<Vec<_>>();
+        rows.push(row);
+    }
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Ok(());
+    }
+
+    let mut widths = vec![0; columns];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    if let Some(max_cell_width) = options.max_cell_width() {
+        for width in &mut widths {
+            *width = (*width).min(max_cell_width.max(1));
+        }
+    }
+    if let Some(max_width) = options.max_width() {
+        let overhead = if options.borders() {
+            3 * columns + 1
+        } else {
+            2 * (columns - 1)
+        };
+        narrow(&mut widths, max_width.saturating_sub(overhead));
+    }
+
+    let mut line = String::new();
+    if options.borders() {
+        writeln!(writer, "{}", rule(&widths, '┌', '┬', '┐'))?;
+    }
+    for (idx, row) in rows.iter().enumerate() {
+        line.clear();
+        for (column, width) in widths.iter().enumerate() {
+            let cell = row.get(column).map_or("", String::as_str);
+            if options.borders() {
+                line.push_str(if column == 0 { "│ " } else { " │ " });
+            } else if column != 0 {
+                line.push_str("  ");
+            }
+            push_cell(&mut line, cell, *width);
+        }
+        if options.borders() {
+            line.push_str(" │");
+        }
+        writeln!(writer, "{}", line.trim_end_matches(' '))?;
+
+        if idx == 0 && options.header() && rows.len() > 1 {
+            if options.borders() {
+                writeln!(writer, "{}", rule(&widths, '├', '┼', '┤'))?;
+            } else {
+                let rule = widths.iter().map(|width| "─".repeat(*width));
+                writeln!(writer, "{}", rule.collect::<Vec<_>>().join("  "))?;
+            }
+        }
+    }
+    if options.borders() {
+        writeln!(writer, "{}", rule(&widths, '└', '┴', '┘'))?;
+    }
+    Ok(())
+}
+
+/// Shows control characters as escape sequences.
+fn escape(field: &str) -> Cow<'_, str> {
+    if !field.chars().any(char::is_control) {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = String::with_capacity(field.len());
+    for ch in field.chars() {
+        match ch {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(escaped, "\\u{{{:x}}}", u32::from(ch));
+            }
+            ch => escaped.push(ch),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Caps the widest columns until their total fits in `available`.
+fn narrow(widths: &mut [usize], available: usize) {
+    let total = |cap: usize| widths.iter().map(|width| (*width).min(cap)).sum::<usize>();
+    let widest = widths.iter().copied().max().unwrap_or(0);
+    if total(widest) <= available {
+        return;
+    }
+    let (mut low, mut high) = (1, widest);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if total(mid) <= available {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    for width in widths {
+        *width = (*width).min(low);
+    }
+}
+
+/// Pushes a cell padded or truncated to exactly `width` columns.
+fn push_cell(line: &mut String, cell: &str, width: usize) {
+    let cell_width = cell.width();
+    if cell_width <= width {
+        line.push_str(cell);
+        line.extend(std::iter::repeat_n(' ', width - cell_width));
+        return;
+    }
+    let mut used = 0;
+    for ch in cell.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width + 1 > width {
+            break;
+        }
+        line.push(ch);
+        used += ch_width;
+    }
+    if width > 0 {
+        line.push('…');
+        used += 1;
+    }
+    line.extend(std::iter::repeat_n(' ', width - used));
+}
+
+fn rule(widths: &[usize], left: char, middle: char, right: char) -> String {
+    let mut rule = String::new();
+    rule.push(left);
+    for (column, width) in widths.iter().enumerate() {
+        if column != 0 {
+            rule.push(middle);
+        }
+        rule.extend(std::iter::repeat_n('─', width + 2));
+    }
+    rule.push(right);
+    rule
+}
@@ -0,0 +1,206 @@
+//! Table renderer, [`render`] options.
+//!
+//! [`render`]: super::render
+
+mod private_builder {
+    pub trait Sealed: Sized {}
+
+    impl Sealed for &mut super::Options {}
+
+    impl Sealed for super::Options {}
+}
+
+/// Options builder methods.
+///
+/// Allow building with either a mutable reference or the value itself.
+pub trait Builder: private_builder::Sealed {
+    #[must_use]
+    fn with_borders(self, borders: bool) -> Self;
+
+    #[must_use]
+    fn with_header(self, header: bool) -> Self;
+
+    #[must_use]
+    fn with_max_cell_width(self, max_cell_width: Option<usize>) -> Self;
+
+    #[must_use]
+    fn with_max_width(self, max_width: Option<usize>) -> Self;
+
+    #[must_use]
+    fn with_rows(self, rows: Option<usize>) -> Self;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    borders: bool,
+    header: bool,
+    max_cell_width: Option<usize>,
+    max_width: Option<usize>,
+    rows: Option<usize>,
+}
+
+impl Options {
+    pub const DEFAULT: Self = Self {
+        borders: false,
+        header: true,
+        max_cell_width: Some(40),
+        max_width: None,
+        rows: Some(100),
+    };
+
+    /// Whether to draw box borders around the table and its cells.
+    #[inline]
+    #[must_use]
+    pub const fn borders(&self) -> bool {
+        self.borders
+    }
+
+    #[inline]
+    pub fn borders_mut(&mut self) -> &mut bool {
+        &mut self.borders
+    }
+
+    #[inline]
+    pub fn set_borders(&mut self, borders: bool) {
+        self.borders = borders;
+    }
+
+    /// Whether the first record is a header, set apart from the rest.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> bool {
+        self.header
+    }
+
+    #[inline]
+    pub fn header_mut(&mut self) -> &mut bool {
+        &mut self.header
+    }
+
+    #[inline]
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+    }
+
+    /// Display width past which a cell is truncated.
+    #[inline]
+    #[must_use]
+    pub const fn max_cell_width(&self) -> Option<usize> {
+        self.max_cell_width
+    }
+
+    #[inline]
+    pub fn max_cell_width_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_cell_width
+    }
+
+    #[inline]
+    pub fn set_max_cell_width(&mut self, max_cell_width: Option<usize>) {
+        self.max_cell_width = max_cell_width;
+    }
+
+    /// Display width of a whole line, which the widest columns are
+    /// narrowed to fit in.
+    #[inline]
+    #[must_use]
+    pub const fn max_width(&self) -> Option<usize> {
+        self.max_width
+    }
+
+    #[inline]
+    pub fn max_width_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_width
+    }
+
+    #[inline]
+    pub fn set_max_width(&mut self, max_width: Option<usize>) {
+        self.max_width = max_width;
+    }
+
+    /// Number of records to read past the header, if limited.
+    #[inline]
+    #[must_use]
+    pub const fn rows(&self) -> Option<usize> {
+        self.rows
+    }
+
+    #[inline]
+    pub fn rows_mut(&mut self) -> &mut Option<usize> {
+        &mut self.rows
+    }
+
+    #[inline]
+    pub fn set_rows(&mut self, rows: Option<usize>) {
+        self.rows = rows;
+    }
+}
+
+impl Builder for &mut Options {
+    #[inline]
+    fn with_borders(self, borders: bool) -> Self {
+        self.set_borders(borders);
+        self
+    }
+
+    #[inline]
+    fn with_header(self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_max_cell_width(self, max_cell_width: Option<usize>) -> Self {
+        self.set_max_cell_width(max_cell_width);
+        self
+    }
+
+    #[inline]
+    fn with_max_width(self, max_width: Option<usize>) -> Self {
+        self.set_max_width(max_width);
+        self
+    }
+
+    #[inline]
+    fn with_rows(self, rows: Option<usize>) -> Self {
+        self.set_rows(rows);
+        self
+    }
+}
+
+impl Builder for Options {
+    #[inline]
+    fn with_borders(mut self, borders: bool) -> Self {
+        self.set_borders(borders);
+        self
+    }
+
+    #[inline]
+    fn with_header(mut self, header: bool) -> Self {
+        self.set_header(header);
+        self
+    }
+
+    #[inline]
+    fn with_max_cell_width(mut self, max_cell_width: Option<usize>) -> Self {
+        self.set_max_cell_width(max_cell_width);
+        self
+    }
+
+    #[inline]
+    fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.set_max_width(max_width);
+        self
+    }
+
+    #[inline]
+    fn with_rows(mut self, rows: Option<usize>) -> Self {
+        self.set_rows(rows);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
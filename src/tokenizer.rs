@@ -1,10 +1,11 @@
 pub mod core;
 pub mod options;
 
-use crate::token::Token;
-use core::next;
+use std::fmt;
+
+use crate::{diagnostic::Severity, token::Token};
+use core::{next, Source};
 pub use options::Options;
-use std::iter::{Enumerate, Peekable};
 
 /// Converting to a csv token iterator.
 #[allow(private_bounds)]
@@ -19,9 +20,23 @@ where
 
     #[inline]
     fn csv_tokens_custom<O>(self, options: O) -> Tokenizer<Self, O> {
+        self.csv_tokens_at(0, options)
+    }
+
+    /// Tokenizes characters that start at index `start` of a larger input,
+    /// such as after seeking, so that token and error indices stay relative
+    /// to the whole input. Only at the start of the input is a `sep=`
+    /// directive read, see [`Options::sep_directive`].
+    #[inline]
+    fn csv_tokens_at<O>(self, start: usize, options: O) -> Tokenizer<Self, O> {
         Tokenizer {
-            iter: self.enumerate().peekable(),
+            iter: if start == 0 {
+                Source::new(self)
+            } else {
+                Source::resume(self)
+            },
             options,
+            start,
         }
     }
 }
@@ -31,8 +46,9 @@ pub struct Tokenizer<I, O>
 where
     I: Iterator<Item = char>,
 {
-    iter: Peekable<Enumerate<I>>,
+    iter: Source<I>,
     options: O,
+    start: usize,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
@@ -69,6 +85,33 @@ pub enum ErrorKind {
         to_string = "quote in unquoted string; a field must be quoted to contain quotes and are escaped by having twos"
     )]
     LateQuote,
+    #[strum(to_string = "quoted field never ends; a field must be closed by a quote")]
+    UnterminatedQuote,
+    #[strum(to_string = "field is longer than the maximum field length of {max}")]
+    FieldTooLong { max: usize },
+    #[strum(to_string = "quoted field has more line breaks than the maximum of {max}")]
+    QuotedLineBreaks { max: usize },
+    #[strum(
+        to_string = "the `sep=` directive declares a delimiter that is a control code character or the quote"
+    )]
+    SepDirective,
+}
+
+/// A relaxation of the quoting rules, reported when enabled by
+/// [`options::Quoting::Relaxed`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Warning {
+    at: usize,
+    kind: WarningKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display)]
+#[non_exhaustive]
+pub enum WarningKind {
+    #[strum(to_string = "quote in unquoted field, taken literally")]
+    LateQuote,
+    #[strum(to_string = "text after the closing quote, appended to the field")]
+    EarlyQuote,
 }
 
 impl Error {
@@ -91,9 +134,167 @@ impl Error {
     }
 }
 
+impl ErrorKind {
+    /// A stable code identifying the kind, see [`crate::diagnostic`].
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Control => "CSV001",
+            Self::CarriageReturn => "CSV002",
+            Self::LineFeed => "CSV003",
+            Self::EarlyQuote => "CSV004",
+            Self::LateQuote => "CSV005",
+            Self::UnterminatedQuote => "CSV006",
+            Self::FieldTooLong { .. } => "CSV007",
+            Self::QuotedLineBreaks { .. } => "CSV008",
+            Self::SepDirective => "CSV009",
+        }
+    }
+
+    /// The configured maximum exceeded, if the kind is of a limit.
+    #[inline]
+    #[must_use]
+    pub const fn max(&self) -> Option<usize> {
+        match self {
+            Self::FieldTooLong { max } | Self::QuotedLineBreaks { max } => Some(*max),
+            Self::Control
+            | Self::CarriageReturn
+            | Self::LineFeed
+            | Self::EarlyQuote
+            | Self::LateQuote
+            | Self::UnterminatedQuote
+            | Self::SepDirective => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// What went wrong, without the resolution the [`Display`] output goes
+    /// on with, which is the [`Self::hint`].
+    ///
+    /// [`Display`]: std::fmt::Display
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self {
+            Self::Control => "unexpected control code character".to_owned(),
+            Self::CarriageReturn => "unexpected carriage return '\\r'".to_owned(),
+            Self::LineFeed => "unexpected line feed '\\n'".to_owned(),
+            Self::EarlyQuote => "quote ended too early".to_owned(),
+            Self::LateQuote => "quote in unquoted string".to_owned(),
+            Self::UnterminatedQuote => "quoted field never ends".to_owned(),
+            Self::SepDirective => "the `sep=` directive declares an invalid delimiter".to_owned(),
+            Self::FieldTooLong { .. } | Self::QuotedLineBreaks { .. } => self.to_string(),
+        }
+    }
+
+    /// How the error may be resolved, if there is more to say than the
+    /// message.
+    #[must_use]
+    pub const fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Control => Some("either remove it, or allow control codes"),
+            Self::CarriageReturn => Some(
+                "possible resolutions:
+ - complete a line break by appending '\\n'
+ - remove the carriage return
+ - quote the field containing the carriage return
+ - enable cross-platform line breaks; or
+ - enable control codes",
+            ),
+            Self::LineFeed => Some(
+                "possible resolutions:
+ - complete a line break by prepending '\\r'
+ - remove the line feed
+ - quote the field containing the line feed
+ - enable cross-platform line breaks; or
+ - enable control codes",
+            ),
+            Self::EarlyQuote => Some("quotes must enclose the entire field"),
+            Self::LateQuote => {
+                Some("a field must be quoted to contain quotes, which are escaped by doubling them")
+            }
+            Self::UnterminatedQuote => Some("a field must be closed by a quote"),
+            Self::FieldTooLong { .. } | Self::QuotedLineBreaks { .. } => None,
+            Self::SepDirective => {
+                Some("the delimiter must be neither a control code character nor the quote")
+            }
+        }
+    }
+}
+
+impl WarningKind {
+    /// A stable code identifying the kind, see [`crate::diagnostic`].
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::LateQuote => "CSV051",
+            Self::EarlyQuote => "CSV052",
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+}
+
+impl Warning {
+    #[inline]
+    #[must_use]
+    pub const fn new(at: usize, kind: WarningKind) -> Self {
+        Self { at, kind }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn at(&self) -> &usize {
+        &self.at
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &WarningKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Quoting Warning at {}: {}", self.at, self.kind)
+    }
+}
+
+impl<I, O> Tokenizer<I, O>
+where
+    I: Iterator<Item = char>,
+{
+    /// Takes the warnings of the tokens read so far, which are kept until
+    /// taken.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        let mut warnings = self.iter.take_warnings();
+        for warning in &mut warnings {
+            warning.at += self.start;
+        }
+        warnings
+    }
+
+    /// The delimiter declared by a `sep=` directive at the start of the
+    /// input, used instead of the configured one once read.
+    #[inline]
+    #[must_use]
+    pub fn declared_delimiter(&self) -> Option<char> {
+        self.iter.declared_delimiter()
+    }
+}
+
 impl<I> Into for I where I: Iterator<Item = char> + Sized {}
 
-impl<'a, I> Iterator for Tokenizer<I, &'a Options>
+impl<I> Iterator for Tokenizer<I, &Options>
 where
     I: Iterator<Item = char>,
 {
@@ -101,11 +302,11 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self::next(&mut self.iter, self.options)
+        shift(self::next(&mut self.iter, self.options)?, self.start)
     }
 }
 
-impl<'a, I> Iterator for Tokenizer<I, &'a mut Options>
+impl<I> Iterator for Tokenizer<I, &mut Options>
 where
     I: Iterator<Item = char>,
 {
@@ -113,7 +314,7 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self::next(&mut self.iter, self.options)
+        shift(self::next(&mut self.iter, self.options)?, self.start)
     }
 }
 
@@ -125,6 +326,14 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self::next(&mut self.iter, &self.options)
+        shift(self::next(&mut self.iter, &self.options)?, self.start)
     }
 }
+
+#[inline]
+fn shift(result: Result<Token, Error>, start: usize) -> Option<Result<Token, Error>> {
+    Some(match result {
+        Ok(token) => Ok(Token::new(token.idx + start, token.kind)),
+        Err(cause) => Err(Error::new(cause.at + start, cause.kind)),
+    })
+}
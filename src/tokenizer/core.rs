@@ -1,38 +1,356 @@
-use std::iter::{Enumerate, Peekable};
+use std::{collections::VecDeque, iter::Enumerate, mem};
 
 use crate::token::{Kind, Token};
 
-use super::{options::LineBreak, Error, ErrorKind, Options};
+use super::{
+    options::{LineBreak, Unterminated},
+    Error, ErrorKind, Options, Warning, WarningKind,
+};
+
+/// The start of a `sep=` directive.
+pub(crate) const SEP: [char; 4] = ['s', 'e', 'p', '='];
+
+/// Indexed characters to tokenize.
+///
+/// Characters can be given back, to be read again, which is how lookahead
+/// and [`Unterminated::Recover`] work.
+///
+/// A source created with [`Source::new`] starts at the start of the input,
+/// where a `sep=` directive may declare the delimiter, see
+/// [`Options::sep_directive`].
+#[derive(Clone, Debug)]
+pub struct Source<I>
+where
+    I: Iterator<Item = char>,
+{
+    iter: Enumerate<I>,
+    replay: VecDeque<(usize, char)>,
+    /// Characters read since recording started.
+    recording: Option<Vec<(usize, char)>>,
+    warnings: Vec<Warning>,
+    /// Whether a `sep=` directive may still be read.
+    sniff: bool,
+    /// The options with the delimiter declared by a `sep=` directive.
+    declared: Option<Options>,
+}
+
+/// Whether characters at the start of an input are a `sep=` directive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Directive {
+    /// More characters are needed to tell.
+    Undecided,
+    Absent,
+    /// A directive of `len` characters, including its line break.
+    Declared {
+        delimiter: char,
+        len: usize,
+    },
+}
+
+impl<I> Source<I>
+where
+    I: Iterator<Item = char>,
+{
+    #[inline]
+    #[must_use]
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter: iter.enumerate(),
+            replay: VecDeque::new(),
+            recording: None,
+            warnings: Vec::new(),
+            sniff: true,
+            declared: None,
+        }
+    }
+
+    /// Characters from within an input, such as after seeking, where there
+    /// is no `sep=` directive to read.
+    #[inline]
+    #[must_use]
+    pub fn resume(iter: I) -> Self {
+        Self {
+            sniff: false,
+            ..Self::new(iter)
+        }
+    }
+
+    /// The delimiter declared by a `sep=` directive, once read.
+    #[inline]
+    #[must_use]
+    pub fn declared_delimiter(&self) -> Option<char> {
+        self.declared.as_ref().map(Options::delimiter)
+    }
+
+    /// Takes the warnings of the characters read so far.
+    #[inline]
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        mem::take(&mut self.warnings)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, char)> {
+        let item = self.replay.pop_front().or_else(|| self.iter.next())?;
+        if let Some(recording) = &mut self.recording {
+            recording.push(item);
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn next_if<F>(&mut self, f: F) -> Option<(usize, char)>
+    where
+        F: FnOnce(&(usize, char)) -> bool,
+    {
+        let item = self.next()?;
+        if f(&item) {
+            return Some(item);
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.pop();
+        }
+        self.replay.push_front(item);
+        None
+    }
+
+    fn record(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    fn take_recording(&mut self) -> Vec<(usize, char)> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    fn warn(&mut self, options: &Options, at: usize, kind: WarningKind) {
+        if options.quoting().warns() {
+            self.warnings.push(Warning::new(at, kind));
+        }
+    }
+
+    /// Gives back characters to be read again, before any other.
+    fn give_back(&mut self, items: &[(usize, char)]) {
+        for item in items.iter().rev() {
+            self.replay.push_front(*item);
+        }
+    }
+}
 
 /// Tokenizes one or more characters.
 ///
+/// At the start of the input, a `sep=` directive is read first, and its
+/// delimiter used from then on.
+///
 /// # Panics
 ///
 /// If `QUOTE == DELIMITER` or if either `QUOTE` or `DELIMITER` is a control
 /// code character or equaled either one of the line terminating characters
 /// `'\r'` or `'\n'`.
 #[inline]
-pub fn next<I>(iter: &mut Peekable<Enumerate<I>>, options: &Options) -> Option<Result<Token, Error>>
+pub fn next<I>(iter: &mut Source<I>, options: &Options) -> Option<Result<Token, Error>>
+where
+    I: Iterator<Item = char>,
+{
+    let mut buf = String::new();
+    let mut token = tokenize(iter, options, &mut buf)?;
+    if let Ok(Token {
+        kind: Kind::Field(field),
+        ..
+    }) = &mut token
+    {
+        *field = buf;
+    }
+    Some(token)
+}
+
+/// Tokenizes one or more characters like [`next`], but appends field
+/// contents to `buf` instead, yielding fields as empty.
+///
+/// # Panics
+///
+/// See [`next`].
+#[inline]
+pub fn next_into<I>(
+    iter: &mut Source<I>,
+    options: &Options,
+    buf: &mut String,
+) -> Option<Result<Token, Error>>
+where
+    I: Iterator<Item = char>,
+{
+    tokenize(iter, options, buf)
+}
+
+/// Tokenizes one or more characters like [`next`], but without buffering
+/// field contents, which are always yielded as empty.
+///
+/// Fields are still checked for errors.
+///
+/// # Panics
+///
+/// See [`next`].
+#[inline]
+pub fn skip<I>(iter: &mut Source<I>, options: &Options) -> Option<Result<Token, Error>>
+where
+    I: Iterator<Item = char>,
+{
+    tokenize(iter, options, &mut Discard)
+}
+
+/// Reads a `sep=` directive: `sep=`, a character, and a line break or the
+/// end of the input, given whether the input ends after `chars`.
+pub(crate) fn directive(chars: &[char], line_break: LineBreak, end: bool) -> Directive {
+    let prefix = chars.len().min(SEP.len());
+    if chars[..prefix] != SEP[..prefix] {
+        return Directive::Absent;
+    }
+    let undecided = |decided| {
+        if end {
+            decided
+        } else {
+            Directive::Undecided
+        }
+    };
+    let Some(&delimiter) = chars.get(SEP.len()) else {
+        return undecided(Directive::Absent);
+    };
+    if matches!(delimiter, '\r' | '\n') {
+        return Directive::Absent;
+    }
+    let declared = |len| Directive::Declared { delimiter, len };
+    let len = SEP.len() + 1;
+    match chars.get(len) {
+        None => undecided(declared(len)),
+        Some('\n') if line_break.contains('\n') => declared(len + 1),
+        Some('\r') if line_break.contains('\r') => match (line_break, chars.get(len + 1)) {
+            (LineBreak::Crlf | LineBreak::Any, Some('\n')) => declared(len + 2),
+            (LineBreak::Cr, _) | (LineBreak::Any, Some(_)) => declared(len + 1),
+            (LineBreak::Any, None) => undecided(declared(len + 1)),
+            (LineBreak::Crlf, None) => undecided(Directive::Absent),
+            _ => Directive::Absent,
+        },
+        Some(_) => Directive::Absent,
+    }
+}
+
+/// Reads a `sep=` directive at the start of the input, if any.
+///
+/// A declared delimiter that cannot be used with the quote is reported, and
+/// the line break ending the directive left to be read.
+fn read_directive<I>(iter: &mut Source<I>, options: &Options) -> Result<(), Error>
+where
+    I: Iterator<Item = char>,
+{
+    iter.sniff = false;
+    if !options.sep_directive() {
+        return Ok(());
+    }
+    let mut read = Vec::new();
+    let mut chars = Vec::new();
+    let (delimiter, len) = loop {
+        let item = iter.next();
+        let end = item.is_none();
+        read.extend(item);
+        chars.extend(item.map(|(_, ch)| ch));
+        match directive(&chars, options.line_break(), end) {
+            Directive::Undecided => {}
+            Directive::Absent => {
+                iter.give_back(&read);
+                return Ok(());
+            }
+            Directive::Declared { delimiter, len } => break (delimiter, len),
+        }
+    };
+    iter.give_back(&read[len..]);
+    let mut declared = options.clone();
+    if declared.set_delimiter(delimiter).is_err() {
+        iter.give_back(&read[SEP.len() + 1..len]);
+        return Err(Error::new(read[SEP.len()].0, ErrorKind::SepDirective));
+    }
+    iter.declared = Some(declared);
+    Ok(())
+}
+
+fn tokenize<I, B>(
+    iter: &mut Source<I>,
+    options: &Options,
+    buf: &mut B,
+) -> Option<Result<Token, Error>>
 where
     I: Iterator<Item = char>,
+    B: Buffer,
 {
-    let (idx, ch): (usize, char) = iter.next()?;
+    if iter.sniff {
+        if let Err(cause) = read_directive(iter, options) {
+            return Some(Err(cause));
+        }
+    }
+    let declared = iter.declared.take();
+    let result = iter
+        .next()
+        .map(|(idx, ch)| next_some(iter, declared.as_ref().unwrap_or(options), idx, ch, buf));
+    iter.declared = declared;
+    result
+}
+
+/// Destination of field characters.
+trait Buffer {
+    fn push(&mut self, ch: char);
+
+    /// A mark to [`Self::truncate`] back to.
+    fn mark(&self) -> usize;
+
+    fn truncate(&mut self, mark: usize);
+}
+
+/// A [`Buffer`] dropping every character.
+struct Discard;
 
-    Some(next_some(iter, options, idx, ch))
+impl Buffer for String {
+    #[inline]
+    fn push(&mut self, ch: char) {
+        self.push(ch);
+    }
+
+    #[inline]
+    fn mark(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn truncate(&mut self, mark: usize) {
+        self.truncate(mark);
+    }
 }
 
-fn next_some<I>(
-    iter: &mut Peekable<Enumerate<I>>,
+impl Buffer for Discard {
+    #[inline]
+    fn push(&mut self, _ch: char) {}
+
+    #[inline]
+    fn mark(&self) -> usize {
+        0
+    }
+
+    #[inline]
+    fn truncate(&mut self, _mark: usize) {}
+}
+
+/// Tokenizes from the character `ch` at `idx` on, pushing field contents to
+/// `buf` and yielding fields as empty.
+fn next_some<I, B>(
+    iter: &mut Source<I>,
     options: &Options,
     idx: usize,
     ch: char,
+    buf: &mut B,
 ) -> Result<Token, Error>
 where
     I: Iterator<Item = char>,
+    B: Buffer,
 {
     #[allow(clippy::if_same_then_else)]
-    let kind = if ch == options.quote() {
-        next_escaped(iter, options)?
+    let kind = if options.is_quote(ch) {
+        next_escaped(iter, options, idx, buf)?
     } else if ch == options.delimiter() {
         Kind::Delimiter
     } else if ch == '\r' {
@@ -40,44 +358,120 @@ where
     } else if ch == '\n' {
         next_line_feed(options, idx, |_| Kind::LineBreak)?
     } else {
-        next_non_escaped(iter, options, ch)?
+        next_non_escaped(iter, options, idx, ch, buf)?
     };
 
     Ok(Token::new(idx, kind))
 }
 
-fn next_escaped<I>(iter: &mut Peekable<Enumerate<I>>, options: &Options) -> Result<Kind, Error>
+/// Tokenizes a quoted field, whose opening quote is at `start`.
+fn next_escaped<I, B>(
+    iter: &mut Source<I>,
+    options: &Options,
+    start: usize,
+    buf: &mut B,
+) -> Result<Kind, Error>
 where
     I: Iterator<Item = char>,
+    B: Buffer,
 {
-    let mut buf = String::new();
+    let unterminated = options.unterminated();
+    if unterminated.is_recover() {
+        iter.record();
+    }
+    let mark = buf.mark();
+    let quoted = next_quoted(iter, options, buf);
+    let recording = iter.take_recording();
+    let (len, closed) = quoted?;
+    if closed {
+        if let Some(item) =
+            iter.next_if(|(_, ch)| *ch != options.delimiter() && !matches!(ch, '\r' | '\n'))
+        {
+            if options.quoting().is_strict() {
+                return Err(Error::new(item.0, ErrorKind::EarlyQuote));
+            }
+            iter.warn(options, item.0, WarningKind::EarlyQuote);
+            iter.give_back(&[item]);
+            next_unquoted(iter, options, buf, len)?;
+        }
+        return Ok(Kind::Field(String::new()));
+    }
+    match unterminated {
+        Unterminated::Strict => Err(Error::new(start, ErrorKind::UnterminatedQuote)),
+        Unterminated::Lenient => Ok(Kind::Field(String::new())),
+        Unterminated::Recover => {
+            let end = recording
+                .iter()
+                .position(|(_, ch)| options.line_break().contains(*ch))
+                .unwrap_or(recording.len());
+            buf.truncate(mark);
+            for (_, ch) in &recording[..end] {
+                buf.push(*ch);
+            }
+            iter.give_back(&recording[end..]);
+            Ok(Kind::Field(String::new()))
+        }
+    }
+}
+
+/// Reads the contents of a quoted field, returning their length, and
+/// whether a closing quote ended them rather than the end of the input.
+fn next_quoted<I, B>(
+    iter: &mut Source<I>,
+    options: &Options,
+    buf: &mut B,
+) -> Result<(usize, bool), Error>
+where
+    I: Iterator<Item = char>,
+    B: Buffer,
+{
+    let max_len = options.max_field_len().unwrap_or(usize::MAX);
+    let max_line_breaks = options.max_quoted_line_breaks().unwrap_or(usize::MAX);
+    let mut len = 0_usize;
+    let mut line_breaks = 0_usize;
     while let Some((idx, ch)) = iter.next() {
         if ch == '\r' {
-            next_carriage_return(iter, options, idx, |ch| buf.push(ch))?;
+            next_carriage_return(iter, options, idx, |ch| {
+                len += 1;
+                buf.push(ch);
+            })?;
+            line_breaks += 1;
         } else if ch == '\n' {
-            next_line_feed(options, idx, |ch| buf.push(ch))?;
-        } else if ch.is_control() {
+            next_line_feed(options, idx, |ch| {
+                len += 1;
+                buf.push(ch);
+            })?;
+            line_breaks += 1;
+        } else if ch.is_control() && ch != options.delimiter() {
             return Err(Error::new(idx, ErrorKind::Control));
         } else if ch == options.quote() {
             if let Some((_, ch)) = iter.next_if(|(_, ch)| *ch == options.quote()) {
+                len += 1;
                 buf.push(ch);
             } else {
-                break;
+                return Ok((len, true));
             }
         } else {
+            len += 1;
             buf.push(ch);
         }
+        if line_breaks > max_line_breaks {
+            return Err(Error::new(
+                idx,
+                ErrorKind::QuotedLineBreaks {
+                    max: max_line_breaks,
+                },
+            ));
+        }
+        if len > max_len {
+            return Err(Error::new(idx, ErrorKind::FieldTooLong { max: max_len }));
+        }
     }
-    if let Some((idx, _)) =
-        iter.next_if(|(_, ch)| *ch != options.delimiter() && !matches!(ch, '\r' | '\n'))
-    {
-        return Err(Error::new(idx, ErrorKind::EarlyQuote));
-    }
-    Ok(Kind::Field(buf))
+    Ok((len, false))
 }
 
 fn next_carriage_return<F, I, R>(
-    iter: &mut Peekable<Enumerate<I>>,
+    iter: &mut Source<I>,
     options: &Options,
     idx: usize,
     mut f: F,
@@ -111,22 +505,47 @@ where
     }
 }
 
-fn next_non_escaped<I>(
-    iter: &mut Peekable<Enumerate<I>>,
+fn next_non_escaped<I, B>(
+    iter: &mut Source<I>,
     options: &Options,
+    start: usize,
     first: char,
+    buf: &mut B,
 ) -> Result<Kind, Error>
 where
     I: Iterator<Item = char>,
+    B: Buffer,
 {
-    let mut buf = first.to_string();
+    if options.max_field_len() == Some(0) {
+        return Err(Error::new(start, ErrorKind::FieldTooLong { max: 0 }));
+    }
+    buf.push(first);
+    next_unquoted(iter, options, buf, 1)?;
+    Ok(Kind::Field(String::new()))
+}
+
+/// Reads the rest of an unquoted field, or the text following a closing
+/// quote when relaxed, into a buffer of `len` characters.
+fn next_unquoted<I, B>(
+    iter: &mut Source<I>,
+    options: &Options,
+    buf: &mut B,
+    mut len: usize,
+) -> Result<(), Error>
+where
+    I: Iterator<Item = char>,
+    B: Buffer,
+{
+    let max_len = options.max_field_len().unwrap_or(usize::MAX);
     while let Some((idx, ch)) =
         iter.next_if(|(_, ch)| *ch != options.delimiter() && !options.line_break().contains(*ch))
     {
-        if ch == options.quote() {
-            return Err(Error::new(idx, ErrorKind::LateQuote));
-        }
-        if !options.allow_controls() && ch.is_control() {
+        if options.is_quote(ch) {
+            if options.quoting().is_strict() {
+                return Err(Error::new(idx, ErrorKind::LateQuote));
+            }
+            iter.warn(options, idx, WarningKind::LateQuote);
+        } else if !options.allow_controls() && ch.is_control() {
             let kind = match ch {
                 '\r' => ErrorKind::CarriageReturn,
                 '\n' => ErrorKind::LineFeed,
@@ -134,7 +553,11 @@ where
             };
             return Err(Error::new(idx, kind));
         }
+        if len == max_len {
+            return Err(Error::new(idx, ErrorKind::FieldTooLong { max: max_len }));
+        }
         buf.push(ch);
+        len += 1;
     }
-    Ok(Kind::Field(buf))
+    Ok(())
 }
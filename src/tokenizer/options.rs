@@ -1,3 +1,5 @@
+use crate::diagnostic::Severity;
+
 mod private_builder {
     pub trait Sealed: Sized {}
 
@@ -18,10 +20,25 @@ pub trait Builder: private_builder::Sealed {
     #[must_use]
     fn with_line_break(self, line_break: LineBreak) -> Self;
 
+    #[must_use]
+    fn with_max_field_len(self, max_field_len: Option<usize>) -> Self;
+
+    #[must_use]
+    fn with_max_quoted_line_breaks(self, max_quoted_line_breaks: Option<usize>) -> Self;
+
     /// # Errors
     ///
     /// See [`Options::set_quote`]
     fn with_quote(self, quote: char) -> Result<Self, Error>;
+
+    #[must_use]
+    fn with_quoting(self, quoting: Quoting) -> Self;
+
+    #[must_use]
+    fn with_sep_directive(self, sep_directive: bool) -> Self;
+
+    #[must_use]
+    fn with_unterminated(self, unterminated: Unterminated) -> Self;
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -29,7 +46,16 @@ pub struct Options {
     allow_controls: bool,
     delimiter: char,
     line_break: LineBreak,
+    /// Maximum number of characters of a field, after unescaping.
+    max_field_len: Option<usize>,
+    /// Maximum number of line breaks within a quoted field.
+    max_quoted_line_breaks: Option<usize>,
     quote: char,
+    quoting: Quoting,
+    /// Whether a `sep=` line at the start of the input declares the
+    /// delimiter.
+    sep_directive: bool,
+    unterminated: Unterminated,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
@@ -41,6 +67,34 @@ pub enum LineBreak {
     Any,
 }
 
+/// How strictly fields must be quoted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Quoting {
+    /// Quotes may only enclose whole fields.
+    #[default]
+    Strict,
+    /// Quotes within unquoted fields are taken literally, and text following
+    /// a closing quote is appended to the field, as spreadsheets do. Each
+    /// relaxation is reported as a [`super::Warning`] if `warn` is set.
+    Relaxed { warn: bool },
+    /// Quotes are ordinary characters, and fields end only at delimiters and
+    /// line breaks.
+    Disabled,
+}
+
+/// What to do with a quoted field still open at the end of the input.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::EnumIs)]
+pub enum Unterminated {
+    /// Report [`super::ErrorKind::UnterminatedQuote`] at the opening quote.
+    #[default]
+    Strict,
+    /// Accept the rest of the input as the field.
+    Lenient,
+    /// Take the rest of the line as the field, as read without unescaping,
+    /// and tokenize the following lines as usual.
+    Recover,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
 #[error("options error: {kind}")]
 pub struct Error {
@@ -50,12 +104,12 @@ pub struct Error {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display)]
 #[non_exhaustive]
 pub enum ErrorKind {
-    #[strum(to_string = "{0} is a control code character")]
-    ControlChar(char),
-    #[strum(to_string = "{0} is already used as delimiter")]
-    UsedByDelimiter(char),
-    #[strum(to_string = "{0} is already used as quote")]
-    UsedByQuote(char),
+    #[strum(to_string = "{ch:?} is a control code character")]
+    ControlChar { ch: char },
+    #[strum(to_string = "{ch:?} is already used as delimiter")]
+    UsedByDelimiter { ch: char },
+    #[strum(to_string = "{ch:?} is already used as quote")]
+    UsedByQuote { ch: char },
 }
 
 impl Error {
@@ -64,6 +118,50 @@ impl Error {
     pub const fn new(kind: ErrorKind) -> Self {
         Self { kind }
     }
+
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl ErrorKind {
+    /// A stable code identifying the kind, see [`crate::diagnostic`].
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::ControlChar { .. } => "CSV201",
+            Self::UsedByDelimiter { .. } => "CSV202",
+            Self::UsedByQuote { .. } => "CSV203",
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// The character that cannot be used.
+    #[inline]
+    #[must_use]
+    pub const fn ch(&self) -> char {
+        match self {
+            Self::ControlChar { ch } | Self::UsedByDelimiter { ch } | Self::UsedByQuote { ch } => {
+                *ch
+            }
+        }
+    }
+}
+
+impl Quoting {
+    /// Whether relaxations are reported as warnings.
+    #[inline]
+    #[must_use]
+    pub const fn warns(self) -> bool {
+        matches!(self, Self::Relaxed { warn: true })
+    }
 }
 
 impl LineBreak {
@@ -75,14 +173,31 @@ impl LineBreak {
             (Self::Crlf | Self::Cr | Self::Any, '\r') | (Self::Crlf | Self::Lf | Self::Any, '\n')
         )
     }
+
+    /// The characters written to end a record, [`Self::Any`] being written
+    /// as [`Self::Crlf`].
+    #[inline]
+    #[must_use]
+    pub const fn terminator(self) -> &'static str {
+        match self {
+            Self::Crlf | Self::Any => "\r\n",
+            Self::Cr => "\r",
+            Self::Lf => "\n",
+        }
+    }
 }
 
 impl Options {
-    pub(super) const DEFAULT: Self = Self {
+    pub(crate) const DEFAULT: Self = Self {
         allow_controls: false,
         delimiter: ',',
         line_break: LineBreak::Crlf,
+        max_field_len: None,
+        max_quoted_line_breaks: None,
         quote: '"',
+        quoting: Quoting::Strict,
+        sep_directive: true,
+        unterminated: Unterminated::Strict,
     };
 
     #[inline]
@@ -110,13 +225,14 @@ impl Options {
     /// # Errors
     ///
     /// Setting the delimiter will fail if the given characters is a
-    /// [`char::is_control`] or is already used as a [`Self::quote`].
+    /// [`char::is_control`] other than a tab, or is already used as a
+    /// [`Self::quote`].
     #[inline]
     pub fn set_delimiter(&mut self, delimiter: char) -> Result<(), Error> {
-        if delimiter.is_control() {
-            Err(Error::new(ErrorKind::ControlChar(delimiter)))
+        if delimiter.is_control() && delimiter != '\t' {
+            Err(Error::new(ErrorKind::ControlChar { ch: delimiter }))
         } else if delimiter == self.quote {
-            return Err(Error::new(ErrorKind::UsedByQuote(delimiter)));
+            Err(Error::new(ErrorKind::UsedByQuote { ch: delimiter }))
         } else {
             self.delimiter = delimiter;
             Ok(())
@@ -139,6 +255,38 @@ impl Options {
         self.line_break = line_break;
     }
 
+    #[inline]
+    #[must_use]
+    pub const fn max_field_len(&self) -> Option<usize> {
+        self.max_field_len
+    }
+
+    #[inline]
+    pub fn max_field_len_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_field_len
+    }
+
+    #[inline]
+    pub fn set_max_field_len(&mut self, max_field_len: Option<usize>) {
+        self.max_field_len = max_field_len;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn max_quoted_line_breaks(&self) -> Option<usize> {
+        self.max_quoted_line_breaks
+    }
+
+    #[inline]
+    pub fn max_quoted_line_breaks_mut(&mut self) -> &mut Option<usize> {
+        &mut self.max_quoted_line_breaks
+    }
+
+    #[inline]
+    pub fn set_max_quoted_line_breaks(&mut self, max_quoted_line_breaks: Option<usize>) {
+        self.max_quoted_line_breaks = max_quoted_line_breaks;
+    }
+
     #[inline]
     #[must_use]
     pub const fn quote(&self) -> char {
@@ -152,14 +300,92 @@ impl Options {
     #[inline]
     pub fn set_quote(&mut self, quote: char) -> Result<(), Error> {
         if quote.is_control() {
-            Err(Error::new(ErrorKind::ControlChar(quote)))
+            Err(Error::new(ErrorKind::ControlChar { ch: quote }))
         } else if quote == self.delimiter {
-            return Err(Error::new(ErrorKind::UsedByDelimiter(quote)));
+            Err(Error::new(ErrorKind::UsedByDelimiter { ch: quote }))
         } else {
             self.quote = quote;
             Ok(())
         }
     }
+
+    /// Sets the delimiter and quote at once, so that they may trade places.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::set_delimiter`] and [`Self::set_quote`]; nothing is set
+    /// then.
+    pub(crate) fn set_delimiter_and_quote(
+        &mut self,
+        delimiter: char,
+        quote: char,
+    ) -> Result<(), Error> {
+        let mut options = self.clone();
+        options.quote = quote;
+        options.set_delimiter(delimiter)?;
+        options.quote = self.quote;
+        options.set_quote(quote)?;
+        *self = options;
+        Ok(())
+    }
+
+    /// Whether a character is the quote, which none is with
+    /// [`Quoting::Disabled`].
+    #[inline]
+    #[must_use]
+    pub const fn is_quote(&self, ch: char) -> bool {
+        ch == self.quote && !self.quoting.is_disabled()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn quoting(&self) -> Quoting {
+        self.quoting
+    }
+
+    #[inline]
+    pub fn quoting_mut(&mut self) -> &mut Quoting {
+        &mut self.quoting
+    }
+
+    #[inline]
+    pub fn set_quoting(&mut self, quoting: Quoting) {
+        self.quoting = quoting;
+    }
+
+    /// Whether a `sep=` line at the start of the input, as Excel writes,
+    /// declares the delimiter instead of being read as a record.
+    #[inline]
+    #[must_use]
+    pub const fn sep_directive(&self) -> bool {
+        self.sep_directive
+    }
+
+    #[inline]
+    pub fn sep_directive_mut(&mut self) -> &mut bool {
+        &mut self.sep_directive
+    }
+
+    #[inline]
+    pub fn set_sep_directive(&mut self, sep_directive: bool) {
+        self.sep_directive = sep_directive;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn unterminated(&self) -> Unterminated {
+        self.unterminated
+    }
+
+    #[inline]
+    pub fn unterminated_mut(&mut self) -> &mut Unterminated {
+        &mut self.unterminated
+    }
+
+    #[inline]
+    pub fn set_unterminated(&mut self, unterminated: Unterminated) {
+        self.unterminated = unterminated;
+    }
 }
 
 impl Builder for &mut Options {
@@ -181,11 +407,41 @@ impl Builder for &mut Options {
         self
     }
 
+    #[inline]
+    fn with_max_field_len(self, max_field_len: Option<usize>) -> Self {
+        self.set_max_field_len(max_field_len);
+        self
+    }
+
+    #[inline]
+    fn with_max_quoted_line_breaks(self, max_quoted_line_breaks: Option<usize>) -> Self {
+        self.set_max_quoted_line_breaks(max_quoted_line_breaks);
+        self
+    }
+
     #[inline]
     fn with_quote(self, quote: char) -> Result<Self, Error> {
         self.set_quote(quote)?;
         Ok(self)
     }
+
+    #[inline]
+    fn with_quoting(self, quoting: Quoting) -> Self {
+        self.set_quoting(quoting);
+        self
+    }
+
+    #[inline]
+    fn with_sep_directive(self, sep_directive: bool) -> Self {
+        self.set_sep_directive(sep_directive);
+        self
+    }
+
+    #[inline]
+    fn with_unterminated(self, unterminated: Unterminated) -> Self {
+        self.set_unterminated(unterminated);
+        self
+    }
 }
 
 impl Builder for Options {
@@ -207,11 +463,41 @@ impl Builder for Options {
         self
     }
 
+    #[inline]
+    fn with_max_field_len(mut self, max_field_len: Option<usize>) -> Self {
+        self.set_max_field_len(max_field_len);
+        self
+    }
+
+    #[inline]
+    fn with_max_quoted_line_breaks(mut self, max_quoted_line_breaks: Option<usize>) -> Self {
+        self.set_max_quoted_line_breaks(max_quoted_line_breaks);
+        self
+    }
+
     #[inline]
     fn with_quote(mut self, quote: char) -> Result<Self, Error> {
         self.set_quote(quote)?;
         Ok(self)
     }
+
+    #[inline]
+    fn with_quoting(mut self, quoting: Quoting) -> Self {
+        self.set_quoting(quoting);
+        self
+    }
+
+    #[inline]
+    fn with_sep_directive(mut self, sep_directive: bool) -> Self {
+        self.set_sep_directive(sep_directive);
+        self
+    }
+
+    #[inline]
+    fn with_unterminated(mut self, unterminated: Unterminated) -> Self {
+        self.set_unterminated(unterminated);
+        self
+    }
 }
 
 impl Default for Options {
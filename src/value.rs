@@ -0,0 +1,100 @@
+//! Type detection of fields, [`Value`] and [`Type`].
+//!
+//! Fields are always parsed as text; detection reads a typed value out of a
+//! field when asked, so that numeric filters, numeric sorting and profiling
+//! agree on what a number is.
+
+/// A field read as the most specific type it fits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'a> {
+    /// Nothing but whitespace.
+    Empty,
+    /// `true` or `false`, in any case.
+    Boolean(bool),
+    Integer(i64),
+    /// A finite number that is not an integer, or too large to be one.
+    Float(f64),
+    Text(&'a str),
+}
+
+/// The type of a [`Value`], or the common type of a column's values.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, strum::Display, strum::EnumIs)]
+#[strum(serialize_all = "lowercase")]
+pub enum Type {
+    #[default]
+    Empty,
+    Boolean,
+    Integer,
+    Float,
+    Text,
+}
+
+impl<'a> Value<'a> {
+    /// Detects the value of a field, ignoring surrounding whitespace.
+    ///
+    /// Numbers must have a digit, so that words such as `inf` or `NaN` are
+    /// text.
+    #[must_use]
+    pub fn detect(field: &'a str) -> Self {
+        let trimmed = field.trim();
+        if trimmed.is_empty() {
+            Self::Empty
+        } else if trimmed.eq_ignore_ascii_case("true") {
+            Self::Boolean(true)
+        } else if trimmed.eq_ignore_ascii_case("false") {
+            Self::Boolean(false)
+        } else if let Ok(integer) = trimmed.parse() {
+            Self::Integer(integer)
+        } else if let Some(float) = trimmed
+            .parse::<f64>()
+            .ok()
+            .filter(|float| float.is_finite() && trimmed.contains(|ch: char| ch.is_ascii_digit()))
+        {
+            Self::Float(float)
+        } else {
+            Self::Text(field)
+        }
+    }
+
+    #[must_use]
+    pub const fn kind(&self) -> Type {
+        match self {
+            Self::Empty => Type::Empty,
+            Self::Boolean(_) => Type::Boolean,
+            Self::Integer(_) => Type::Integer,
+            Self::Float(_) => Type::Float,
+            Self::Text(_) => Type::Text,
+        }
+    }
+
+    /// The value as a number, if it is one.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(integer) => Some(*integer as f64),
+            Self::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+}
+
+impl Type {
+    /// The most specific type of both types' values, empty values fitting
+    /// any type and integers fitting floats.
+    #[must_use]
+    pub const fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, other) | (other, Self::Empty) => other,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => Self::Float,
+            (lhs, rhs) if lhs as u8 == rhs as u8 => lhs,
+            _ => Self::Text,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_numeric(self) -> bool {
+        matches!(self, Self::Integer | Self::Float)
+    }
+}
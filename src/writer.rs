@@ -0,0 +1,335 @@
+//! Record writer, [`Writer`].
+//!
+//! Fields are quoted only when the [`Tokenizer`] would otherwise misread
+//! them, following the same dialect [`Options`]. With quoting disabled,
+//! fields that would need quoting are rejected. Output is UTF-8, optionally
+//! starting with a byte order mark, which Excel needs to detect it, and a
+//! `sep=` directive declaring the delimiter to Excel.
+//!
+//! [`Tokenizer`]: crate::tokenizer::Tokenizer
+
+use std::io;
+
+use crate::tokenizer::{options::LineBreak, Options};
+
+#[derive(Clone, Debug)]
+pub struct Writer<W> {
+    inner: W,
+    options: Options,
+    buf: String,
+    /// The line break ending records.
+    line_break: LineBreak,
+    /// Whether a byte order mark is still to be written.
+    bom: bool,
+    /// Whether a `sep=` directive is still to be written.
+    sep_directive: bool,
+    /// Whether readers take a delimiter at the end of a line as a trailing
+    /// empty field, so that one can be written with quoting disabled.
+    trailing_delimiter: bool,
+    /// Whether nothing but a byte order mark was written, so that a first
+    /// field starting with `sep=` could be read as a directive.
+    start: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("write field {field}: {kind}")]
+    Field { field: usize, kind: ErrorKind },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum::Display)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    #[strum(
+        to_string = "unrepresentable control code character; either remove it, or allow control codes"
+    )]
+    Control,
+    #[strum(
+        to_string = "line break in field differs from the configured line break; either normalize it or enable cross-platform line breaks"
+    )]
+    LineBreak,
+    #[strum(
+        to_string = "field needs quoting, which is disabled; either remove delimiters and line breaks from it, or enable quoting"
+    )]
+    Unquotable,
+}
+
+impl<W> Writer<W>
+where
+    W: io::Write,
+{
+    #[inline]
+    #[must_use]
+    pub const fn new(inner: W, options: Options) -> Self {
+        Self {
+            line_break: options.line_break(),
+            inner,
+            options,
+            buf: String::new(),
+            bom: false,
+            sep_directive: false,
+            trailing_delimiter: false,
+            start: true,
+        }
+    }
+
+    /// Ends records with a line break other than that of the options, such
+    /// as the one an input was read with when the options accept any.
+    #[inline]
+    #[must_use]
+    pub const fn with_line_break(mut self, line_break: LineBreak) -> Self {
+        self.line_break = line_break;
+        self
+    }
+
+    /// The line break ending records, CRLF for [`LineBreak::Any`].
+    #[inline]
+    #[must_use]
+    pub const fn line_break(&self) -> LineBreak {
+        self.line_break
+    }
+
+    /// Starts the output with a byte order mark, unless something was
+    /// written already.
+    #[inline]
+    #[must_use]
+    pub const fn with_bom(mut self, bom: bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Whether a byte order mark is still to be written, before the first
+    /// record or on flushing.
+    #[inline]
+    #[must_use]
+    pub const fn bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Starts the output with a `sep=` directive declaring the delimiter,
+    /// after the byte order mark if any, unless something was written
+    /// already.
+    #[inline]
+    #[must_use]
+    pub const fn with_sep_directive(mut self, sep_directive: bool) -> Self {
+        self.sep_directive = sep_directive;
+        self
+    }
+
+    /// Whether a `sep=` directive is still to be written, before the first
+    /// record or on flushing.
+    #[inline]
+    #[must_use]
+    pub const fn sep_directive(&self) -> bool {
+        self.sep_directive
+    }
+
+    /// Writes an empty last field as a trailing delimiter when quoting is
+    /// disabled, for readers that allow trailing delimiters, rather than
+    /// rejecting it.
+    #[inline]
+    #[must_use]
+    pub const fn with_trailing_delimiter(mut self, trailing_delimiter: bool) -> Self {
+        self.trailing_delimiter = trailing_delimiter;
+        self
+    }
+
+    /// Whether an empty last field is written as a trailing delimiter when
+    /// quoting is disabled.
+    #[inline]
+    #[must_use]
+    pub const fn trailing_delimiter(&self) -> bool {
+        self.trailing_delimiter
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn options(&self) -> &Options {
+        &self.options
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes one record followed by a line break.
+    ///
+    /// # Errors
+    ///
+    /// If a field cannot be represented in the configured dialect, in which
+    /// case nothing is written, or if writing fails.
+    pub fn write_record<I>(&mut self, record: I) -> Result<(), Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.buf.clear();
+        self.push_preamble();
+        let directive = self.start && !self.sep_directive && self.options.sep_directive();
+        let mut record = record.into_iter().enumerate().peekable();
+        while let Some((idx, field)) = record.next() {
+            if idx != 0 {
+                self.buf.push(self.options.delimiter());
+            }
+            let position = Position {
+                first: idx == 0,
+                last: record.peek().is_none(),
+                trailing_delimiter: self.trailing_delimiter,
+                directive: directive && idx == 0,
+            };
+            push_field(&mut self.buf, &self.options, field.as_ref(), position)
+                .map_err(|kind| Error::Field { field: idx, kind })?;
+        }
+        self.buf.push_str(self.line_break.terminator());
+        self.inner.write_all(self.buf.as_bytes())?;
+        self.bom = false;
+        self.sep_directive = false;
+        self.start = false;
+        Ok(())
+    }
+
+    /// Writes the pending byte order mark and `sep=` directive, if any, and
+    /// flushes.
+    ///
+    /// # Errors
+    ///
+    /// See [`io::Write::flush`].
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.buf.clear();
+        self.push_preamble();
+        self.inner.write_all(self.buf.as_bytes())?;
+        if self.sep_directive {
+            self.start = false;
+        }
+        self.bom = false;
+        self.sep_directive = false;
+        self.inner.flush()
+    }
+
+    /// Pushes what is still to be written before the first record.
+    fn push_preamble(&mut self) {
+        if self.bom {
+            self.buf.push('\u{feff}');
+        }
+        if self.sep_directive {
+            self.buf.push_str("sep=");
+            self.buf.push(self.options.delimiter());
+            self.buf.push_str(self.line_break.terminator());
+        }
+    }
+}
+
+/// Where a field is within the output.
+#[derive(Clone, Copy, Debug)]
+struct Position {
+    first: bool,
+    last: bool,
+    /// Whether an empty last field may be written as a trailing delimiter.
+    trailing_delimiter: bool,
+    /// Whether the field starts the output, after any byte order mark, where
+    /// a `sep=` directive is read.
+    directive: bool,
+}
+
+impl Position {
+    /// Whether the field would be misread if pushed as is.
+    fn ambiguous(self, field: &str) -> bool {
+        (field.is_empty() && self.last) || (self.directive && field.starts_with("sep="))
+    }
+}
+
+/// Pushes a field, quoting it if needed.
+///
+/// An empty last field is quoted so that it is not read as a trailing
+/// delimiter, or as an empty line when it is the only field, and so is a
+/// field starting the output with `sep=`, so that it is not read as a
+/// directive. With quoting disabled, such fields are rejected, except an
+/// empty last field that is not the only field if trailing delimiters are
+/// allowed.
+fn push_field(
+    buf: &mut String,
+    options: &Options,
+    field: &str,
+    position: Position,
+) -> Result<(), ErrorKind> {
+    if options.quoting().is_disabled() {
+        return push_unquoted(buf, options, field, position);
+    }
+    let mut quoted = position.ambiguous(field);
+    let mut controls = false;
+    let mut chars = field.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == options.quote() || ch == options.delimiter() {
+            quoted = true;
+        } else if ch == '\r' {
+            let lf = chars.next_if_eq(&'\n').is_some();
+            match (options.line_break(), lf) {
+                (LineBreak::Crlf, true) | (LineBreak::Cr, false) | (LineBreak::Any, _) => {}
+                _ => return Err(ErrorKind::LineBreak),
+            }
+            quoted = true;
+        } else if ch == '\n' {
+            if !options.line_break().contains('\n') {
+                return Err(ErrorKind::LineBreak);
+            }
+            quoted = true;
+        } else if ch.is_control() {
+            controls = true;
+        }
+    }
+
+    if controls && (quoted || !options.allow_controls()) {
+        return Err(ErrorKind::Control);
+    }
+    if quoted {
+        buf.push(options.quote());
+        for ch in field.chars() {
+            if ch == options.quote() {
+                buf.push(ch);
+            }
+            buf.push(ch);
+        }
+        buf.push(options.quote());
+    } else {
+        buf.push_str(field);
+    }
+    Ok(())
+}
+
+/// Pushes a field without quoting, failing if it would be misread.
+fn push_unquoted(
+    buf: &mut String,
+    options: &Options,
+    field: &str,
+    position: Position,
+) -> Result<(), ErrorKind> {
+    let trailing = field.is_empty() && !position.first && position.trailing_delimiter;
+    if position.ambiguous(field) && !trailing {
+        return Err(ErrorKind::Unquotable);
+    }
+    for ch in field.chars() {
+        if ch == options.delimiter() || options.line_break().contains(ch) {
+            return Err(ErrorKind::Unquotable);
+        } else if ch.is_control() && !options.allow_controls() {
+            return Err(ErrorKind::Control);
+        }
+    }
+    buf.push_str(field);
+    Ok(())
+}